@@ -0,0 +1,219 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+use pest::error::ErrorVariant;
+
+use crate::{parser::ast::Span, Rule};
+
+use self::source::{FileId, SourceMap};
+
+pub mod render;
+pub mod source;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Note => write!(f, "note"),
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+impl Label {
+    pub fn primary<S: ToString>(span: Span, message: S) -> Self {
+        Self {
+            span,
+            message: message.to_string(),
+            primary: true,
+        }
+    }
+
+    pub fn secondary<S: ToString>(span: Span, message: S) -> Self {
+        Self {
+            span,
+            message: message.to_string(),
+            primary: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+impl Diagnostic {
+    pub fn new<S: ToString>(severity: Severity, message: S) -> Self {
+        Self {
+            severity,
+            message: message.to_string(),
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+
+    pub fn error<S: ToString>(message: S) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    pub fn warning<S: ToString>(message: S) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+
+    pub fn with_note<S: ToString>(mut self, note: S) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Builds a diagnostic from a grammar error raised while parsing `file`.
+    pub fn from_pest(error: &pest::error::Error<Rule>, file: FileId) -> Self {
+        let (start, end) = match error.location {
+            pest::error::InputLocation::Pos(pos) => (pos, pos),
+            pest::error::InputLocation::Span(span) => span,
+        };
+        let span = Span {
+            content: String::new(),
+            start,
+            end,
+            file,
+        };
+
+        let message = match &error.variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => match (one_of(negatives), one_of(positives)) {
+                (None, None) => "unknown parsing error".to_owned(),
+                (None, Some(positives)) => format!("expected {}", positives),
+                (Some(negatives), None) => format!("unexpected {}", negatives),
+                (Some(negatives), Some(positives)) => {
+                    format!("unexpected {}; expected {}", negatives, positives)
+                }
+            },
+            ErrorVariant::CustomError { message } => message.clone(),
+        };
+
+        Self::error("syntax error").with_label(Label::primary(span, message))
+    }
+
+    pub fn render(&self, sources: &SourceMap) -> String {
+        render::render(self, sources)
+    }
+}
+
+/// `rules` by their readable names as `a, b, or c`, or `None` if empty.
+fn one_of(rules: &[Rule]) -> Option<String> {
+    let names = rules.iter().map(rule_name).unique().collect::<Vec<_>>();
+    match names.as_slice() {
+        [] => None,
+        [name] => Some(name.clone()),
+        [first, second] => Some(format!("{} or {}", first, second)),
+        [rest @ .., last] => Some(format!("{}, or {}", rest.join(", "), last)),
+    }
+}
+
+/// How syntax errors name `rule`.
+fn rule_name(rule: &Rule) -> String {
+    let name = match rule {
+        Rule::ident => "identifier",
+        Rule::strict_keyword | Rule::contextual_keyword => "keyword",
+        Rule::literal | Rule::number | Rule::bool => "literal",
+        Rule::string => "string",
+        Rule::chr => "character",
+        Rule::expr | Rule::infix_expr => "expression",
+        Rule::stmt | Rule::stmts => "statement",
+        Rule::parenthesized_expr | Rule::call_params => "`(`",
+        Rule::function => "`fn`",
+        Rule::type_definition => "`type`",
+        Rule::function_parameters => "parameters",
+        Rule::function_parameter => "parameter",
+        Rule::type_expr => "type",
+        Rule::type_args | Rule::type_params => "`<`",
+        Rule::array_suffix | Rule::empty_pattern => "`[]`",
+        Rule::generator_suffix => "`*`",
+        Rule::variant => "variant",
+        Rule::field_definition => "field",
+        Rule::else_if | Rule::else_branch => "`else`",
+        Rule::pipe | Rule::leading_pipe => "`:`",
+        Rule::nop => "`;`",
+        Rule::add | Rule::plus => "`+`",
+        Rule::subtract | Rule::minus => "`-`",
+        Rule::multiply => "`*`",
+        Rule::divide => "`/`",
+        Rule::pow => "`**`",
+        Rule::r#mod => "`%`",
+        Rule::and => "`&&`",
+        Rule::or => "`||`",
+        Rule::bit_and => "`&`",
+        Rule::bit_or => "`|`",
+        Rule::bit_xor => "`^`",
+        Rule::eq => "`==`",
+        Rule::neq => "`!=`",
+        Rule::greater => "`>`",
+        Rule::lesser => "`<`",
+        Rule::greater_eq => "`>=`",
+        Rule::lesser_eq => "`<=`",
+        Rule::assign => "`=`",
+        Rule::comma => "`,`",
+        Rule::not => "`!`",
+        Rule::bit_not => "`~`",
+        Rule::inc | Rule::post_inc => "`++`",
+        Rule::dec | Rule::post_dec => "`--`",
+        Rule::field_access => "`.`",
+        Rule::range | Rule::range_to | Rule::range_from => "`..`",
+        Rule::range_inclusive | Rule::range_to_inclusive => "`..=`",
+        Rule::EOI => "end of input",
+        rule => return format!("{:?}", rule).replace('_', " "),
+    };
+    name.to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::session::Session;
+
+    /// The message of the syntax error in `source`.
+    fn syntax_error(source: &str) -> String {
+        let output = Session::new().parse_str_partial(source);
+        let diagnostic = output.diagnostics.first().expect("source is rejected");
+        assert_eq!(diagnostic.message, "syntax error");
+        diagnostic.labels[0].message.clone()
+    }
+
+    #[test]
+    fn syntax_errors_name_what_was_expected() {
+        assert_eq!(syntax_error("fn\n"), "expected identifier");
+        assert_eq!(
+            syntax_error("fn f (x int => 1\n"),
+            "expected `[]`, `*`, or `<`"
+        );
+        assert_eq!(
+            syntax_error("fn f => 1 +\n"),
+            "expected identifier, `+`, `-`, literal, `++`, `--`, `..`, `..=`, `!`, `~`, `:`, or `(`"
+        );
+    }
+}
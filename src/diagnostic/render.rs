@@ -0,0 +1,268 @@
+use std::fmt::Write;
+
+use super::{source::SourceMap, Diagnostic, Label};
+
+/// Renders `diagnostic` in the style of rustc:
+///
+/// ```text
+/// error: Duplicate type definition `Point`
+///  --> samples/types.fn:4:6
+///   |
+/// 4 | type Point {
+///   |      ^^^^^ redefined here
+///   |
+/// ```
+pub fn render(diagnostic: &Diagnostic, sources: &SourceMap) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}: {}", diagnostic.severity, diagnostic.message);
+
+    let gutter = diagnostic
+        .labels
+        .iter()
+        .filter_map(|label| sources.location(&label.span))
+        .map(|(start, _)| start.line.to_string().len())
+        .max()
+        .unwrap_or(0);
+
+    // Labels in the primary label's file continue its snippet; any other
+    // file gets a `:::` header of its own.
+    let primary_file = diagnostic
+        .labels
+        .iter()
+        .find(|label| label.primary)
+        .or(diagnostic.labels.first())
+        .map(|label| label.span.file);
+    for (i, label) in diagnostic.labels.iter().enumerate() {
+        let header = if i == 0 {
+            Some("-->")
+        } else if Some(label.span.file) != primary_file {
+            Some(":::")
+        } else {
+            None
+        };
+        render_label(&mut out, label, sources, gutter, header);
+    }
+
+    for note in &diagnostic.notes {
+        let _ = writeln!(out, "{:gutter$} = note: {}", "", note);
+    }
+
+    out
+}
+
+fn render_label(
+    out: &mut String,
+    label: &Label,
+    sources: &SourceMap,
+    gutter: usize,
+    header: Option<&str>,
+) {
+    let Some(file) = sources.get(label.span.file) else {
+        let _ = writeln!(out, "{:gutter$} = {}", "", label.message);
+        return;
+    };
+
+    let start = file.line_col(label.span.start);
+    let end = file.line_col(label.span.end);
+
+    if let Some(arrow) = header {
+        let _ = writeln!(out, "{:gutter$}{} {}:{}", "", arrow, file.name, start);
+        let _ = writeln!(out, "{:gutter$} |", "");
+    }
+
+    let text = file.line(start.line - 1);
+    let _ = writeln!(out, "{:>gutter$} | {}", start.line, text.replace('\t', TAB));
+
+    // Spans running past the end of their first line are underlined to the
+    // end of that line.
+    let pad = columns(text, start.column - 1);
+    let width = if end.line == start.line {
+        columns(text, end.column - 1).saturating_sub(pad)
+    } else {
+        (columns(text, usize::MAX) + 1).saturating_sub(pad)
+    }
+    .max(1);
+    let marker = if label.primary { "^" } else { "-" };
+    let _ = writeln!(
+        out,
+        "{:gutter$} | {:pad$}{} {}",
+        "",
+        "",
+        marker.repeat(width),
+        label.message,
+    );
+    let _ = writeln!(out, "{:gutter$} |", "");
+}
+
+/// What a tab is shown as, so markers line up whatever the terminal's tab width.
+const TAB: &str = "    ";
+
+/// How many columns the first `chars` characters of `text` take up on screen.
+fn columns(text: &str, chars: usize) -> usize {
+    text.chars()
+        .take(chars)
+        .map(|c| if c == '\t' { TAB.len() } else { 1 })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        diagnostic::{
+            source::{FileId, SourceMap},
+            Diagnostic, Label,
+        },
+        parser::ast::Span,
+    };
+
+    /// A span over `start..end` of `file` in `sources`.
+    fn span(sources: &SourceMap, file: FileId, start: usize, end: usize) -> Span {
+        let source = &sources.get(file).expect("file is registered").source;
+        Span {
+            content: source[start..end].to_owned(),
+            start,
+            end,
+            file,
+        }
+    }
+
+    #[test]
+    fn labels_underline_their_span() {
+        let mut sources = SourceMap::new();
+        let file = sources.add("a.fn", "fn main => foo(1)\n");
+        let diagnostic = Diagnostic::error("Unknown function `foo`")
+            .with_label(Label::primary(span(&sources, file, 11, 14), "not found"))
+            .with_note("functions are defined with `fn`");
+        assert_eq!(
+            diagnostic.render(&sources),
+            "error: Unknown function `foo`
+ --> a.fn:1:12
+  |
+1 | fn main => foo(1)
+  |            ^^^ not found
+  |
+  = note: functions are defined with `fn`
+"
+        );
+    }
+
+    #[test]
+    fn spans_over_several_lines_are_underlined_to_the_end_of_the_first() {
+        let mut sources = SourceMap::new();
+        let file = sources.add("a.fn", "fn main => {\n    1\n}\n");
+        let diagnostic = Diagnostic::error("block")
+            .with_label(Label::primary(span(&sources, file, 11, 21), "here"));
+        assert_eq!(
+            diagnostic.render(&sources),
+            "error: block
+ --> a.fn:1:12
+  |
+1 | fn main => {
+  |            ^^ here
+  |
+"
+        );
+    }
+
+    #[test]
+    fn spans_at_the_end_of_the_file_point_past_the_last_line() {
+        let mut sources = SourceMap::new();
+        let file = sources.add("a.fn", "fn main => (");
+        let diagnostic = Diagnostic::error("syntax error")
+            .with_label(Label::primary(span(&sources, file, 12, 12), "expected `)`"));
+        assert_eq!(
+            diagnostic.render(&sources),
+            "error: syntax error
+ --> a.fn:1:13
+  |
+1 | fn main => (
+  |             ^ expected `)`
+  |
+"
+        );
+    }
+
+    #[test]
+    fn markers_line_up_after_tabs_and_multibyte_characters() {
+        let mut sources = SourceMap::new();
+        let file = sources.add("a.fn", "fn main => {\n\tlet é = \"ü\" + x\n}\n");
+        let x = sources
+            .get(file)
+            .expect("file is registered")
+            .source
+            .rfind('x')
+            .unwrap();
+        let diagnostic = Diagnostic::error("Unknown variable `x`")
+            .with_label(Label::primary(span(&sources, file, x, x + 1), "not found"));
+        assert_eq!(
+            diagnostic.render(&sources),
+            "error: Unknown variable `x`
+ --> a.fn:2:16
+  |
+2 |     let é = \"ü\" + x
+  |                   ^ not found
+  |
+"
+        );
+    }
+
+    #[test]
+    fn secondary_labels_share_the_primary_file_header() {
+        let mut sources = SourceMap::new();
+        let source = "type P {\n    x int\n}\n\ntype P {\n    y int\n}\n";
+        let file = sources.add("a.fn", source);
+        let diagnostic = Diagnostic::error("Duplicate type definition `P`")
+            .with_label(Label::primary(
+                span(&sources, file, 27, 28),
+                "redefined here",
+            ))
+            .with_label(Label::secondary(
+                span(&sources, file, 5, 6),
+                "previously defined here",
+            ));
+        assert_eq!(
+            diagnostic.render(&sources),
+            "error: Duplicate type definition `P`
+ --> a.fn:5:6
+  |
+5 | type P {
+  |      ^ redefined here
+  |
+1 | type P {
+  |      - previously defined here
+  |
+"
+        );
+    }
+
+    #[test]
+    fn labels_in_other_files_get_their_own_header() {
+        let mut sources = SourceMap::new();
+        let main = sources.add("main.fn", "fn main => f(1)\n");
+        let lib = sources.add("lib.fn", "fn f (x str) => x\n");
+        let diagnostic = Diagnostic::error("Mismatched types")
+            .with_label(Label::primary(
+                span(&sources, main, 13, 14),
+                "expected `str`",
+            ))
+            .with_label(Label::secondary(
+                span(&sources, lib, 6, 11),
+                "parameter declared here",
+            ));
+        assert_eq!(
+            diagnostic.render(&sources),
+            "error: Mismatched types
+ --> main.fn:1:14
+  |
+1 | fn main => f(1)
+  |              ^ expected `str`
+  |
+ ::: lib.fn:1:7
+  |
+1 | fn f (x str) => x
+  |       ----- parameter declared here
+  |
+"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+use crate::parser::ast::Span;
+
+/// Identifies a file registered with a [`SourceMap`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct FileId(pub usize);
+
+/// A 1-based line and column, with the column counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: usize,
+    pub column: usize,
+}
+impl Display for LineCol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    line_starts: Vec<usize>,
+}
+impl SourceFile {
+    pub fn new(name: String, source: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            name,
+            source,
+            line_starts,
+        }
+    }
+
+    /// The 0-based index of the line containing `offset`.
+    pub fn line_index(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let offset = offset.min(self.source.len());
+        let line = self.line_index(offset);
+        let start = self.line_starts[line];
+        LineCol {
+            line: line + 1,
+            column: self.source[start..offset].chars().count() + 1,
+        }
+    }
+
+    /// The text of the 0-based line `line`, without its line terminator.
+    pub fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .copied()
+            .unwrap_or(self.source.len());
+        self.source[start..end].trim_end_matches(['\n', '\r'])
+    }
+}
+
+/// Every file seen during a session, addressable by [`FileId`].
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<N: ToString, S: ToString>(&mut self, name: N, source: S) -> FileId {
+        self.files
+            .push(SourceFile::new(name.to_string(), source.to_string()));
+        FileId(self.files.len() - 1)
+    }
+
+    pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.0)
+    }
+
+    pub fn name(&self, id: FileId) -> &str {
+        self.get(id)
+            .map(|file| file.name.as_str())
+            .unwrap_or("<unknown>")
+    }
+
+    /// The start and end positions of `span`.
+    pub fn location(&self, span: &Span) -> Option<(LineCol, LineCol)> {
+        let file = self.get(span.file)?;
+        Some((file.line_col(span.start), file.line_col(span.end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineCol, SourceFile};
+
+    fn file(source: &str) -> SourceFile {
+        SourceFile::new("a.fn".to_owned(), source.to_owned())
+    }
+
+    fn at(line: usize, column: usize) -> LineCol {
+        LineCol { line, column }
+    }
+
+    #[test]
+    fn offsets_map_to_lines_and_columns() {
+        let file = file("fn a => 1\nfn b => 2\n");
+        assert_eq!(file.line_col(0), at(1, 1));
+        assert_eq!(file.line_col(9), at(1, 10));
+        assert_eq!(file.line_col(10), at(2, 1));
+        assert_eq!(file.line_col(13), at(2, 4));
+    }
+
+    #[test]
+    fn the_end_of_the_file_is_past_its_last_line() {
+        let file = file("fn a => 1\n");
+        assert_eq!(file.line_col(10), at(2, 1));
+        assert_eq!(file.line_col(100), at(2, 1));
+        assert_eq!(file.line(1), "");
+
+        let unterminated = SourceFile::new("b.fn".to_owned(), "fn a => 1".to_owned());
+        assert_eq!(unterminated.line_col(9), at(1, 10));
+        assert_eq!(unterminated.line(0), "fn a => 1");
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let file = file("\"é\tü\" + x\n");
+        assert_eq!(file.line_col("\"é\tü\"".len()), at(1, 6));
+        assert_eq!(file.line_col(file.source.rfind('x').unwrap()), at(1, 9));
+    }
+
+    #[test]
+    fn lines_drop_their_terminator() {
+        let file = file("fn a => 1\r\nfn b => 2\n\nfn c => 3");
+        assert_eq!(file.line(0), "fn a => 1");
+        assert_eq!(file.line(1), "fn b => 2");
+        assert_eq!(file.line(2), "");
+        assert_eq!(file.line(3), "fn c => 3");
+    }
+}
@@ -0,0 +1,243 @@
+//! Machine-readable renderings of a parsed [`Module`].
+
+use std::iter;
+
+use itertools::Itertools;
+
+use crate::{
+    format::{generics, literal_source},
+    parser::ast::{
+        expr::{atom::Atom, DoBranch, Expression, StringPart},
+        function::Function,
+        function_parameter::FunctionParameter,
+        module::{Item, Module},
+        statement::Statement,
+        type_definition::TypeDefinition,
+    },
+};
+
+pub fn json(module: &Module) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(module)
+}
+
+/// Renders `module` as one s-expression per top-level item.
+pub fn sexpr(module: &Module) -> String {
+    let items = module
+        .items()
+        .into_iter()
+        .map(|item| match item {
+            Item::Function(f) => function(f),
+            Item::Type(ty) => type_definition(ty),
+            Item::Error(span) => format!("(error {:?})", span.content),
+        })
+        .join("\n  ");
+    format!("(module\n  {})\n", items)
+}
+
+fn type_definition(ty: &TypeDefinition) -> String {
+    let variants = ty.variants.iter().map(|variant| {
+        list(
+            ["variant".to_owned(), variant.name.to_string()]
+                .into_iter()
+                .chain(variant.fields.iter().map(ToString::to_string)),
+        )
+    });
+    let members = variants
+        .chain(ty.fields.iter().map(|field| expression(&field.definition)))
+        .chain(ty.methods.iter().map(function));
+    list(
+        [
+            "type".to_owned(),
+            format!("{}{}", ty.name, generics(&ty.generics)),
+        ]
+        .into_iter()
+        .chain(members),
+    )
+}
+
+fn function(function: &Function) -> String {
+    let params = list(function.params.iter().map(parameter));
+    let return_type = function.return_type.as_ref().map(ToString::to_string);
+    let body = function.body.iter().map(statement);
+    list(
+        [
+            "fn".to_owned(),
+            format!("{}{}", function.func_name, generics(&function.generics)),
+            params,
+        ]
+        .into_iter()
+        .chain(return_type)
+        .chain(body),
+    )
+}
+
+fn parameter(param: &FunctionParameter) -> String {
+    match param {
+        FunctionParameter::NamedAndTyped { name, ty } => format!("({} {})", name, ty),
+        FunctionParameter::NamedDynamic { name } => format!("({})", name),
+        FunctionParameter::Anonymous { ty } => format!("(_ {})", ty),
+        FunctionParameter::Literal { literal, .. } => literal_source(literal),
+        FunctionParameter::Wildcard { .. } => "_".to_owned(),
+        FunctionParameter::Cons { head, tail, ty, .. } => list(
+            ["cons".to_owned(), head.to_string(), tail.to_string()]
+                .into_iter()
+                .chain(ty.as_ref().map(ToString::to_string)),
+        ),
+        FunctionParameter::Empty { ty, .. } => list(
+            ["empty".to_owned()]
+                .into_iter()
+                .chain(ty.as_ref().map(ToString::to_string)),
+        ),
+        FunctionParameter::Variant { name, fields, .. } => list(
+            ["variant".to_owned(), name.to_string()]
+                .into_iter()
+                .chain(fields.iter().map(parameter)),
+        ),
+    }
+}
+
+fn statement(stmt: &Statement) -> String {
+    match stmt {
+        Statement::Nop => "(nop)".to_owned(),
+        Statement::Expression(expr) => expression(expr),
+        Statement::Assignment { ident, typ, value } => list(
+            ["let".to_owned(), ident.to_string()]
+                .into_iter()
+                .chain(typ.as_ref().map(ToString::to_string))
+                .chain([expression(value)]),
+        ),
+        Statement::Declaration { ident, typ } => format!("(let {} {})", ident, typ),
+        Statement::If {
+            condition,
+            then_branch,
+            else_ifs,
+            else_branch,
+            ..
+        } => {
+            let block = |head: &str, body: &[Statement]| {
+                list(iter::once(head.to_owned()).chain(body.iter().map(statement)))
+            };
+            let else_ifs = else_ifs.iter().map(|else_if| {
+                list([
+                    "elif".to_owned(),
+                    expression(&else_if.condition),
+                    block("then", &else_if.body),
+                ])
+            });
+            list(
+                [
+                    "if".to_owned(),
+                    expression(condition),
+                    block("then", then_branch),
+                ]
+                .into_iter()
+                .chain(else_ifs)
+                .chain(else_branch.as_deref().map(|body| block("else", body))),
+            )
+        }
+        Statement::Error { span } => format!("(error {:?})", span.content),
+    }
+}
+
+fn expression(expr: &Expression) -> String {
+    match expr {
+        Expression::Atom(Atom::Ident(ident)) => ident.to_string(),
+        Expression::Atom(Atom::Literal { literal, .. }) => literal_source(literal),
+        Expression::BinaryOperation {
+            lhs, operator, rhs, ..
+        } => {
+            format!("({} {} {})", operator, expression(lhs), expression(rhs))
+        }
+        Expression::PrefixOperation { operator, rhs, .. } => {
+            format!("({} {})", operator, expression(rhs))
+        }
+        Expression::PostfixOperation { lhs, operator, .. } => {
+            format!("(post{} {})", operator, expression(lhs))
+        }
+        Expression::Call { lhs, params, .. } => {
+            let args = params
+                .iter()
+                .flat_map(|params| params.arguments())
+                .map(expression);
+            list(["call".to_owned(), expression(lhs)].into_iter().chain(args))
+        }
+        Expression::Assignment {
+            declare,
+            name,
+            typ,
+            value,
+            ..
+        } => {
+            let head = if *declare { "let" } else { "set" };
+            list(
+                [head.to_owned(), name.to_string()]
+                    .into_iter()
+                    .chain(typ.as_ref().map(ToString::to_string))
+                    .chain(value.as_deref().map(expression)),
+            )
+        }
+        Expression::Do {
+            branches,
+            default_branch,
+            ..
+        } => {
+            let default = format!("(_ {})", expression(&default_branch.behavior));
+            list(
+                ["do".to_owned()]
+                    .into_iter()
+                    .chain(branches.iter().map(do_branch))
+                    .chain([default]),
+            )
+        }
+        Expression::Match {
+            scrutinee,
+            branches,
+            ..
+        } => list(
+            ["match".to_owned(), expression(scrutinee)]
+                .into_iter()
+                .chain(branches.iter().map(|branch| {
+                    format!(
+                        "({} {})",
+                        parameter(&branch.pattern),
+                        expression(&branch.behavior)
+                    )
+                })),
+        ),
+        Expression::Interpolation { parts, .. } => list(iter::once("str".to_owned()).chain(
+            parts.iter().map(|part| match part {
+                StringPart::Text(text) => format!("{:?}", text),
+                StringPart::Expression(expr) => expression(expr),
+            }),
+        )),
+        Expression::Pipe {
+            receiver,
+            name,
+            params,
+            ..
+        } => {
+            let args = params
+                .iter()
+                .flat_map(|params| params.arguments())
+                .map(expression);
+            list(
+                ["pipe".to_owned(), name.to_string()]
+                    .into_iter()
+                    .chain(receiver.as_deref().map(expression))
+                    .chain(args),
+            )
+        }
+    }
+}
+
+fn do_branch(branch: &DoBranch) -> String {
+    format!(
+        "({} {})",
+        expression(&branch.condition),
+        expression(&branch.behavior)
+    )
+}
+
+fn list<I: IntoIterator<Item = String>>(items: I) -> String {
+    format!("({})", items.into_iter().join(" "))
+}
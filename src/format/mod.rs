@@ -0,0 +1,762 @@
+//! Pretty-printer that renders a [`Module`] back to canonical source text.
+
+use itertools::Itertools;
+
+use crate::parser::ast::{
+    expr::{
+        atom::Atom,
+        literal::{Literal, NumberSuffix, Radix},
+        operator::Operator,
+        DoBranch, Expression, StringPart,
+    },
+    function::Function,
+    function_parameter::FunctionParameter,
+    ident::Ident,
+    module::{Comment, Item, Module},
+    statement::Statement,
+    type_definition::{TypeDefinition, VariantDefinition},
+    Span,
+};
+
+const INDENT: &str = "    ";
+
+/// Binding power of operators, mirroring the table in
+/// [`pratt`](crate::parser::ast::expr::pratt). Higher binds tighter.
+mod precedence {
+    pub const LOWEST: u8 = 0;
+    pub const COMMA: u8 = 1;
+    pub const ASSIGN: u8 = 2;
+    pub const RANGE: u8 = 3;
+    pub const OR: u8 = 4;
+    pub const AND: u8 = 5;
+    pub const BIT_OR: u8 = 6;
+    pub const BIT_XOR: u8 = 7;
+    pub const BIT_AND: u8 = 8;
+    pub const EQUALITY: u8 = 9;
+    pub const COMPARISON: u8 = 10;
+    pub const ADDITIVE: u8 = 11;
+    pub const MULTIPLICATIVE: u8 = 12;
+    pub const PREFIX: u8 = 13;
+    pub const POW: u8 = 14;
+    pub const POSTFIX: u8 = 15;
+    pub const ATOM: u8 = 16;
+}
+
+fn infix_precedence(operator: &Operator) -> (u8, bool) {
+    use precedence::*;
+    // (precedence, right-associative)
+    match operator {
+        Operator::Comma => (COMMA, false),
+        Operator::Assign => (ASSIGN, true),
+        Operator::Range | Operator::RangeInclusive => (RANGE, false),
+        Operator::Or => (OR, false),
+        Operator::And => (AND, false),
+        Operator::BitOr => (BIT_OR, false),
+        Operator::BitXor => (BIT_XOR, false),
+        Operator::BitAnd => (BIT_AND, false),
+        Operator::Eq | Operator::Neq => (EQUALITY, false),
+        Operator::Greater | Operator::Lesser | Operator::GreaterEq | Operator::LesserEq => {
+            (COMPARISON, false)
+        }
+        Operator::Add | Operator::Subtract => (ADDITIVE, false),
+        Operator::Multiply | Operator::Divide | Operator::Mod => (MULTIPLICATIVE, false),
+        Operator::Pow => (POW, true),
+        Operator::FieldAccess => (POSTFIX, false),
+        Operator::Inc | Operator::Dec | Operator::Not | Operator::BitNot => (PREFIX, false),
+    }
+}
+
+/// Open ranges (`..b`, `a..`) bind as loosely as infix ranges; every other
+/// prefix or postfix operator binds at `default`.
+fn affix_precedence(operator: &Operator, default: u8) -> u8 {
+    match operator {
+        Operator::Range | Operator::RangeInclusive => precedence::RANGE,
+        _ => default,
+    }
+}
+
+/// The loosest operand a prefix or postfix operator can print unparenthesized.
+fn affix_operand_precedence(operator: &Operator, default: u8) -> u8 {
+    match operator {
+        Operator::Range | Operator::RangeInclusive => precedence::RANGE + 1,
+        _ => default,
+    }
+}
+
+/// Formats `module` as source text.
+pub fn format_module(module: &Module) -> String {
+    let mut printer = Printer::default();
+    printer.module(module);
+    printer.finish()
+}
+
+#[derive(Default)]
+pub struct Printer {
+    out: String,
+    indent: usize,
+    /// The module's comments not yet written, last first.
+    comments: Vec<Comment>,
+}
+impl Printer {
+    pub fn finish(self) -> String {
+        self.out
+    }
+
+    fn write<S: AsRef<str>>(&mut self, text: S) {
+        self.out.push_str(text.as_ref());
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    /// Writes the comments that start before `offset` in the source. A
+    /// comment that followed code on its line stays at the end of the last
+    /// line written; any other gets a line of its own, and keeps a blank line
+    /// after it at the top level.
+    ///
+    /// Comments are only placed between items, fields, methods and
+    /// statements, so one inside an expression moves to the next such gap.
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.pop() {
+            if comment.span.start >= offset {
+                self.comments.push(comment);
+                return;
+            }
+            let text = comment.span.content.trim_end();
+            if comment.trailing && !self.out.is_empty() {
+                let rest = self.out.split_off(self.out.trim_end().len());
+                self.write(format!(" {}", text));
+                self.write(rest);
+            } else if self.out.is_empty() || self.out.ends_with('\n') {
+                self.write(format!("{}\n", text));
+                if comment.detached {
+                    self.write("\n");
+                }
+            } else {
+                self.newline();
+                self.write(text);
+            }
+        }
+    }
+
+    /// Top-level items are printed in source order, separated by blank lines.
+    /// Consecutive clauses of one function are kept together.
+    pub fn module(&mut self, module: &Module) {
+        self.comments = module.comments.iter().rev().cloned().collect();
+        let mut previous: Option<&Function> = None;
+        for (i, item) in module.items().into_iter().enumerate() {
+            let same_function = match (&item, previous) {
+                (Item::Function(function), Some(previous)) => {
+                    function.func_name.to_string() == previous.func_name.to_string()
+                }
+                _ => false,
+            };
+            if i > 0 && !same_function {
+                self.write("\n");
+            }
+            self.comments_before(item.span().start);
+            previous = match item {
+                Item::Function(function) => Some(function),
+                _ => None,
+            };
+            match item {
+                Item::Function(function) => self.function(function),
+                Item::Type(ty) => self.type_definition(ty),
+                Item::Error(span) => self.write(span.content.trim_end()),
+            }
+            self.write("\n");
+        }
+
+        if self.comments.last().is_some_and(|c| !c.trailing) && !self.out.is_empty() {
+            self.write("\n");
+        }
+        self.comments_before(usize::MAX);
+        if self.out.ends_with("\n\n") {
+            self.out.truncate(self.out.trim_end().len() + 1);
+        }
+    }
+
+    /// Writes each line of `docs` as a `///` comment on its own line.
+    fn docs(&mut self, docs: &[String]) {
+        for line in docs {
+            match line.is_empty() {
+                true => self.write("///"),
+                false => self.write(format!("/// {}", line)),
+            }
+            self.newline();
+        }
+    }
+
+    pub fn type_definition(&mut self, ty: &TypeDefinition) {
+        self.docs(&ty.docs);
+        if ty.is_enum() {
+            let variants = ty.variants.iter().map(variant).join(" | ");
+            self.write(format!(
+                "type {}{} = {}",
+                ty.name,
+                generics(&ty.generics),
+                variants
+            ));
+            return;
+        }
+        self.write(format!("type {}{} {{", ty.name, generics(&ty.generics)));
+        self.indent += 1;
+        for field in &ty.fields {
+            self.comments_before(field.definition.span().start);
+            self.newline();
+            self.docs(&field.docs);
+            self.expression(&field.definition, precedence::LOWEST);
+        }
+        for method in &ty.methods {
+            self.comments_before(method.span.start);
+            self.newline();
+            self.function(method);
+        }
+        self.comments_before(ty.span.end);
+        self.indent -= 1;
+        self.newline();
+        self.write("}");
+    }
+
+    pub fn function(&mut self, function: &Function) {
+        self.docs(&function.docs);
+        self.write(format!(
+            "fn {}{}",
+            function.func_name,
+            generics(&function.generics)
+        ));
+        if !function.params.is_empty() {
+            let params = function.params.iter().map(parameter).join(", ");
+            self.write(format!(" ({})", params));
+        }
+        if let Some(return_type) = &function.return_type {
+            self.write(format!(" {}", return_type));
+        }
+        self.write(" => ");
+        self.body(&function.body, function.span.end);
+    }
+
+    /// A single expression body is kept inline, anything else becomes a block.
+    /// So are bodies holding a comment, which would otherwise move below
+    /// the function.
+    fn body(&mut self, body: &[Statement], end: usize) {
+        let commented = self
+            .comments
+            .iter()
+            .rev()
+            .take_while(|c| c.span.start < end)
+            .any(|c| !c.trailing);
+        if let [Statement::Expression(expr)] = body {
+            if !matches!(**expr, Expression::Assignment { .. }) && !commented {
+                self.expression(expr, precedence::LOWEST);
+                return;
+            }
+        }
+        self.block(body, end);
+    }
+
+    /// Writes `body` in braces, along with the comments before `end`.
+    fn block(&mut self, body: &[Statement], end: usize) {
+        self.write("{");
+        self.indent += 1;
+        for stmt in body {
+            if let Some(start) = statement_start(stmt) {
+                self.comments_before(start);
+            }
+            self.newline();
+            self.statement(stmt);
+        }
+        self.comments_before(end);
+        self.indent -= 1;
+        self.newline();
+        self.write("}");
+    }
+
+    pub fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Nop => self.write(";"),
+            Statement::Expression(expr) => self.expression(expr, precedence::LOWEST),
+            Statement::Assignment { ident, typ, value } => {
+                self.write(format!("let {}", ident));
+                if let Some(typ) = typ {
+                    self.write(format!(" {}", typ));
+                }
+                self.write(" = ");
+                self.expression(value, precedence::ASSIGN);
+            }
+            Statement::Declaration { ident, typ } => self.write(format!("let {} {}", ident, typ)),
+            Statement::If {
+                condition,
+                then_branch,
+                else_ifs,
+                else_branch,
+                else_span,
+                span,
+            } => {
+                // A branch's comments end where the next branch starts, or
+                // with the whole `if`.
+                let mut ends = else_ifs
+                    .iter()
+                    .map(|else_if| else_if.condition.span().start)
+                    .chain([else_span.as_ref().map_or(span.end, |s| s.start)]);
+
+                self.write("if (");
+                self.expression(condition, precedence::LOWEST);
+                self.write(") ");
+                self.block(then_branch, ends.next().unwrap_or(span.end));
+                for else_if in else_ifs {
+                    self.write(" else if (");
+                    self.expression(&else_if.condition, precedence::LOWEST);
+                    self.write(") ");
+                    self.block(&else_if.body, ends.next().unwrap_or(span.end));
+                }
+                if let Some(else_branch) = else_branch {
+                    self.write(" else ");
+                    self.block(else_branch, span.end);
+                }
+            }
+            Statement::Error { span } => self.write(span.content.trim_end()),
+        }
+    }
+
+    /// Prints `expr`, parenthesized if it binds looser than `min_precedence`.
+    pub fn expression(&mut self, expr: &Expression, min_precedence: u8) {
+        let own = expression_precedence(expr);
+        let parenthesize = own < min_precedence;
+        if parenthesize {
+            self.write("(");
+        }
+
+        match expr {
+            Expression::Atom(atom) => self.atom(atom),
+            Expression::BinaryOperation {
+                lhs, operator, rhs, ..
+            } => {
+                let (precedence, right_assoc) = infix_precedence(operator);
+                let (lhs_min, rhs_min) = if right_assoc {
+                    (precedence + 1, precedence)
+                } else {
+                    (precedence, precedence + 1)
+                };
+                self.expression(lhs, lhs_min);
+                match operator {
+                    Operator::FieldAccess | Operator::Range | Operator::RangeInclusive => {
+                        self.write(operator.symbol())
+                    }
+                    Operator::Comma => self.write(", "),
+                    operator => self.write(format!(" {} ", operator)),
+                }
+                self.expression(rhs, rhs_min);
+            }
+            Expression::PrefixOperation { operator, rhs, .. } => {
+                let symbol = operator.symbol();
+                self.write(symbol);
+                let start = self.out.len();
+                self.expression(rhs, affix_operand_precedence(operator, precedence::PREFIX));
+                // `-(-x)` must not run together into the decrement `--x`.
+                let sign = symbol.chars().last().filter(|c| matches!(c, '+' | '-'));
+                if sign.is_some() && sign == self.out[start..].chars().next() {
+                    self.out.insert(start, ' ');
+                }
+            }
+            Expression::PostfixOperation { lhs, operator, .. } => {
+                self.expression(lhs, affix_operand_precedence(operator, precedence::POSTFIX));
+                self.write(operator.symbol());
+            }
+            Expression::Call { lhs, params, .. } => {
+                self.expression(lhs, precedence::POSTFIX);
+                self.write("(");
+                if let Some(params) = params {
+                    self.expression(params, precedence::LOWEST);
+                }
+                self.write(")");
+            }
+            Expression::Interpolation { parts, .. } => {
+                self.write("\"");
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => self.write(escape_text(text)),
+                        StringPart::Expression(expr) => {
+                            self.write("{");
+                            self.expression(expr, precedence::LOWEST);
+                            self.write("}");
+                        }
+                    }
+                }
+                self.write("\"");
+            }
+            Expression::Pipe {
+                receiver,
+                name,
+                params,
+                ..
+            } => {
+                if let Some(receiver) = receiver {
+                    self.expression(receiver, precedence::POSTFIX);
+                }
+                self.write(format!(":{}", name));
+                if let Some(params) = params {
+                    self.write("(");
+                    self.expression(params, precedence::LOWEST);
+                    self.write(")");
+                }
+            }
+            Expression::Assignment {
+                declare,
+                name,
+                typ,
+                value,
+                ..
+            } => {
+                if *declare {
+                    self.write("let ");
+                }
+                self.write(name.to_string());
+                if let Some(typ) = typ {
+                    self.write(format!(" {}", typ));
+                }
+                if let Some(value) = value {
+                    self.write(" = ");
+                    self.expression(value, precedence::ASSIGN);
+                }
+            }
+            Expression::Match {
+                scrutinee,
+                branches,
+                ..
+            } => {
+                self.write("do ");
+                // The scrutinee is an infix expression, not a full one.
+                self.expression(scrutinee, precedence::RANGE);
+                self.write(" {");
+                self.indent += 1;
+                for branch in branches {
+                    self.newline();
+                    self.write(format!("{} => ", parameter(&branch.pattern)));
+                    self.expression(&branch.behavior, precedence::LOWEST);
+                }
+                self.indent -= 1;
+                self.newline();
+                self.write("}");
+            }
+            Expression::Do {
+                branches,
+                default_branch,
+                ..
+            } => {
+                self.write("do {");
+                self.indent += 1;
+                for branch in branches {
+                    self.newline();
+                    self.do_branch(branch);
+                }
+                self.newline();
+                self.write("_ => ");
+                self.expression(&default_branch.behavior, precedence::LOWEST);
+                self.indent -= 1;
+                self.newline();
+                self.write("}");
+            }
+        }
+
+        if parenthesize {
+            self.write(")");
+        }
+    }
+
+    fn do_branch(&mut self, branch: &DoBranch) {
+        self.expression(&branch.condition, precedence::LOWEST);
+        self.write(" => ");
+        self.expression(&branch.behavior, precedence::LOWEST);
+    }
+
+    fn atom(&mut self, atom: &Atom) {
+        match atom {
+            Atom::Ident(ident) => self.write(ident.to_string()),
+            Atom::Literal { literal, span } => self.write(literal_text(literal, span)),
+        }
+    }
+}
+
+/// Where `stmt` starts in the source, if it was written there.
+fn statement_start(stmt: &Statement) -> Option<usize> {
+    match stmt {
+        Statement::Nop => None,
+        Statement::Expression(expr) => Some(expr.span().start),
+        Statement::Assignment { ident, .. } | Statement::Declaration { ident, .. } => {
+            Some(ident.span().start)
+        }
+        Statement::If { span, .. } | Statement::Error { span } => Some(span.start),
+    }
+}
+
+fn expression_precedence(expr: &Expression) -> u8 {
+    match expr {
+        Expression::Atom(_) | Expression::Do { .. } | Expression::Match { .. } => precedence::ATOM,
+        Expression::Call { .. } => precedence::POSTFIX,
+        Expression::Interpolation { .. } => precedence::ATOM,
+        Expression::Pipe { receiver, .. } => match receiver {
+            Some(_) => precedence::POSTFIX,
+            None => precedence::ATOM,
+        },
+        Expression::BinaryOperation { operator, .. } => infix_precedence(operator).0,
+        Expression::PrefixOperation { operator, .. } => {
+            affix_precedence(operator, precedence::PREFIX)
+        }
+        Expression::PostfixOperation { operator, .. } => {
+            affix_precedence(operator, precedence::POSTFIX)
+        }
+        Expression::Assignment { .. } => precedence::ASSIGN,
+    }
+}
+
+/// The source text of `param`.
+pub fn parameter(param: &FunctionParameter) -> String {
+    match param {
+        FunctionParameter::NamedAndTyped { name, ty } => format!("{} {}", name, ty),
+        FunctionParameter::NamedDynamic { name } => name.to_string(),
+        FunctionParameter::Anonymous { ty } => ty.to_string(),
+        FunctionParameter::Literal { literal, span } => literal_text(literal, span),
+        FunctionParameter::Wildcard { .. } => "_".to_owned(),
+        FunctionParameter::Cons { head, tail, ty, .. } => match ty {
+            Some(ty) => format!("{}:{} {}", head, tail, ty),
+            None => format!("{}:{}", head, tail),
+        },
+        FunctionParameter::Empty { ty, .. } => match ty {
+            Some(ty) => format!("[] {}", ty),
+            None => "[]".to_owned(),
+        },
+        FunctionParameter::Variant { name, fields, .. } if fields.is_empty() => name.to_string(),
+        FunctionParameter::Variant { name, fields, .. } => {
+            format!("{}({})", name, fields.iter().map(parameter).join(", "))
+        }
+    }
+}
+
+/// The type parameter list `<A, B>`, or nothing for a non-generic item.
+pub fn generics(generics: &[Ident]) -> String {
+    match generics.is_empty() {
+        true => String::new(),
+        false => format!("<{}>", generics.iter().join(", ")),
+    }
+}
+
+fn variant(variant: &VariantDefinition) -> String {
+    match variant.fields.is_empty() {
+        true => variant.name.to_string(),
+        false => format!("{}({})", variant.name, variant.fields.iter().join(", ")),
+    }
+}
+
+/// String text as it must be written between quotes: escapes for quotes,
+/// backslashes and control characters, and doubled braces so they are not
+/// read as interpolations.
+fn escape_text(text: &str) -> String {
+    escape(text, '"')
+}
+
+/// `text` with escapes for `quote`, backslashes and control characters.
+/// Braces are doubled inside strings.
+fn escape(text: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\0' => escaped.push_str("\\0"),
+            '{' | '}' if quote == '"' => {
+                escaped.push(c);
+                escaped.push(c);
+            }
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The source text of `literal`, found at `span`. Numbers keep the spelling
+/// they were written with, less any space after a `-`.
+pub fn literal_text(literal: &Literal, span: &Span) -> String {
+    match literal {
+        Literal::Integer { .. } | Literal::Float { .. } if !span.content.is_empty() => {
+            span.content.split_whitespace().collect()
+        }
+        literal => literal_source(literal),
+    }
+}
+
+/// The source text of `literal`.
+pub fn literal_source(literal: &Literal) -> String {
+    match literal {
+        Literal::Integer {
+            value,
+            radix,
+            suffix,
+        } => {
+            // `{:#x}` and friends print negatives in two's complement
+            let magnitude = value.unsigned_abs();
+            let digits = match radix {
+                Radix::Binary => format!("{:#b}", magnitude),
+                Radix::Octal => format!("{:#o}", magnitude),
+                Radix::Decimal => magnitude.to_string(),
+                Radix::Hexadecimal => format!("{:#x}", magnitude),
+            };
+            let sign = if *value < 0 { "-" } else { "" };
+            format!(
+                "{}{}{}",
+                sign,
+                digits,
+                suffix.map_or("", NumberSuffix::as_str)
+            )
+        }
+        Literal::Float { value, suffix } => {
+            format!("{:?}{}", value, suffix.map_or("", NumberSuffix::as_str))
+        }
+        Literal::String(raw) => format!("\"{}\"", escape_text(raw)),
+        Literal::Char(value) => format!("'{}'", escape(&value.to_string(), '\'')),
+        Literal::Bool(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dump, parse_str};
+
+    use super::format_module;
+
+    /// Formats `source`, checking the result parses to the same tree and
+    /// formats to itself.
+    fn round_trip(source: &str) -> String {
+        let module = parse_str(source).expect("source parses");
+        let formatted = format_module(&module);
+        let reparsed = parse_str(&formatted).expect("formatted source parses");
+        assert_eq!(
+            dump::sexpr(&module),
+            dump::sexpr(&reparsed),
+            "{}",
+            formatted
+        );
+        assert_eq!(formatted, format_module(&reparsed));
+        formatted
+    }
+
+    #[test]
+    fn nested_signs_stay_apart() {
+        let formatted = round_trip("fn f (x int) => -(-x) + +(+x) - -(--x) + -(-5)\n");
+        assert!(formatted.contains("- -x"), "{}", formatted);
+        assert!(formatted.contains("+ +x"), "{}", formatted);
+        assert!(formatted.contains("- --x"), "{}", formatted);
+        assert!(formatted.contains("- -5"), "{}", formatted);
+    }
+
+    #[test]
+    fn other_prefixes_stay_together() {
+        let formatted = round_trip("fn f (x int) => !!true, ~-x, -~x, --x\n");
+        assert!(formatted.contains("!!true, ~-x, -~x, --x"), "{}", formatted);
+    }
+
+    #[test]
+    fn negative_literals_keep_their_radix() {
+        let formatted =
+            round_trip("fn f (-0x10) => -0b101, -0o17i8, -9223372036854775808, 0.1f32\n");
+        assert!(formatted.contains("fn f (-0x10)"), "{}", formatted);
+        assert!(
+            formatted.contains("-0b101, -0o17i8, -9223372036854775808, 0.1f32"),
+            "{}",
+            formatted
+        );
+    }
+
+    #[test]
+    fn numbers_keep_their_spelling() {
+        let formatted =
+            round_trip("fn f (0x1F) => 1_000i32 + 1e3 - - 0b1010, -5 ** 2, (-5) ** 2\n");
+        assert!(formatted.contains("fn f (0x1F)"), "{}", formatted);
+        assert!(
+            formatted.contains("1_000i32 + 1e3 - -0b1010, -5 ** 2, (-5) ** 2"),
+            "{}",
+            formatted
+        );
+    }
+
+    #[test]
+    fn ranges_keep_their_bounds() {
+        let formatted = round_trip("fn f (n int) => (1..=n + 1, ..5, 2.., (0..n):collect)\n");
+        assert!(
+            formatted.contains("1..=n + 1, ..5, 2.., (0..n):collect"),
+            "{}",
+            formatted
+        );
+    }
+
+    #[test]
+    fn pipes_stay_pipes() {
+        let formatted = round_trip(
+            "fn f (x int) => {\n    x:add(1):double\n    (x + 1):double\n    :println(x)\n}\n",
+        );
+        assert!(
+            formatted.contains("x:add(1):double\n    (x + 1):double\n    :println(x)"),
+            "{}",
+            formatted
+        );
+    }
+
+    #[test]
+    fn comments_stay_in_place() {
+        let source = "\
+// Header
+
+/// Adds one.
+fn inc (x int) int => x + 1 // trailing
+
+/* block
+   comment */
+type Point {
+    x int // trailing
+    // before y
+    y int
+    // end of type
+}
+
+fn main => {
+    // first
+    if (true) {
+        println(1) // shown
+        // end of then
+    } else if (false) {
+        // in else if
+        println(2)
+    } else {
+        // in else
+        println(3)
+    }
+    /// stray
+    println(inc(1))
+}
+
+// end of file
+";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn commented_bodies_become_blocks() {
+        let source = "fn one => {\n    // why\n    1\n}\n";
+        assert_eq!(round_trip(source), source);
+        assert_eq!(
+            round_trip("fn one => {\n    1 // why\n}\n"),
+            "fn one => 1 // why\n"
+        );
+        let source = "fn one => {\n    1 // why\n    // and why not\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+}
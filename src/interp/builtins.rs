@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::parser::ast::Span;
+
+use super::{
+    error::{RuntimeError, RuntimeResult},
+    value::Value,
+};
+
+/// Functions provided by the interpreter rather than defined in source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Builtin {
+    Print,
+    Println,
+    Assert,
+    AssertEq,
+    Collect,
+}
+impl Builtin {
+    pub const ALL: [Self; 5] = [
+        Self::Print,
+        Self::Println,
+        Self::Assert,
+        Self::AssertEq,
+        Self::Collect,
+    ];
+
+    pub fn lookup(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|builtin| builtin.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Print => "print",
+            Self::Println => "println",
+            Self::Assert => "assert",
+            Self::AssertEq => "assert_eq",
+            Self::Collect => "collect",
+        }
+    }
+
+    pub fn call(&self, args: Vec<Value>, span: &Span, out: &mut dyn Write) -> RuntimeResult<Value> {
+        let arity = |expected: usize| {
+            if args.len() == expected {
+                return Ok(());
+            }
+            Err(RuntimeError::ArityMismatch {
+                callee: self.name().to_owned(),
+                expected,
+                actual: args.len(),
+                span: span.clone(),
+            })
+        };
+
+        match self {
+            Self::Print | Self::Println => {
+                let text = args.iter().join(" ");
+                let newline = if *self == Self::Println { "\n" } else { "" };
+                // Output is best-effort, as with Rust's `print!`.
+                let _ = write!(out, "{}{}", text, newline);
+                Ok(Value::Unit)
+            }
+            Self::Assert => {
+                arity(1)?;
+                match &args[0] {
+                    Value::Bool(true) => Ok(Value::Unit),
+                    Value::Bool(false) => Err(RuntimeError::AssertionFailed {
+                        message: "condition was false".to_owned(),
+                        span: span.clone(),
+                    }),
+                    other => Err(RuntimeError::type_error(
+                        format!("`assert` expects a bool, got `{}`", other.type_name()),
+                        span,
+                    )),
+                }
+            }
+            Self::AssertEq => {
+                arity(2)?;
+                if args[0].equals(&args[1]) {
+                    return Ok(Value::Unit);
+                }
+                Err(RuntimeError::AssertionFailed {
+                    message: format!("`{}` != `{}`", args[0], args[1]),
+                    span: span.clone(),
+                })
+            }
+            Self::Collect => {
+                arity(1)?;
+                if matches!(&args[0], Value::Range(range) if !range.is_bounded()) {
+                    return Err(RuntimeError::UnboundedRange { span: span.clone() });
+                }
+                match args[0].iter() {
+                    Some(elements) => Ok(Value::Array(elements.collect())),
+                    None => Err(RuntimeError::type_error(
+                        format!(
+                            "`collect` expects a sequence, got `{}`",
+                            args[0].type_name()
+                        ),
+                        span,
+                    )),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::resolve::DefId;
+
+use super::value::Value;
+
+/// The local bindings of a single call frame.
+///
+/// Each `let`, parameter and pattern name has a [`DefId`] of its own, so
+/// scoping was settled by name resolution and a frame needs just one map.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<DefId, Value>,
+}
+impl Environment {
+    pub fn define(&mut self, id: DefId, value: Value) {
+        self.values.insert(id, value);
+    }
+
+    pub fn get(&self, id: DefId) -> Option<&Value> {
+        self.values.get(&id)
+    }
+
+    /// Rebinds `id`. Returns `false` if it is not bound yet.
+    pub fn assign(&mut self, id: DefId, value: Value) -> bool {
+        match self.values.get_mut(&id) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
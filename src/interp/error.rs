@@ -0,0 +1,100 @@
+use thiserror::Error;
+
+use crate::{
+    diagnostic::{Diagnostic, Label},
+    parser::ast::Span,
+};
+
+#[derive(Debug, Error)]
+pub enum RuntimeError {
+    #[error("No `main` function defined")]
+    NoMain,
+    #[error("Failed to start the interpreter: {0}")]
+    Spawn(#[from] std::io::Error),
+    #[error("Cannot find `{name}` in this scope")]
+    Undefined { name: String, span: Span },
+    #[error("Type error: {message}")]
+    TypeError { message: String, span: Span },
+    #[error("`{callee}` expects {expected} argument(s), got {actual}")]
+    ArityMismatch {
+        callee: String,
+        expected: usize,
+        actual: usize,
+        span: Span,
+    },
+    #[error("No clause of `{callee}` matches the arguments ({args})")]
+    NoMatchingClause {
+        callee: String,
+        args: String,
+        span: Span,
+    },
+    #[error("No `do` branch matches `{value}`")]
+    NoMatchingBranch { value: String, span: Span },
+    #[error("Value of type `{ty}` is not callable")]
+    NotCallable { ty: String, span: Span },
+    #[error("Type `{ty}` has no field or method `{field}`")]
+    UnknownField {
+        ty: String,
+        field: String,
+        span: Span,
+    },
+    #[error("Division by zero")]
+    DivisionByZero { span: Span },
+    #[error("Arithmetic overflow")]
+    Overflow { span: Span },
+    #[error("Cannot collect a range without both bounds")]
+    UnboundedRange { span: Span },
+    #[error("Invalid assignment target")]
+    InvalidAssignment { span: Span },
+    #[error("Assertion failed: {message}")]
+    AssertionFailed { message: String, span: Span },
+    #[error("Recursion limit of {limit} calls exceeded")]
+    RecursionLimit { limit: usize, span: Span },
+    #[error("Out of stack after {depth} nested calls")]
+    StackOverflow { depth: usize, span: Span },
+    #[error("Cannot evaluate code that failed to parse")]
+    InvalidNode { span: Span },
+    #[error("{what} are not supported yet")]
+    Unsupported { what: &'static str, span: Span },
+}
+impl RuntimeError {
+    pub fn type_error<S: ToString>(message: S, span: &Span) -> Self {
+        Self::TypeError {
+            message: message.to_string(),
+            span: span.clone(),
+        }
+    }
+
+    /// The location the error originated from, if any.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Self::NoMain | Self::Spawn(_) => None,
+            Self::Undefined { span, .. }
+            | Self::TypeError { span, .. }
+            | Self::ArityMismatch { span, .. }
+            | Self::NoMatchingClause { span, .. }
+            | Self::NoMatchingBranch { span, .. }
+            | Self::NotCallable { span, .. }
+            | Self::UnknownField { span, .. }
+            | Self::DivisionByZero { span }
+            | Self::Overflow { span }
+            | Self::UnboundedRange { span }
+            | Self::InvalidAssignment { span }
+            | Self::AssertionFailed { span, .. }
+            | Self::RecursionLimit { span, .. }
+            | Self::StackOverflow { span, .. }
+            | Self::InvalidNode { span }
+            | Self::Unsupported { span, .. } => Some(span),
+        }
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self);
+        match self.span() {
+            Some(span) => diagnostic.with_label(Label::primary(span.clone(), "raised here")),
+            None => diagnostic,
+        }
+    }
+}
+
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
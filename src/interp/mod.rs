@@ -0,0 +1,1465 @@
+//! A tree-walking interpreter over the parsed AST.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, Write},
+    mem,
+    rc::Rc,
+    thread,
+};
+
+use itertools::Itertools;
+use log::trace;
+
+use crate::parser::ast::{
+    expr::{
+        atom::Atom, literal::Literal, operator::Operator, DoBranch, Expression, MatchBranch,
+        StringPart,
+    },
+    function::{Function, FunctionDefinition},
+    function_parameter::FunctionParameter,
+    ident::Ident,
+    module::Module,
+    statement::Statement,
+    type_definition::{TypeDefinition, VariantDefinition},
+    type_expr::TypeExpr,
+    Span,
+};
+use crate::resolve::{DefKind, Resolution};
+
+use self::{
+    builtins::Builtin,
+    env::Environment,
+    error::{RuntimeError, RuntimeResult},
+    value::{Array, Callable, Instance, IntRange, Value, Variant},
+};
+
+pub mod builtins;
+pub mod env;
+pub mod error;
+pub mod value;
+
+/// Deepest call nesting allowed before evaluation is aborted.
+pub const RECURSION_LIMIT: usize = 10_000;
+/// Native stack a call takes: an optimized build measures about 3.6 KiB for
+/// `fn down (n int) => 1 + down(n - 1)`, doubled for bodies that nest deeper.
+const FRAME_SIZE: usize = 8 * 1024;
+/// Stack reserved for evaluation, enough to reach [`RECURSION_LIMIT`] in an
+/// optimized build. Unoptimized builds take several times as much per call,
+/// and run out of stack first; see [`Interpreter::limit_stack`].
+const STACK_SIZE: usize = RECURSION_LIMIT * FRAME_SIZE;
+/// Stack left over when a call is refused, for the builtins and error
+/// reporting that may still run.
+const STACK_RESERVE: usize = 256 * 1024;
+
+/// Runs `module`, starting at `fn main`, writing program output to stdout.
+/// Names are looked up through `resolution`, which must be that of `module`.
+///
+/// Evaluation recurses on the native stack, so it runs on a dedicated thread
+/// with a stack large enough for deeply recursive programs.
+pub fn run(module: &Module, resolution: &Resolution) -> RuntimeResult<()> {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .name("interp".to_owned())
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || {
+                Interpreter::new(module, resolution)
+                    .limit_stack(STACK_SIZE)
+                    .run_main()
+                    .map(drop)
+            })
+            .map_err(RuntimeError::Spawn)?
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+pub struct Interpreter<'m> {
+    functions: HashMap<String, FunctionDefinition<'m>>,
+    types: HashMap<String, &'m TypeDefinition>,
+    /// Every variant of a sum type, with the name of that type.
+    variants: HashMap<String, (String, &'m VariantDefinition)>,
+    resolution: &'m Resolution,
+    /// Bindings of the frame currently executing.
+    env: Environment,
+    depth: usize,
+    /// The lowest native stack address a call may start at; see
+    /// [`Interpreter::limit_stack`].
+    stack_floor: usize,
+    out: Box<dyn Write + 'm>,
+}
+impl<'m> Interpreter<'m> {
+    pub fn new(module: &'m Module, resolution: &'m Resolution) -> Self {
+        Self::with_output(module, resolution, io::stdout())
+    }
+
+    /// An interpreter whose `print`/`println` output goes to `out`.
+    pub fn with_output<W: Write + 'm>(
+        module: &'m Module,
+        resolution: &'m Resolution,
+        out: W,
+    ) -> Self {
+        let functions = module
+            .definitions()
+            .into_iter()
+            .map(|definition| (definition.name.clone(), definition))
+            .collect();
+        let types = module
+            .types
+            .iter()
+            .map(|ty| (ty.name.to_string(), ty))
+            .collect();
+        let variants = module
+            .types
+            .iter()
+            .flat_map(|ty| {
+                ty.variants
+                    .iter()
+                    .map(|variant| (variant.name.to_string(), (ty.name.to_string(), variant)))
+            })
+            .collect();
+
+        Self {
+            functions,
+            types,
+            variants,
+            resolution,
+            env: Environment::default(),
+            depth: 0,
+            stack_floor: 0,
+            out: Box::new(out),
+        }
+    }
+
+    /// Refuses calls once about `size` bytes of native stack below the
+    /// caller's frame are in use, so a program that recurses too deeply for
+    /// the thread's stack fails with an error rather than aborting.
+    pub fn limit_stack(mut self, size: usize) -> Self {
+        self.stack_floor = stack_address().saturating_sub(size.saturating_sub(STACK_RESERVE));
+        self
+    }
+
+    pub fn run_main(&mut self) -> RuntimeResult<Value> {
+        trace!("[Start] interp:run-main");
+        let main = self
+            .functions
+            .get("main")
+            .and_then(|definition| definition.clauses.first())
+            .copied()
+            .ok_or(RuntimeError::NoMain)?;
+        let result = self.call_function(main, vec![], &main.func_name.span());
+        let _ = self.out.flush();
+        trace!("[EndOf] interp:run-main");
+        result
+    }
+
+    /// Calls the user-defined function `name` with already evaluated arguments.
+    pub fn call(&mut self, name: &str, args: Vec<Value>, span: &Span) -> RuntimeResult<Value> {
+        let definition = self
+            .functions
+            .get(name)
+            .ok_or_else(|| RuntimeError::Undefined {
+                name: name.to_owned(),
+                span: span.clone(),
+            })?;
+        let function = select(name, &definition.clauses, &args, span)?;
+        self.call_function(function, args, span)
+    }
+
+    fn call_function(
+        &mut self,
+        function: &Function,
+        args: Vec<Value>,
+        span: &Span,
+    ) -> RuntimeResult<Value> {
+        self.call_with_receiver(function, None, args, span)
+    }
+
+    /// Runs `function` in a fresh frame, binding `self` to `receiver` if given.
+    fn call_with_receiver(
+        &mut self,
+        function: &Function,
+        receiver: Option<Value>,
+        args: Vec<Value>,
+        span: &Span,
+    ) -> RuntimeResult<Value> {
+        let name = function.func_name.to_string();
+        trace!("[Start] interp:call({})", name);
+
+        if function.params.len() != args.len() {
+            return Err(RuntimeError::ArityMismatch {
+                callee: name,
+                expected: function.params.len(),
+                actual: args.len(),
+                span: span.clone(),
+            });
+        }
+        if self.depth >= RECURSION_LIMIT {
+            return Err(RuntimeError::RecursionLimit {
+                limit: RECURSION_LIMIT,
+                span: span.clone(),
+            });
+        }
+        if stack_address() < self.stack_floor {
+            return Err(RuntimeError::StackOverflow {
+                depth: self.depth,
+                span: span.clone(),
+            });
+        }
+
+        let mut frame = Environment::default();
+        let this = self.resolution.binding(&function.func_name.span());
+        if let (Some(receiver), Some(id)) = (receiver, this) {
+            frame.define(id, receiver);
+        }
+        for (param, arg) in function.params.iter().zip(args) {
+            // A lone candidate reports a mismatched annotation as a type error
+            // when binding it, rather than as a failed match.
+            let annotated = matches!(
+                param,
+                FunctionParameter::NamedAndTyped { .. }
+                    | FunctionParameter::NamedDynamic { .. }
+                    | FunctionParameter::Anonymous { .. }
+            );
+            if !annotated && !matches(param, &arg) {
+                return Err(RuntimeError::NoMatchingClause {
+                    callee: name,
+                    args: arg.to_string(),
+                    span: span.clone(),
+                });
+            }
+            bind(self.resolution, &mut frame, param, arg, span)?;
+        }
+
+        let caller = mem::replace(&mut self.env, frame);
+        self.depth += 1;
+        let result = self.eval_body(&function.body);
+        self.depth -= 1;
+        self.env = caller;
+
+        if let (Ok(value), Some(return_type)) = (&result, &function.return_type) {
+            expect_type(value, return_type, &function.func_name.span())?;
+        }
+
+        trace!("[EndOf] interp:call({})", name);
+        result
+    }
+
+    /// Evaluates each statement in turn; the body's value is that of the last.
+    fn eval_body(&mut self, body: &[Statement]) -> RuntimeResult<Value> {
+        let mut value = Value::Unit;
+        for stmt in body {
+            value = self.eval_statement(stmt)?;
+        }
+        Ok(value)
+    }
+
+    pub fn eval_statement(&mut self, stmt: &Statement) -> RuntimeResult<Value> {
+        match stmt {
+            Statement::Nop => Ok(Value::Unit),
+            Statement::Expression(expr) => self.eval(expr),
+            Statement::Assignment { ident, typ, value } => {
+                let value = self.eval(value)?;
+                if let Some(typ) = typ {
+                    expect_type(&value, typ, &ident.span())?;
+                }
+                self.define(ident, value.clone());
+                Ok(value)
+            }
+            Statement::Declaration { ident, typ } => {
+                self.define(ident, default_value(typ));
+                Ok(Value::Unit)
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_ifs,
+                else_branch,
+                ..
+            } => {
+                if self.eval_condition(condition, "if")? {
+                    return self.eval_body(then_branch);
+                }
+                for else_if in else_ifs {
+                    if self.eval_condition(&else_if.condition, "else if")? {
+                        return self.eval_body(&else_if.body);
+                    }
+                }
+                match else_branch {
+                    Some(body) => self.eval_body(body),
+                    None => Ok(Value::Unit),
+                }
+            }
+            Statement::Error { span } => Err(RuntimeError::InvalidNode { span: span.clone() }),
+        }
+    }
+
+    /// Evaluates the condition of `construct`, which must produce a bool.
+    fn eval_condition(&mut self, condition: &Expression, construct: &str) -> RuntimeResult<bool> {
+        match self.eval(condition)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(RuntimeError::type_error(
+                format!(
+                    "`{}` condition must be `bool`, found `{}`",
+                    construct,
+                    other.type_name()
+                ),
+                &condition.span(),
+            )),
+        }
+    }
+
+    pub fn eval(&mut self, expr: &Expression) -> RuntimeResult<Value> {
+        match expr {
+            Expression::Atom(Atom::Literal { literal, .. }) => Ok(literal_value(literal)),
+            Expression::Atom(Atom::Ident(ident)) => self.lookup(ident),
+            Expression::BinaryOperation {
+                lhs,
+                operator,
+                rhs,
+                span,
+            } => self.eval_binary(lhs, operator, rhs, span),
+            Expression::PrefixOperation {
+                operator,
+                rhs,
+                span,
+            } => self.eval_prefix(operator, rhs, span),
+            Expression::PostfixOperation {
+                lhs,
+                operator,
+                span,
+            } => {
+                let old = self.eval(lhs)?;
+                if matches!(operator, Operator::Range) {
+                    return range(Some(&old), None, false, span);
+                }
+                let new = step(&old, operator, span)?;
+                self.assign_to(lhs, new)?;
+                Ok(old)
+            }
+            Expression::Call { lhs, params, span } => {
+                let callee = self.eval(lhs)?;
+                let args = self.eval_arguments(params.as_deref())?;
+                self.apply(callee, args, span)
+            }
+            Expression::Interpolation { parts, span } => {
+                let mut string = String::new();
+                for part in parts {
+                    match part {
+                        StringPart::Text(text) => string.push_str(text),
+                        StringPart::Expression(expr) => {
+                            let value = self.eval(expr)?;
+                            string.push_str(&self.stringify(value, span)?);
+                        }
+                    }
+                }
+                Ok(Value::Str(string.into()))
+            }
+            Expression::Pipe {
+                receiver,
+                name,
+                params,
+                span,
+            } => {
+                let receiver = receiver.as_deref().map(|r| self.eval(r)).transpose()?;
+                let mut args = self.eval_arguments(params.as_deref())?;
+
+                // A method of the receiver's type wins over a free function.
+                if let Some(Value::Instance(instance)) = &receiver {
+                    let ty = instance.borrow().ty.clone();
+                    if let Ok(clauses) = self.methods(&ty, &name.to_string(), span) {
+                        let method = select(&name.to_string(), &clauses, &args, span)?;
+                        return self.call_with_receiver(method, receiver, args, span);
+                    }
+                }
+                args.splice(0..0, receiver);
+                let callee = self.lookup(name)?;
+                self.apply(callee, args, span)
+            }
+            Expression::Assignment {
+                declare,
+                name,
+                typ,
+                value,
+                span,
+            } => {
+                let value = match (value, typ) {
+                    (Some(value), _) => self.eval(value)?,
+                    (None, Some(typ)) => default_value(typ),
+                    (None, None) => Value::Unit,
+                };
+                if let Some(typ) = typ {
+                    expect_type(&value, typ, span)?;
+                }
+
+                if *declare {
+                    self.define(name, value.clone());
+                } else {
+                    self.assign(name, value.clone())?;
+                }
+                Ok(value)
+            }
+            Expression::Do {
+                branches,
+                default_branch,
+                ..
+            } => self.eval_do(branches, default_branch),
+            Expression::Match {
+                scrutinee,
+                branches,
+                span,
+            } => self.eval_match(scrutinee, branches, span),
+        }
+    }
+
+    /// The to-string protocol used by interpolation and printing: instances
+    /// whose type defines a `to_string` method are shown through it,
+    /// everything else by its `Display`.
+    pub fn stringify(&mut self, value: Value, span: &Span) -> RuntimeResult<String> {
+        let Value::Instance(instance) = &value else {
+            return Ok(value.to_string());
+        };
+        let ty = instance.borrow().ty.clone();
+        let Ok(clauses) = self.methods(&ty, "to_string", span) else {
+            return Ok(value.to_string());
+        };
+        let method = select("to_string", &clauses, &[], span)?;
+
+        match self.call_with_receiver(method, Some(value), vec![], span)? {
+            Value::Str(string) => Ok(string.to_string()),
+            other => Err(RuntimeError::type_error(
+                format!(
+                    "`{}.to_string` must return `str`, found `{}`",
+                    ty,
+                    other.type_name()
+                ),
+                span,
+            )),
+        }
+    }
+
+    fn eval_arguments(&mut self, params: Option<&Expression>) -> RuntimeResult<Vec<Value>> {
+        params
+            .map(Expression::arguments)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|arg| self.eval(arg))
+            .collect()
+    }
+
+    /// Runs the behavior of the first branch whose condition holds, or the
+    /// `_` branch if none does.
+    fn eval_do(
+        &mut self,
+        branches: &[DoBranch],
+        default_branch: &DoBranch,
+    ) -> RuntimeResult<Value> {
+        for branch in branches {
+            if self.eval_condition(&branch.condition, "do")? {
+                return self.eval(&branch.behavior);
+            }
+        }
+        self.eval(&default_branch.behavior)
+    }
+
+    /// Runs the behavior of the first branch whose pattern matches the value
+    /// of `scrutinee`, with the pattern's names bound.
+    fn eval_match(
+        &mut self,
+        scrutinee: &Expression,
+        branches: &[MatchBranch],
+        span: &Span,
+    ) -> RuntimeResult<Value> {
+        let value = self.eval(scrutinee)?;
+        let Some(branch) = branches
+            .iter()
+            .find(|branch| matches(&branch.pattern, &value))
+        else {
+            return Err(RuntimeError::NoMatchingBranch {
+                value: value.to_string(),
+                span: span.clone(),
+            });
+        };
+
+        bind(self.resolution, &mut self.env, &branch.pattern, value, span)?;
+        self.eval(&branch.behavior)
+    }
+
+    /// The value of the definition `ident` resolved to. A variant without
+    /// fields is a value rather than a constructor.
+    fn lookup(&self, ident: &Ident) -> RuntimeResult<Value> {
+        let name = ident.to_string();
+        let undefined = || RuntimeError::Undefined {
+            name: name.clone(),
+            span: ident.span(),
+        };
+        let id = self.resolution.use_of(ident).ok_or_else(undefined)?;
+        match self.resolution.definition(id).kind {
+            DefKind::Param | DefKind::Local | DefKind::SelfValue => {
+                self.env.get(id).cloned().ok_or_else(undefined)
+            }
+            DefKind::Function => Ok(Value::Function(Callable::Function(name))),
+            DefKind::Type => Ok(Value::Function(Callable::Constructor(name))),
+            DefKind::Variant => match self.variants.get(&name) {
+                Some((ty, variant)) if variant.fields.is_empty() => {
+                    Ok(Value::Variant(Rc::new(Variant {
+                        ty: ty.clone(),
+                        name,
+                        fields: vec![],
+                    })))
+                }
+                _ => Ok(Value::Function(Callable::Variant(name))),
+            },
+            DefKind::Builtin => Builtin::lookup(&name)
+                .map(|builtin| Value::Function(Callable::Builtin(builtin)))
+                .ok_or_else(undefined),
+            // Methods are only called through a pipe on their receiver.
+            DefKind::Method => Err(undefined()),
+        }
+    }
+
+    /// Binds the local `ident` introduces.
+    fn define(&mut self, ident: &Ident, value: Value) {
+        if let Some(id) = self.resolution.binding(&ident.span()) {
+            self.env.define(id, value);
+        }
+    }
+
+    /// Rebinds the local `ident` refers to.
+    fn assign(&mut self, ident: &Ident, value: Value) -> RuntimeResult<()> {
+        if let Some(id) = self.resolution.use_of(ident) {
+            if self.env.assign(id, value) {
+                return Ok(());
+            }
+        }
+        Err(RuntimeError::Undefined {
+            name: ident.to_string(),
+            span: ident.span(),
+        })
+    }
+
+    pub fn apply(&mut self, callee: Value, args: Vec<Value>, span: &Span) -> RuntimeResult<Value> {
+        let Value::Function(callable) = callee else {
+            return Err(RuntimeError::NotCallable {
+                ty: callee.type_name(),
+                span: span.clone(),
+            });
+        };
+
+        match callable {
+            Callable::Function(name) => self.call(&name, args, span),
+            Callable::Constructor(name) => self.construct(&name, args, span),
+            Callable::Variant(name) => self.construct_variant(&name, args, span),
+            Callable::Builtin(builtin @ (Builtin::Print | Builtin::Println)) => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| Ok(Value::Str(self.stringify(arg, span)?.into())))
+                    .collect::<RuntimeResult<_>>()?;
+                builtin.call(args, span, &mut self.out)
+            }
+            Callable::Builtin(builtin) => builtin.call(args, span, &mut self.out),
+            Callable::Method { receiver, name } => {
+                let ty = receiver.borrow().ty.clone();
+                let method = self.method(&ty, &name, &args, span)?;
+                self.call_with_receiver(method, Some(Value::Instance(receiver)), args, span)
+            }
+        }
+    }
+
+    /// The clauses of the method `name` of `ty`, in declaration order.
+    fn methods(&self, ty: &str, name: &str, span: &Span) -> RuntimeResult<Vec<&'m Function>> {
+        let clauses = self
+            .types
+            .get(ty)
+            .map(|definition| {
+                definition
+                    .methods
+                    .iter()
+                    .filter(|method| method.func_name.to_string() == name)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        if clauses.is_empty() {
+            return Err(RuntimeError::UnknownField {
+                ty: ty.to_owned(),
+                field: name.to_owned(),
+                span: span.clone(),
+            });
+        }
+        Ok(clauses)
+    }
+
+    /// The clause of the method `name` of `ty` that accepts `args`.
+    fn method(
+        &self,
+        ty: &str,
+        name: &str,
+        args: &[Value],
+        span: &Span,
+    ) -> RuntimeResult<&'m Function> {
+        select(name, &self.methods(ty, name, span)?, args, span)
+    }
+
+    /// Builds an instance of `ty`, assigning `args` to its fields in
+    /// declaration order. Trailing fields fall back to their default values.
+    fn construct(&mut self, ty: &str, args: Vec<Value>, span: &Span) -> RuntimeResult<Value> {
+        let definition = self.types[ty];
+        let too_many = args.len() > definition.fields.len();
+        let mut args = args.into_iter();
+        let mut fields = vec![];
+
+        for field in &definition.fields {
+            let Expression::Assignment {
+                name, typ, value, ..
+            } = &field.definition
+            else {
+                continue;
+            };
+
+            let value = match (args.next(), value) {
+                (Some(arg), _) => arg,
+                (None, Some(default)) => self.eval(default)?,
+                (None, None) => {
+                    return Err(RuntimeError::ArityMismatch {
+                        callee: ty.to_owned(),
+                        expected: definition.fields.len(),
+                        actual: fields.len(),
+                        span: span.clone(),
+                    })
+                }
+            };
+            if let Some(typ) = typ {
+                expect_type(&value, typ, span)?;
+            }
+            fields.push((name.to_string(), value));
+        }
+
+        if too_many {
+            return Err(RuntimeError::ArityMismatch {
+                callee: ty.to_owned(),
+                expected: definition.fields.len(),
+                actual: definition.fields.len() + args.count() + 1,
+                span: span.clone(),
+            });
+        }
+
+        Ok(Value::Instance(Rc::new(RefCell::new(Instance {
+            ty: ty.to_owned(),
+            fields,
+        }))))
+    }
+
+    /// Builds a value of the variant `name` from one argument per field.
+    fn construct_variant(&self, name: &str, args: Vec<Value>, span: &Span) -> RuntimeResult<Value> {
+        let (ty, variant) = &self.variants[name];
+        if args.len() != variant.fields.len() {
+            return Err(RuntimeError::ArityMismatch {
+                callee: name.to_owned(),
+                expected: variant.fields.len(),
+                actual: args.len(),
+                span: span.clone(),
+            });
+        }
+        for (arg, field) in args.iter().zip(&variant.fields) {
+            expect_type(arg, field, span)?;
+        }
+
+        Ok(Value::Variant(Rc::new(Variant {
+            ty: ty.clone(),
+            name: name.to_owned(),
+            fields: args,
+        })))
+    }
+
+    fn eval_binary(
+        &mut self,
+        lhs: &Expression,
+        operator: &Operator,
+        rhs: &Expression,
+        span: &Span,
+    ) -> RuntimeResult<Value> {
+        match operator {
+            Operator::FieldAccess => {
+                let receiver = self.eval(lhs)?;
+                self.field(receiver, rhs, span)
+            }
+            Operator::Assign => {
+                let value = self.eval(rhs)?;
+                self.assign_to(lhs, value.clone())?;
+                Ok(value)
+            }
+            Operator::Comma => {
+                self.eval(lhs)?;
+                self.eval(rhs)
+            }
+            Operator::And | Operator::Or => {
+                let short_circuit = matches!(operator, Operator::Or);
+                if expect_bool(&self.eval(lhs)?, operator, span)? == short_circuit {
+                    return Ok(Value::Bool(short_circuit));
+                }
+                Ok(Value::Bool(expect_bool(&self.eval(rhs)?, operator, span)?))
+            }
+            _ => {
+                let lhs = self.eval(lhs)?;
+                let rhs = self.eval(rhs)?;
+                binary(&lhs, operator, &rhs, span)
+            }
+        }
+    }
+
+    /// `receiver.name`: a field value, or a method bound to `receiver`.
+    fn field(&self, receiver: Value, rhs: &Expression, span: &Span) -> RuntimeResult<Value> {
+        let Expression::Atom(Atom::Ident(field)) = rhs else {
+            return Err(RuntimeError::type_error(
+                "expected a field name after `.`",
+                &rhs.span(),
+            ));
+        };
+        let field = field.to_string();
+
+        let Value::Instance(instance) = receiver else {
+            return Err(RuntimeError::UnknownField {
+                ty: receiver.type_name(),
+                field,
+                span: span.clone(),
+            });
+        };
+
+        if let Some(value) = instance.borrow().get(&field) {
+            return Ok(value.clone());
+        }
+        let ty = instance.borrow().ty.clone();
+        self.methods(&ty, &field, span)?;
+        Ok(Value::Function(Callable::Method {
+            receiver: instance,
+            name: field,
+        }))
+    }
+
+    /// Stores `value` into the place denoted by `target`.
+    fn assign_to(&mut self, target: &Expression, value: Value) -> RuntimeResult<()> {
+        match target {
+            Expression::Atom(Atom::Ident(ident)) => self.assign(ident, value),
+            Expression::BinaryOperation {
+                lhs,
+                operator: Operator::FieldAccess,
+                rhs: field,
+                span,
+            } => {
+                let receiver = self.eval(lhs)?;
+                let (Value::Instance(instance), Expression::Atom(Atom::Ident(field))) =
+                    (&receiver, &**field)
+                else {
+                    return Err(RuntimeError::InvalidAssignment { span: span.clone() });
+                };
+                if instance.borrow_mut().set(&field.to_string(), value) {
+                    return Ok(());
+                }
+                Err(RuntimeError::UnknownField {
+                    ty: receiver.type_name(),
+                    field: field.to_string(),
+                    span: span.clone(),
+                })
+            }
+            target => Err(RuntimeError::InvalidAssignment {
+                span: target.span(),
+            }),
+        }
+    }
+
+    fn eval_prefix(
+        &mut self,
+        operator: &Operator,
+        rhs: &Expression,
+        span: &Span,
+    ) -> RuntimeResult<Value> {
+        let value = self.eval(rhs)?;
+        match (operator, &value) {
+            (Operator::Inc | Operator::Dec, _) => {
+                let new = step(&value, operator, span)?;
+                self.assign_to(rhs, new.clone())?;
+                Ok(new)
+            }
+            (Operator::Range | Operator::RangeInclusive, _) => range(
+                None,
+                Some(&value),
+                matches!(operator, Operator::RangeInclusive),
+                span,
+            ),
+            (Operator::Add, Value::Int(_) | Value::Float(_)) => Ok(value),
+            (Operator::Subtract, Value::Int(int)) => int
+                .checked_neg()
+                .map(Value::Int)
+                .ok_or(RuntimeError::Overflow { span: span.clone() }),
+            (Operator::Subtract, Value::Float(float)) => Ok(Value::Float(-float)),
+            (Operator::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (Operator::BitNot, Value::Int(int)) => Ok(Value::Int(!int)),
+            _ => Err(RuntimeError::type_error(
+                format!(
+                    "cannot apply prefix `{}` to `{}`",
+                    operator,
+                    value.type_name()
+                ),
+                span,
+            )),
+        }
+    }
+}
+
+/// The first of `clauses` that accepts `args`. With a single clause of the
+/// right arity, that clause is returned anyway so that binding it reports
+/// exactly which argument it rejected.
+fn select<'m>(
+    name: &str,
+    clauses: &[&'m Function],
+    args: &[Value],
+    span: &Span,
+) -> RuntimeResult<&'m Function> {
+    let candidates = clauses
+        .iter()
+        .filter(|clause| clause.params.len() == args.len())
+        .copied()
+        .collect::<Vec<_>>();
+
+    match candidates.iter().find(|clause| accepts(clause, args)) {
+        Some(clause) => Ok(*clause),
+        None if candidates.len() == 1 => Ok(candidates[0]),
+        None if candidates.is_empty() => Ok(clauses[0]),
+        None => Err(RuntimeError::NoMatchingClause {
+            callee: name.to_owned(),
+            args: args.iter().join(", "),
+            span: span.clone(),
+        }),
+    }
+}
+
+/// Whether `clause` matches a call with `args`.
+fn accepts(clause: &Function, args: &[Value]) -> bool {
+    clause.params.len() == args.len()
+        && clause
+            .params
+            .iter()
+            .zip(args)
+            .all(|(param, arg)| matches(param, arg))
+}
+
+/// Whether the pattern of `param` matches `value`.
+fn matches(param: &FunctionParameter, value: &Value) -> bool {
+    match param {
+        // Only the head is checked against the annotation: the tail is
+        // checked as the head of the next match, so recursing over a
+        // sequence stays linear.
+        FunctionParameter::Cons { ty, .. } => value
+            .split_first()
+            .is_some_and(|(head, _)| ty.as_ref().is_none_or(|ty| conforms_head(value, &head, ty))),
+        // Every element of an empty sequence conforms, so the check is cheap
+        // once it is known to be empty.
+        FunctionParameter::Empty { ty, .. } => {
+            value.is_empty_sequence() && ty.as_ref().is_none_or(|ty| conforms(value, ty))
+        }
+        _ if param.ty().is_some_and(|ty| !conforms(value, ty)) => false,
+        FunctionParameter::Literal { literal, .. } => value.equals(&literal_value(literal)),
+        FunctionParameter::Variant { name, fields, .. } => match value {
+            Value::Variant(variant) => {
+                *name.to_string() == variant.name
+                    && fields
+                        .iter()
+                        .zip(&variant.fields)
+                        .all(|(field, value)| matches(field, value))
+            }
+            _ => false,
+        },
+        _ => true,
+    }
+}
+
+/// Binds the names in `param` to the parts of `value` it matched, checking
+/// type annotations on the way.
+fn bind(
+    resolution: &Resolution,
+    env: &mut Environment,
+    param: &FunctionParameter,
+    value: Value,
+    span: &Span,
+) -> RuntimeResult<()> {
+    let mut define = |at: Span, value: Value| {
+        if let Some(id) = resolution.binding(&at) {
+            env.define(id, value);
+        }
+    };
+    match param {
+        FunctionParameter::NamedAndTyped { name, ty } => {
+            expect_type(&value, ty, span)?;
+            define(name.span(), value);
+        }
+        FunctionParameter::NamedDynamic { name } => define(name.span(), value),
+        FunctionParameter::Anonymous { ty } => {
+            expect_type(&value, ty, span)?;
+            define(ty.span(), value);
+        }
+        FunctionParameter::Cons { head, tail, .. } => {
+            if let Some((first, rest)) = value.split_first() {
+                define(head.span(), first);
+                define(tail.span(), rest);
+            }
+        }
+        FunctionParameter::Variant { fields, .. } => {
+            if let Value::Variant(variant) = &value {
+                for (field, value) in fields.iter().zip(&variant.fields) {
+                    bind(resolution, env, field, value.clone(), span)?;
+                }
+            }
+        }
+        FunctionParameter::Literal { .. }
+        | FunctionParameter::Empty { .. }
+        | FunctionParameter::Wildcard { .. } => {}
+    }
+    Ok(())
+}
+
+/// Builds a range from its (integer) bounds; a missing bound is open.
+fn range(
+    start: Option<&Value>,
+    end: Option<&Value>,
+    inclusive: bool,
+    span: &Span,
+) -> RuntimeResult<Value> {
+    let bound = |value: Option<&Value>| match value {
+        None => Ok(None),
+        Some(Value::Int(int)) => Ok(Some(*int)),
+        Some(other) => Err(RuntimeError::type_error(
+            format!("range bounds must be `int`, found `{}`", other.type_name()),
+            span,
+        )),
+    };
+    Ok(Value::Range(IntRange {
+        start: bound(start)?,
+        end: bound(end)?,
+        inclusive,
+    }))
+}
+
+/// `++`/`--` applied to `value`.
+fn step(value: &Value, operator: &Operator, span: &Span) -> RuntimeResult<Value> {
+    let delta = if matches!(operator, Operator::Inc) {
+        1
+    } else {
+        -1
+    };
+    match value {
+        Value::Int(int) => int
+            .checked_add(delta)
+            .map(Value::Int)
+            .ok_or(RuntimeError::Overflow { span: span.clone() }),
+        Value::Float(float) => Ok(Value::Float(float + delta as f64)),
+        other => Err(RuntimeError::type_error(
+            format!("cannot apply `{}` to `{}`", operator, other.type_name()),
+            span,
+        )),
+    }
+}
+
+fn expect_bool(value: &Value, operator: &Operator, span: &Span) -> RuntimeResult<bool> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        other => Err(RuntimeError::type_error(
+            format!(
+                "`{}` expects bool operands, got `{}`",
+                operator,
+                other.type_name()
+            ),
+            span,
+        )),
+    }
+}
+
+/// Applies an arithmetic, comparison or bitwise operator to evaluated operands.
+fn binary(lhs: &Value, operator: &Operator, rhs: &Value, span: &Span) -> RuntimeResult<Value> {
+    let overflow = || RuntimeError::Overflow { span: span.clone() };
+    let mismatch = || {
+        RuntimeError::type_error(
+            format!(
+                "cannot apply `{}` to `{}` and `{}`",
+                operator,
+                lhs.type_name(),
+                rhs.type_name()
+            ),
+            span,
+        )
+    };
+
+    match operator {
+        Operator::Eq => return Ok(Value::Bool(lhs.equals(rhs))),
+        Operator::Neq => return Ok(Value::Bool(!lhs.equals(rhs))),
+        Operator::Range | Operator::RangeInclusive => {
+            let inclusive = matches!(operator, Operator::RangeInclusive);
+            return range(Some(lhs), Some(rhs), inclusive, span);
+        }
+        _ => {}
+    }
+
+    match (lhs, rhs) {
+        (Value::Int(a), Value::Int(b)) => {
+            let (a, b) = (*a, *b);
+            let value = match operator {
+                Operator::Add => a.checked_add(b).ok_or_else(overflow)?,
+                Operator::Subtract => a.checked_sub(b).ok_or_else(overflow)?,
+                Operator::Multiply => a.checked_mul(b).ok_or_else(overflow)?,
+                Operator::Divide | Operator::Mod if b == 0 => {
+                    return Err(RuntimeError::DivisionByZero { span: span.clone() })
+                }
+                Operator::Divide => a.checked_div(b).ok_or_else(overflow)?,
+                Operator::Mod => a.checked_rem(b).ok_or_else(overflow)?,
+                Operator::Pow => {
+                    let exponent = u32::try_from(b).map_err(|_| {
+                        RuntimeError::type_error("integer exponent must be non-negative", span)
+                    })?;
+                    a.checked_pow(exponent).ok_or_else(overflow)?
+                }
+                Operator::BitAnd => a & b,
+                Operator::BitOr => a | b,
+                Operator::BitXor => a ^ b,
+                Operator::Greater => return Ok(Value::Bool(a > b)),
+                Operator::Lesser => return Ok(Value::Bool(a < b)),
+                Operator::GreaterEq => return Ok(Value::Bool(a >= b)),
+                Operator::LesserEq => return Ok(Value::Bool(a <= b)),
+                _ => return Err(mismatch()),
+            };
+            Ok(Value::Int(value))
+        }
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (a, b) = (as_float(lhs), as_float(rhs));
+            let value = match operator {
+                Operator::Add => a + b,
+                Operator::Subtract => a - b,
+                Operator::Multiply => a * b,
+                Operator::Divide => a / b,
+                Operator::Mod => a % b,
+                Operator::Pow => a.powf(b),
+                Operator::Greater => return Ok(Value::Bool(a > b)),
+                Operator::Lesser => return Ok(Value::Bool(a < b)),
+                Operator::GreaterEq => return Ok(Value::Bool(a >= b)),
+                Operator::LesserEq => return Ok(Value::Bool(a <= b)),
+                _ => return Err(mismatch()),
+            };
+            Ok(Value::Float(value))
+        }
+        (Value::Str(a), Value::Str(b)) => match operator {
+            Operator::Add => Ok(Value::Str(format!("{}{}", a, b).into())),
+            Operator::Greater => Ok(Value::Bool(**a > **b)),
+            Operator::Lesser => Ok(Value::Bool(**a < **b)),
+            Operator::GreaterEq => Ok(Value::Bool(**a >= **b)),
+            Operator::LesserEq => Ok(Value::Bool(**a <= **b)),
+            _ => Err(mismatch()),
+        },
+        (Value::Char(a), Value::Char(b)) => match operator {
+            Operator::Greater => Ok(Value::Bool(a > b)),
+            Operator::Lesser => Ok(Value::Bool(a < b)),
+            Operator::GreaterEq => Ok(Value::Bool(a >= b)),
+            Operator::LesserEq => Ok(Value::Bool(a <= b)),
+            _ => Err(mismatch()),
+        },
+        (Value::Bool(a), Value::Bool(b)) => match operator {
+            Operator::BitAnd => Ok(Value::Bool(a & b)),
+            Operator::BitOr => Ok(Value::Bool(a | b)),
+            Operator::BitXor => Ok(Value::Bool(a ^ b)),
+            _ => Err(mismatch()),
+        },
+        _ => Err(mismatch()),
+    }
+}
+
+fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(int) => *int as f64,
+        Value::Float(float) => *float,
+        _ => f64::NAN,
+    }
+}
+
+fn literal_value(literal: &Literal) -> Value {
+    match literal {
+        Literal::Integer { value, .. } => Value::Int(*value),
+        Literal::Float { value, .. } => Value::Float(*value),
+        Literal::String(string) => Value::Str(string.as_str().into()),
+        Literal::Char(chr) => Value::Char(*chr),
+        Literal::Bool(b) => Value::Bool(*b),
+    }
+}
+
+/// The value of a binding declared with a type but no initializer.
+fn default_value(ty: &TypeExpr) -> Value {
+    match ty.to_string().as_str() {
+        "int" => Value::Int(0),
+        "float" => Value::Float(0.0),
+        "char" => Value::Char('\0'),
+        "str" => Value::Str("".into()),
+        "bool" => Value::Bool(false),
+        _ if ty.element().is_some() => Value::Array(Array::default()),
+        _ => Value::Unit,
+    }
+}
+
+/// Whether `value` is of type `ty`.
+///
+/// Every value carries its own representation, so generic code runs
+/// unchanged whatever it is instantiated with: type arguments are erased and
+/// a type parameter accepts anything. The checker keeps them consistent.
+fn conforms(value: &Value, ty: &TypeExpr) -> bool {
+    match ty {
+        TypeExpr::Named { name, .. } | TypeExpr::Native { name, .. } => *name == value.type_name(),
+        TypeExpr::Param { .. } => true,
+        TypeExpr::Array { element, .. } => match value {
+            Value::Array(elements) => elements.iter().all(|value| conforms(value, element)),
+            _ => false,
+        },
+        // Anything that can be iterated can be consumed lazily.
+        TypeExpr::Generator { element, .. } => match value {
+            Value::Range(range) => range.start.is_some() && conforms(&Value::Int(0), element),
+            value => value
+                .iter()
+                .is_some_and(|mut elements| elements.all(|value| conforms(&value, element))),
+        },
+    }
+}
+
+/// Whether the sequence `value`, whose first element is `head`, may be of
+/// type `ty`, judging its elements by `head` alone.
+fn conforms_head(value: &Value, head: &Value, ty: &TypeExpr) -> bool {
+    match ty {
+        TypeExpr::Array { element, .. } => {
+            matches!(value, Value::Array(_)) && conforms(head, element)
+        }
+        TypeExpr::Generator { element, .. } => conforms(head, element),
+        _ => conforms(value, ty),
+    }
+}
+
+/// Checks `value` against a type annotation.
+fn expect_type(value: &Value, ty: &TypeExpr, span: &Span) -> RuntimeResult<()> {
+    if conforms(value, ty) {
+        return Ok(());
+    }
+    Err(RuntimeError::type_error(
+        format!("expected `{}`, found `{}`", ty, value.type_name()),
+        span,
+    ))
+}
+
+/// An address in the caller's native stack frame. The stack grows down, so
+/// deeper calls see lower addresses.
+#[inline(always)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    std::hint::black_box(&marker) as *const u8 as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_str, resolve::resolve};
+
+    use super::{error::RuntimeError, Interpreter};
+
+    /// What running `source`'s `main` prints.
+    fn output(source: &str) -> String {
+        let module = parse_str(source).expect("source parses");
+        let resolved = resolve(&module);
+        let mut out = vec![];
+        Interpreter::with_output(&module, &resolved.resolution, &mut out)
+            .run_main()
+            .expect("main runs");
+        String::from_utf8(out).expect("output is UTF-8")
+    }
+
+    /// The message of the error running `source`'s `main` fails with.
+    fn error(source: &str) -> String {
+        let module = parse_str(source).expect("source parses");
+        let resolved = resolve(&module);
+        let mut interpreter = Interpreter::with_output(&module, &resolved.resolution, vec![]);
+        interpreter.run_main().expect_err("main fails").to_string()
+    }
+
+    #[test]
+    fn branch_lets_leave_outer_bindings_alone() {
+        let source = "fn main => {
+    let x = 1
+    if (x == 1) {
+        let x = 2
+        x = 3
+        println(x)
+    }
+    println(x)
+}
+";
+        assert_eq!(output(source), "3\n1\n");
+    }
+
+    #[test]
+    fn the_first_matching_clause_runs() {
+        let source = "fn kind (0) => \"zero\"
+fn kind (n int) => \"int\"
+fn kind (1) => \"one\"
+fn kind (x) => \"other\"
+
+fn main => println(kind(0), kind(1), kind(2), kind(\"a\"))
+";
+        assert_eq!(output(source), "zero int int other\n");
+    }
+
+    #[test]
+    fn signs_bind_looser_than_powers() {
+        let source = "fn main => println(-5 ** 2, (-5) ** 2, -2 ** 3 ** 2)\n";
+        assert_eq!(output(source), "-25 25 -512\n");
+    }
+
+    #[test]
+    fn methods_dispatch_on_clauses() {
+        let source = "type P {
+    x int
+
+    fn get (0) => self.x
+    fn get (n int) => self.x + n
+    fn get (a int, b int) => a + b
+}
+
+fn main => {
+    let p = P(1)
+    println(p.get(0), p.get(5), p.get(2, 3))
+    let get = p.get
+    println(get(5), p:get(5))
+}
+";
+        assert_eq!(output(source), "1 6 5\n6 6\n");
+    }
+
+    #[test]
+    fn cons_patterns_walk_arrays() {
+        let source = "fn sum ([]) => 0
+fn sum (x:xs) => x + sum(xs)
+
+fn rest (x:xs) => xs
+
+fn main => println(sum(collect(1..5)), rest(rest(collect(1..5))), rest(collect(1..2)))
+";
+        assert_eq!(output(source), "10 [3, 4] []\n");
+    }
+
+    #[test]
+    fn typed_cons_patterns_check_the_head() {
+        let source = "fn kind (x:xs int[]) => \"ints\"
+fn kind (c:cs str) => \"text\"
+fn kind (x:xs int*) => \"range\"
+
+fn count (\"\") => 0
+fn count (c:cs str) => 1 + count(cs)
+
+fn sum ([] int*) => 0
+fn sum (x:xs int*) => x + sum(xs)
+
+fn main => println(kind(collect(1..3)), kind(\"ab\"), kind(1..3), count(\"h\u{e9}llo\"), sum(1..5))
+";
+        assert_eq!(output(source), "ints text range 5 10\n");
+    }
+
+    #[test]
+    fn deep_recursion_runs_out_of_stack_with_an_error() {
+        let source = "fn down (0) => 0
+fn down (n int) => 1 + down(n - 1)
+
+fn main => down(9000)
+";
+        let module = parse_str(source).expect("source parses");
+        let resolved = resolve(&module);
+        let result = Interpreter::with_output(&module, &resolved.resolution, vec![])
+            .limit_stack(512 * 1024)
+            .run_main();
+        assert!(
+            matches!(result, Err(RuntimeError::StackOverflow { depth, .. }) if depth > 0),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn do_runs_the_first_branch_that_holds() {
+        let source = "fn sign (n int) => do {
+    n < 0 => -1
+    n < 10 => 0
+    n < 100 => 10
+    _ => 100
+}
+
+fn first => do {
+    true => 1
+    true => 2
+    _ => 3
+}
+
+fn main => println(sign(-5), sign(5), sign(50), sign(500), first())
+";
+        assert_eq!(output(source), "-1 0 10 100 1\n");
+    }
+
+    #[test]
+    fn do_branches_are_scoped() {
+        let source = "fn main => {
+    let x = 1
+    do {
+        false => 0
+        _ => x = 2
+    }
+    println(x)
+}
+";
+        assert_eq!(output(source), "2\n");
+    }
+
+    #[test]
+    fn do_conditions_must_be_bool() {
+        let source = "fn main => do {
+    1 => 2
+    _ => 3
+}
+";
+        assert_eq!(
+            error(source),
+            "Type error: `do` condition must be `bool`, found `int`"
+        );
+    }
+
+    #[test]
+    fn if_runs_the_first_branch_that_holds() {
+        let source = "fn sign (n int) => {
+    if (n < 0) {
+        \"negative\"
+    } else if (n == 0) {
+        \"zero\"
+    } else if (n < 10) {
+        \"small\"
+    } else {
+        \"large\"
+    }
+}
+
+fn main => {
+    println(sign(-1), sign(0), sign(5), sign(50))
+    if (false) {
+        println(\"never\")
+    }
+}
+";
+        assert_eq!(output(source), "negative zero small large\n");
+    }
+
+    #[test]
+    fn if_branches_are_scoped() {
+        let source = "fn main => {
+    let x = 1
+    if (true) {
+        let x = 2
+        x = 3
+    }
+    println(x)
+}
+";
+        assert_eq!(output(source), "1\n");
+    }
+
+    #[test]
+    fn if_conditions_must_be_bool() {
+        let source = "fn main => if (1) {
+    2
+}
+";
+        assert_eq!(
+            error(source),
+            "Type error: `if` condition must be `bool`, found `int`"
+        );
+    }
+
+    #[test]
+    fn ranges_are_lazy_sequences() {
+        let source = "fn first (x:xs) => x
+
+fn main => {
+    let n = 4
+    println(1..=n + 1, ..5, 2.., 0..3)
+    println(collect(1..=n), collect(3..3), first(7..), (1..4):collect)
+}
+";
+        assert_eq!(
+            output(source),
+            "1..=5 ..5 2.. 0..3\n[1, 2, 3, 4] [] 7 [1, 2, 3]\n"
+        );
+    }
+
+    #[test]
+    fn ranges_reach_the_ends_of_int() {
+        let source = "fn len ([]) => 0
+fn len (x:xs) => 1 + len(xs)
+
+fn main => {
+    println(collect(9223372036854775806..=9223372036854775807))
+    println(collect(9223372036854775806..9223372036854775807))
+    println(len(9223372036854775806..=9223372036854775807))
+    println(collect(-9223372036854775808..=-9223372036854775807))
+    println(collect(-9223372036854775808..-9223372036854775807))
+    println(len(-9223372036854775808..=-9223372036854775807))
+}
+";
+        assert_eq!(
+            output(source),
+            "[9223372036854775806, 9223372036854775807]
+[9223372036854775806]
+2
+[-9223372036854775808, -9223372036854775807]
+[-9223372036854775808]
+2
+"
+        );
+    }
+
+    #[test]
+    fn open_ranges_cannot_be_collected() {
+        assert_eq!(
+            error("fn main => collect(1..)\n"),
+            "Cannot collect a range without both bounds"
+        );
+    }
+
+    #[test]
+    fn pipes_pass_the_receiver_first() {
+        let source = "type Counter {
+    n int = 0
+    fn add (k int) int => self.n + k
+}
+
+fn add (x int, y int) => x + y
+
+fn double (x int) => x * 2
+
+fn main => {
+    println(Counter(5):add(1), 3:double, 3:double:add(4), (1..=3):collect)
+    :println(\"done\")
+}
+";
+        assert_eq!(output(source), "6 6 10 [1, 2, 3]\ndone\n");
+    }
+
+    #[test]
+    fn printing_and_interpolation_use_to_string() {
+        let source = "type Point {
+    x int
+    y int
+    fn to_string str => \"({self.x}, {self.y})\"
+}
+
+fn main => {
+    let p = Point(1, 2)
+    println(p, \"at {p}\", \"{{p}}\")
+}
+";
+        assert_eq!(output(source), "(1, 2) at (1, 2) {p}\n");
+    }
+}
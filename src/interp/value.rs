@@ -0,0 +1,366 @@
+use std::{cell::RefCell, fmt::Display, ops::Deref, rc::Rc};
+
+use itertools::Itertools;
+
+use super::builtins::Builtin;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Unit,
+    Int(i64),
+    Float(f64),
+    Char(char),
+    Str(Str),
+    Bool(bool),
+    Instance(Rc<RefCell<Instance>>),
+    Variant(Rc<Variant>),
+    Function(Callable),
+    Array(Array),
+    Range(IntRange),
+}
+impl Value {
+    /// The name of this value's type, as written in source.
+    pub fn type_name(&self) -> String {
+        match self {
+            Self::Unit => "()".to_owned(),
+            Self::Int(_) => "int".to_owned(),
+            Self::Float(_) => "float".to_owned(),
+            Self::Char(_) => "char".to_owned(),
+            Self::Str(_) => "str".to_owned(),
+            Self::Bool(_) => "bool".to_owned(),
+            Self::Instance(instance) => instance.borrow().ty.clone(),
+            Self::Variant(variant) => variant.ty.clone(),
+            Self::Function(_) => "fn".to_owned(),
+            Self::Array(elements) => match elements.first() {
+                Some(first) => format!("{}[]", first.type_name()),
+                None => "[]".to_owned(),
+            },
+            Self::Range(_) => "int*".to_owned(),
+        }
+    }
+
+    /// Splits a non-empty sequence into its first element and the rest.
+    /// `None` for empty sequences and values that are not sequences.
+    pub fn split_first(&self) -> Option<(Value, Value)> {
+        match self {
+            Self::Str(string) => {
+                let head = string.chars().next()?;
+                Some((Self::Char(head), Self::Str(string.rest())))
+            }
+            Self::Array(elements) => {
+                let head = elements.first()?.clone();
+                Some((head, Self::Array(elements.rest())))
+            }
+            Self::Range(range) => {
+                let (head, tail) = range.split_first()?;
+                Some((Self::Int(head), Self::Range(tail)))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_empty_sequence(&self) -> bool {
+        match self {
+            Self::Str(string) => string.is_empty(),
+            Self::Array(elements) => elements.is_empty(),
+            Self::Range(range) => range.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The elements of a sequence, produced lazily. `None` for values that
+    /// are not sequences and ranges with no start.
+    pub fn iter(&self) -> Option<Box<dyn Iterator<Item = Value> + '_>> {
+        match self {
+            Self::Str(string) => Some(Box::new(string.chars().map(Self::Char))),
+            Self::Array(elements) => Some(Box::new(elements.iter().cloned())),
+            Self::Range(range) => Some(Box::new(range.iter()?.map(Self::Int))),
+            _ => None,
+        }
+    }
+
+    /// Structural equality; instances and variants compare field by field.
+    pub fn equals(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Unit, Self::Unit) => true,
+            (Self::Int(a), Self::Int(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            (Self::Int(a), Self::Float(b)) | (Self::Float(b), Self::Int(a)) => *a as f64 == *b,
+            (Self::Char(a), Self::Char(b)) => a == b,
+            (Self::Str(a), Self::Str(b)) => **a == **b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Array(a), Self::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.equals(b))
+            }
+            (Self::Range(a), Self::Range(b)) => a == b,
+            (Self::Instance(a), Self::Instance(b)) => {
+                let (a, b) = (a.borrow(), b.borrow());
+                a.ty == b.ty
+                    && a.fields.len() == b.fields.len()
+                    && a.fields
+                        .iter()
+                        .zip(b.fields.iter())
+                        .all(|((_, a), (_, b))| a.equals(b))
+            }
+            (Self::Variant(a), Self::Variant(b)) => {
+                a.name == b.name
+                    && a.fields.len() == b.fields.len()
+                    && a.fields
+                        .iter()
+                        .zip(b.fields.iter())
+                        .all(|(a, b)| a.equals(b))
+            }
+            _ => false,
+        }
+    }
+}
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{:?}", value),
+            Self::Char(value) => write!(f, "{}", value),
+            Self::Str(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Instance(instance) => write!(f, "{}", instance.borrow()),
+            Self::Variant(variant) => write!(f, "{}", variant),
+            Self::Function(callable) => write!(f, "<fn {}>", callable.name()),
+            Self::Array(elements) => write!(f, "[{}]", elements.iter().join(", ")),
+            Self::Range(range) => write!(f, "{}", range),
+        }
+    }
+}
+
+/// The elements of an array value. Matching `x:xs` shares the elements with
+/// the rest instead of copying them.
+#[derive(Debug, Clone)]
+pub struct Array {
+    elements: Rc<[Value]>,
+    start: usize,
+}
+impl Array {
+    /// Every element but the first.
+    pub fn rest(&self) -> Self {
+        Self {
+            elements: self.elements.clone(),
+            start: (self.start + 1).min(self.elements.len()),
+        }
+    }
+}
+impl Default for Array {
+    fn default() -> Self {
+        Self {
+            elements: Rc::new([]),
+            start: 0,
+        }
+    }
+}
+impl Deref for Array {
+    type Target = [Value];
+
+    fn deref(&self) -> &[Value] {
+        &self.elements[self.start..]
+    }
+}
+impl FromIterator<Value> for Array {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Self {
+            elements: iter.into_iter().collect(),
+            start: 0,
+        }
+    }
+}
+
+/// The text of a string value. Like [`Array`], matching `c:cs` shares the
+/// text with the rest instead of copying it.
+#[derive(Debug, Clone)]
+pub struct Str {
+    text: Rc<str>,
+    start: usize,
+}
+impl Str {
+    /// Every character but the first.
+    pub fn rest(&self) -> Self {
+        let first = self.chars().next().map_or(0, char::len_utf8);
+        Self {
+            text: self.text.clone(),
+            start: self.start + first,
+        }
+    }
+}
+impl Deref for Str {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text[self.start..]
+    }
+}
+impl From<&str> for Str {
+    fn from(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            start: 0,
+        }
+    }
+}
+impl From<String> for Str {
+    fn from(text: String) -> Self {
+        Self {
+            text: text.into(),
+            start: 0,
+        }
+    }
+}
+impl Display for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self)
+    }
+}
+
+/// The integers from `start` up to `end`, computed on demand.
+///
+/// Either bound may be open: `..b` can only be compared and printed, while
+/// `a..` goes on forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntRange {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+    pub inclusive: bool,
+}
+impl IntRange {
+    /// The first integer past the range, if it has an upper bound. Wider
+    /// than `i64` so that a range may end at `i64::MAX`.
+    fn stop(&self) -> Option<i128> {
+        let end = i128::from(self.end?);
+        Some(end + i128::from(self.inclusive))
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        self.start.is_some() && self.end.is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match (self.start, self.stop()) {
+            (Some(start), Some(stop)) => i128::from(start) >= stop,
+            _ => false,
+        }
+    }
+
+    /// The first element and the range of the rest.
+    pub fn split_first(&self) -> Option<(i64, Self)> {
+        let start = self.start?;
+        if self.is_empty() {
+            return None;
+        }
+        let rest = match start.checked_add(1) {
+            Some(next) => Self {
+                start: Some(next),
+                ..*self
+            },
+            // Only `..=i64::MAX` gets here, and nothing is left of it.
+            None => Self {
+                start: Some(start),
+                end: Some(start),
+                inclusive: false,
+            },
+        };
+        Some((start, rest))
+    }
+
+    pub fn iter(&self) -> Option<impl Iterator<Item = i64>> {
+        let start = i128::from(self.start?);
+        let stop = self.stop().unwrap_or(i128::from(i64::MAX) + 1);
+        Some((start..stop).map(|i| i as i64))
+    }
+}
+impl Display for IntRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(start) = self.start {
+            write!(f, "{}", start)?;
+        }
+        write!(f, "{}", if self.inclusive { "..=" } else { ".." })?;
+        if let Some(end) = self.end {
+            write!(f, "{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+/// A value of a user-defined `type`.
+#[derive(Debug)]
+pub struct Instance {
+    pub ty: String,
+    /// Fields in declaration order.
+    pub fields: Vec<(String, Value)>,
+}
+impl Instance {
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+    }
+
+    /// Overwrites `field`. Returns `false` if the type has no such field.
+    pub fn set(&mut self, field: &str, value: Value) -> bool {
+        match self.fields.iter_mut().find(|(name, _)| name == field) {
+            Some((_, slot)) => {
+                *slot = value;
+                true
+            }
+            None => false,
+        }
+    }
+}
+impl Display for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .join(", ");
+        write!(f, "{} {{ {} }}", self.ty, fields)
+    }
+}
+
+/// A value of a sum type: one of its variants and that variant's fields.
+#[derive(Debug)]
+pub struct Variant {
+    pub ty: String,
+    pub name: String,
+    pub fields: Vec<Value>,
+}
+impl Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.fields.is_empty() {
+            true => write!(f, "{}", self.name),
+            false => write!(f, "{}({})", self.name, self.fields.iter().join(", ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Callable {
+    /// A top-level `fn`.
+    Function(String),
+    /// A `type`, called to construct an instance.
+    Constructor(String),
+    /// A variant of a sum type with fields, called to construct a value.
+    Variant(String),
+    Builtin(Builtin),
+    /// A method together with the instance it was accessed on.
+    Method {
+        receiver: Rc<RefCell<Instance>>,
+        name: String,
+    },
+}
+impl Callable {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Function(name) | Self::Constructor(name) | Self::Variant(name) => name.clone(),
+            Self::Builtin(builtin) => builtin.name().to_owned(),
+            Self::Method { receiver, name } => format!("{}.{}", receiver.borrow().ty, name),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::path::Path;
+
+use pest_derive::Parser;
+
+use crate::parser::{ast::module::Module, error::ParseResult, session::Session};
+
+#[derive(Parser)]
+#[grammar = "fns.pest"]
+pub struct FNSParser;
+
+pub mod diagnostic;
+pub mod dump;
+pub mod format;
+pub mod interp;
+pub mod parser;
+pub mod resolve;
+pub mod typeck;
+
+/// Parse a complete source file from a string in a fresh [`Session`].
+pub fn parse_str(source: &str) -> ParseResult<Module> {
+    Session::new().parse_str(source)
+}
+
+/// Read and parse the source file at `path` in a fresh [`Session`].
+pub fn parse_file<P: AsRef<Path>>(path: P) -> ParseResult<Module> {
+    Session::new().parse_file(path)
+}
@@ -1,21 +1,91 @@
+use std::{fs, path::PathBuf, process::ExitCode};
+
 use chrono::Utc;
-use parser::ast::context::ParseContext;
-use pest::Parser;
-use pest_derive::Parser;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use func::{
+    dump,
+    format::format_module,
+    interp,
+    parser::session::{ParseOutput, Session},
+    resolve::{self, Resolution, ResolveOutput},
+    typeck::{self, TypeckOutput},
+};
+use log::LevelFilter;
 
 #[derive(Parser)]
-#[grammar = "fns.pest"]
-pub struct FNSParser;
+#[command(
+    name = "func",
+    version,
+    about = "Parse, check, format and run func programs"
+)]
+struct Cli {
+    /// Increase log verbosity (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Decrease log verbosity (-q errors only, -qq silent)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    quiet: u8,
+    /// Also write logs to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
 
-pub mod parser;
-use std::fs;
+#[derive(Subcommand)]
+enum Command {
+    /// Parse a file, optionally dumping its syntax tree
+    Parse {
+        file: PathBuf,
+        /// Print the parsed syntax tree in the given format
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        dump_ast: Option<DumpFormat>,
+    },
+    /// Report every diagnostic in the given files
+    Check {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Print the inferred signature of every function
+        #[arg(long)]
+        show_types: bool,
+    },
+    /// Print the given files in canonical format
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Rewrite the files in place instead of printing them
+        #[arg(long, conflicts_with = "check")]
+        write: bool,
+        /// Exit with an error if any file is not already formatted
+        #[arg(long)]
+        check: bool,
+    },
+    /// Run a program, starting at `fn main`
+    Run { file: PathBuf },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpFormat {
+    Debug,
+    Json,
+    Sexpr,
+}
 
-use crate::parser::ast::function::Function;
-use crate::parser::ast::type_definition::TypeDefinition;
-use crate::parser::ast::Parse;
+fn setup_logger(cli: &Cli) -> Result<(), fern::InitError> {
+    let levels = [
+        LevelFilter::Off,
+        LevelFilter::Error,
+        LevelFilter::Warn,
+        LevelFilter::Info,
+        LevelFilter::Debug,
+        LevelFilter::Trace,
+    ];
+    let level = (2 + cli.verbose as usize)
+        .saturating_sub(cli.quiet as usize)
+        .min(levels.len() - 1);
 
-fn setup_logger() -> Result<(), fern::InitError> {
-    fern::Dispatch::new()
+    let mut dispatch = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "[{} {} {}] {}",
@@ -25,37 +95,178 @@ fn setup_logger() -> Result<(), fern::InitError> {
                 message
             ))
         })
-        .level(log::LevelFilter::Trace)
-        .chain(std::io::stdout())
-        .chain(fern::log_file("output.log")?)
-        .apply()?;
+        .level(levels[level])
+        .chain(std::io::stderr());
+    if let Some(path) = &cli.log_file {
+        dispatch = dispatch.chain(fern::log_file(path)?);
+    }
+    dispatch.apply()?;
     Ok(())
 }
 
-fn main() {
-    setup_logger().unwrap();
-    let unparsed_file = fs::read_to_string("./samples/playground.fn").expect("cannot read file");
-    ParseContext::init();
+/// Parses `path` in its own session, printing any diagnostics to stderr.
+fn parse(path: &PathBuf) -> Option<ParseOutput> {
+    parse_in(&mut Session::new(), path)
+}
 
-    let file = FNSParser::parse(Rule::file, &unparsed_file)
-        .expect("unsuccessful parse") // unwrap the parse result
-        .next()
-        .unwrap(); // get and unwrap the `file` rule; never fails
+/// Parses `path` into `session`, printing any diagnostics to stderr.
+fn parse_in(session: &mut Session, path: &PathBuf) -> Option<ParseOutput> {
+    let output = match session.parse_file_partial(path) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("error: {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    for diagnostic in &output.diagnostics {
+        eprint!("{}", diagnostic.render(session.sources()));
+    }
+    Some(output)
+}
 
-    for line in file.into_inner() {
-        println!("Rule: {:?}", line.as_rule());
-        match line.as_rule() {
-            Rule::function => {
-                println!("{:#?}", line);
-                let f = Function::parse(line).unwrap();
-                println!("{:#?}", f);
+/// Resolves the names in a module parsed without errors, printing what it
+/// finds.
+fn resolve_names(session: &Session, output: &ParseOutput) -> ResolveOutput {
+    let resolved = resolve::resolve(&output.module);
+    for error in &resolved.errors {
+        eprint!("{}", error.diagnostic().render(session.sources()));
+    }
+    resolved
+}
+
+/// Type checks a module parsed without errors, printing what it finds.
+fn typecheck(session: &Session, output: &ParseOutput, resolution: &Resolution) -> TypeckOutput {
+    let typed = typeck::check(&output.module, resolution);
+    for error in &typed.errors {
+        eprint!("{}", error.diagnostic().render(session.sources()));
+    }
+    typed
+}
+
+fn exit_code(success: bool) -> ExitCode {
+    if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_command(file: &PathBuf, dump_ast: Option<DumpFormat>) -> ExitCode {
+    let Some(output) = parse(file) else {
+        return ExitCode::FAILURE;
+    };
+
+    match dump_ast {
+        Some(DumpFormat::Debug) => println!("{:#?}", output.module),
+        Some(DumpFormat::Json) => match dump::json(&output.module) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("error: failed to serialize syntax tree: {}", e);
+                return ExitCode::FAILURE;
             }
-            Rule::type_definition => {
-                println!("{:#?}", line);
-                let t = TypeDefinition::parse(line).unwrap();
-                println!("{:#?}", t);
+        },
+        Some(DumpFormat::Sexpr) => print!("{}", dump::sexpr(&output.module)),
+        None => {}
+    }
+
+    exit_code(!output.has_errors())
+}
+
+fn check_command(files: &[PathBuf], show_types: bool) -> ExitCode {
+    let mut success = true;
+    for file in files {
+        let mut session = Session::new();
+        let Some(output) = parse_in(&mut session, file).filter(|output| !output.has_errors())
+        else {
+            success = false;
+            continue;
+        };
+
+        let resolved = resolve_names(&session, &output);
+        let typed = typecheck(&session, &output, &resolved.resolution);
+        if show_types {
+            for signature in &typed.signatures {
+                println!("{}", signature);
             }
-            _ => {}
         }
+        success &= !resolved.has_errors() && typed.errors.is_empty();
+    }
+    exit_code(success)
+}
+
+fn fmt_command(files: &[PathBuf], write: bool, check: bool) -> ExitCode {
+    let mut success = true;
+    for file in files {
+        let mut session = Session::new();
+        let Some(output) = parse_in(&mut session, file).filter(|output| !output.has_errors())
+        else {
+            eprintln!("error: {}: not formatted due to errors", file.display());
+            success = false;
+            continue;
+        };
+        let formatted = format_module(&output.module);
+
+        if check {
+            let original = fs::read_to_string(file).unwrap_or_default();
+            if original != formatted {
+                eprintln!("{}: not formatted", file.display());
+                success = false;
+            }
+        } else if write {
+            if let Err(e) = fs::write(file, formatted) {
+                eprintln!("error: {}: {}", file.display(), e);
+                success = false;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    exit_code(success)
+}
+
+fn run_command(file: &PathBuf) -> ExitCode {
+    let mut session = Session::new();
+    let Some((output, resolved)) = parse_in(&mut session, file)
+        .filter(|output| !output.has_errors())
+        .map(|output| {
+            let resolved = resolve_names(&session, &output);
+            (output, resolved)
+        })
+        .filter(|(output, resolved)| {
+            !resolved.has_errors()
+                && typecheck(&session, output, &resolved.resolution)
+                    .errors
+                    .is_empty()
+        })
+    else {
+        eprintln!("error: {}: not run due to errors", file.display());
+        return ExitCode::FAILURE;
+    };
+
+    match interp::run(&output.module, &resolved.resolution) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprint!("{}", e.diagnostic().render(session.sources()));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Err(e) = setup_logger(&cli) {
+        eprintln!("error: failed to set up logging: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match &cli.command {
+        Command::Parse { file, dump_ast } => parse_command(file, *dump_ast),
+        Command::Check { files, show_types } => check_command(files, *show_types),
+        Command::Fmt {
+            files,
+            write,
+            check,
+        } => fmt_command(files, *write, *check),
+        Command::Run { file } => run_command(file),
     }
 }
@@ -1,68 +1,295 @@
-use std::{
-    collections::HashMap,
-    sync::{Mutex, MutexGuard},
-};
-
-use log::warn;
-use once_cell::sync::OnceCell;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::parser::error::{context_uninitialized, unexpected_lock, ParseError, ParseResult};
-
-use super::Span;
+use crate::{
+    diagnostic::{source::FileId, Diagnostic},
+    parser::error::{ParseError, ParseResult},
+};
 
-static INSTANCE: OnceCell<Mutex<ParseContext>> = OnceCell::new();
+use super::{ident::Ident, module::Comment, Span};
 
 #[derive(Clone, Default)]
 pub struct TypeInformation {
     pub is_native: bool,
     pub span: Span,
+    /// How many type parameters the type takes, as in `type Pair<A, B>`.
+    pub generics: usize,
 }
 impl TypeInformation {
     pub fn native() -> Self {
         Self {
             is_native: true,
             span: Span::default(),
+            generics: 0,
         }
     }
 }
 
+/// A variant of a sum type, see [`VariantDefinition`](super::type_definition::VariantDefinition).
+#[derive(Clone)]
+pub struct VariantInformation {
+    /// The sum type the variant belongs to.
+    pub ty: String,
+    /// How many fields the variant carries.
+    pub arity: usize,
+    pub span: Span,
+}
+
+/// State shared by every node of a single parse, passed down explicitly
+/// through [`Parse::parse`](super::Parse::parse).
+#[derive(Clone)]
 pub struct ParseContext {
     pub types: HashMap<String, TypeInformation>,
+    pub variants: HashMap<String, VariantInformation>,
+    /// The file currently being parsed, and its text.
+    pub file: FileId,
+    pub source: Rc<str>,
+    /// The type parameters of the `type` and `fn` being parsed, innermost
+    /// last; see [`ParseContext::with_type_params`].
+    type_params: RefCell<Vec<(String, Span)>>,
+    /// Problems recovered from so far; see [`ParseContext::report`].
+    diagnostics: RefCell<Vec<Diagnostic>>,
+    /// The comments of the file, and whether an item has claimed each as
+    /// its docs; see [`ParseContext::docs_before`].
+    comments: RefCell<Vec<(Comment, bool)>>,
+}
+impl Default for ParseContext {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl ParseContext {
-    pub fn init() {
+    pub fn new() -> Self {
         let mut types = HashMap::new();
         types.insert("int".into(), TypeInformation::native());
         types.insert("float".into(), TypeInformation::native());
         types.insert("char".into(), TypeInformation::native());
         types.insert("str".into(), TypeInformation::native());
-        let this = Self { types };
+        types.insert("bool".into(), TypeInformation::native());
+        Self {
+            types,
+            variants: HashMap::new(),
+            file: FileId::default(),
+            source: Rc::from(""),
+            type_params: RefCell::default(),
+            diagnostics: RefCell::default(),
+            comments: RefCell::default(),
+        }
+    }
 
-        if INSTANCE.set(Mutex::new(this)).is_err() {
-            warn!("Attempt to re-initialize ParseContext.");
+    pub fn add_type(&mut self, ident: String, info: TypeInformation) -> ParseResult<()> {
+        if let Some(previous) = self.types.get(&ident) {
+            if previous.is_native {
+                return Err(ParseError::NativeType {
+                    ident,
+                    span: info.span,
+                });
+            }
+            return Err(ParseError::DuplicateType {
+                ident,
+                span: info.span,
+                previous: previous.span.clone(),
+            });
+        }
+
+        self.types.insert(ident, info);
+
+        Ok(())
+    }
+
+    /// A type parameter in scope shadows a `type` of the same name.
+    pub fn is_type<S: ToString>(&self, ident: S) -> Option<TypeInformation> {
+        let ident = ident.to_string();
+        match self.type_param(&ident) {
+            Some(span) => Some(TypeInformation {
+                is_native: false,
+                span,
+                generics: 0,
+            }),
+            None => self.types.get(&ident).cloned(),
         }
     }
 
-    pub fn get<'a>() -> ParseResult<MutexGuard<'a, Self>> {
-        let mutex = INSTANCE.get().ok_or(context_uninitialized("add_type"))?;
-        let this = mutex.try_lock().map_err(|_| unexpected_lock("add_type"))?;
-        return Ok(this);
+    /// Where `ident` is declared, if it is a type parameter in scope.
+    pub fn type_param(&self, ident: &str) -> Option<Span> {
+        self.type_params
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(name, _)| name == ident)
+            .map(|(_, span)| span.clone())
     }
 
-    pub fn add_type(ident: String, info: TypeInformation) -> ParseResult<()> {
-        let mut this = Self::get()?;
+    /// Runs `parse` with `params` in scope as types.
+    pub fn with_type_params<T>(&self, params: &[Ident], parse: impl FnOnce() -> T) -> T {
+        let depth = self.type_params.borrow().len();
+        self.type_params
+            .borrow_mut()
+            .extend(params.iter().map(|param| (param.to_string(), param.span())));
+        let result = parse();
+        self.type_params.borrow_mut().truncate(depth);
+        result
+    }
 
-        if this.types.get(&ident).is_some() {
-            return Err(ParseError::DuplicateType { ident });
+    pub fn add_variant(&mut self, ident: String, info: VariantInformation) -> ParseResult<()> {
+        if let Some(previous) = self.variants.get(&ident) {
+            return Err(ParseError::DuplicateVariant {
+                ident,
+                span: info.span,
+                previous: previous.span.clone(),
+            });
         }
 
-        this.types.insert(ident, info);
+        self.variants.insert(ident, info);
 
         Ok(())
     }
 
-    pub fn is_type<S: ToString>(ident: S) -> ParseResult<Option<TypeInformation>> {
-        let this = Self::get()?;
-        return Ok(this.types.get(&ident.to_string()).cloned());
+    pub fn variant<S: ToString>(&self, ident: S) -> Option<VariantInformation> {
+        self.variants.get(&ident.to_string()).cloned()
+    }
+
+    /// A span over `start..end` of the file currently being parsed.
+    pub fn span_between(&self, start: usize, end: usize) -> Span {
+        Span {
+            content: self.source.get(start..end).unwrap_or_default().to_owned(),
+            start,
+            end,
+            file: self.file,
+        }
+    }
+
+    /// Sets the comments of the file about to be parsed.
+    pub fn set_comments(&self, comments: Vec<Comment>) {
+        *self.comments.borrow_mut() = comments.into_iter().map(|c| (c, false)).collect();
+    }
+
+    /// Claims the `///` lines directly above `start` as an item's docs, one
+    /// line of text each. Blank lines and plain comments may come between
+    /// them, but a comment after code on its line ends them.
+    pub fn docs_before(&self, start: usize) -> Vec<String> {
+        let mut comments = self.comments.borrow_mut();
+        let mut docs = vec![];
+        let mut end = start;
+        for (comment, claimed) in comments.iter_mut().rev() {
+            if comment.span.start >= start {
+                continue;
+            }
+            let gap = self.source.get(comment.span.end..end).unwrap_or_default();
+            if comment.trailing || !gap.trim().is_empty() {
+                break;
+            }
+            end = comment.span.start;
+            if let Some(text) = comment.doc() {
+                docs.push(text.to_owned());
+                *claimed = true;
+            }
+        }
+        docs.reverse();
+        docs
+    }
+
+    /// Every comment no item has claimed as its docs.
+    pub fn unclaimed_comments(&self) -> Vec<Comment> {
+        self.comments
+            .borrow()
+            .iter()
+            .filter(|(_, claimed)| !claimed)
+            .map(|(comment, _)| comment.clone())
+            .collect()
+    }
+
+    /// Records a problem the parser recovered from.
+    pub fn report(&self, diagnostic: Diagnostic) {
+        self.diagnostics.borrow_mut().push(diagnostic);
+    }
+
+    /// Removes and returns every diagnostic reported so far.
+    pub fn take_diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pest::Parser;
+
+    use crate::{
+        parser::{
+            ast::{
+                expr::{atom::Atom, Expression},
+                ident::Ident,
+                statement::Statement,
+                Parse,
+            },
+            session::{ParseOutput, Session},
+        },
+        FNSParser, Rule,
+    };
+
+    use super::{ParseContext, TypeInformation};
+
+    fn parse(source: &str) -> ParseOutput {
+        Session::new().parse_str_partial(source)
+    }
+
+    /// `name` parsed as an identifier in `ctx`.
+    fn ident(name: &str, ctx: &ParseContext) -> Ident {
+        let pair = FNSParser::parse(Rule::ident, name)
+            .expect("an identifier")
+            .next()
+            .expect("an identifier");
+        Ident::parse(pair, ctx).expect("identifier parses")
+    }
+
+    #[test]
+    fn types_may_be_used_before_their_definition() {
+        let output = parse("fn origin => Point(0, 0)\n\ntype Point {\n    x int\n    y int\n}\n");
+        assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+        let [Statement::Expression(call)] = output.module.functions[0].body.as_slice() else {
+            panic!("{:?}", output.module.functions[0].body);
+        };
+        let Expression::Call { lhs, .. } = &**call else {
+            panic!("{:?}", call);
+        };
+        assert!(matches!(
+            **lhs,
+            Expression::Atom(Atom::Ident(Ident::Type { .. }))
+        ));
+    }
+
+    #[test]
+    fn duplicate_types_point_at_both_definitions() {
+        let source = "type P {\n    x int\n}\n\ntype P {\n    y int\n}\n";
+        let output = parse(source);
+        let [diagnostic] = output.diagnostics.as_slice() else {
+            panic!("{:?}", output.diagnostics);
+        };
+        assert_eq!(diagnostic.message, "Duplicate type definition `P`");
+        let labels = diagnostic
+            .labels
+            .iter()
+            .map(|label| (label.primary, label.span.start))
+            .collect::<Vec<_>>();
+        assert_eq!(labels, [(true, source.rfind('P').unwrap()), (false, 5)]);
+    }
+
+    #[test]
+    fn native_types_cannot_be_redefined() {
+        let output = parse("type int {\n    x int\n}\n");
+        let [diagnostic] = output.diagnostics.as_slice() else {
+            panic!("{:?}", output.diagnostics);
+        };
+        assert_eq!(diagnostic.message, "`int` is a built-in type");
+        assert_eq!(diagnostic.labels.len(), 1);
+    }
+
+    #[test]
+    fn identifiers_name_types_once_registered() {
+        let mut ctx = ParseContext::new();
+        assert!(matches!(ident("Point", &ctx), Ident::Identifier { .. }));
+        ctx.add_type("Point".to_owned(), TypeInformation::default())
+            .expect("not yet defined");
+        assert!(matches!(ident("Point", &ctx), Ident::Type { .. }));
+        assert!(matches!(ident("int", &ctx), Ident::Native { .. }));
     }
 }
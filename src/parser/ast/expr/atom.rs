@@ -1,8 +1,9 @@
 use pest::iterators::Pair;
+use serde::Serialize;
 
 use crate::{
     parser::{
-        ast::{ident::Ident, Parse},
+        ast::{context::ParseContext, ident::Ident, span, Parse, Span},
         error::{missing, ParseError, ParseResult},
     },
     Rule,
@@ -10,27 +11,45 @@ use crate::{
 
 use super::literal::Literal;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Atom {
-    Literal(Literal),
+    Literal { literal: Literal, span: Span },
     Ident(Ident),
 }
+impl Atom {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Literal { span, .. } => span.clone(),
+            Self::Ident(ident) => ident.span(),
+        }
+    }
+
+    /// Parses a number `literal` with a unary `-` folded into it.
+    pub fn parse_negative(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
+        let span = span(&line, ctx);
+        let rule = line.into_inner().next().ok_or(missing("atom:literal"))?;
+        Ok(Self::Literal {
+            literal: Literal::parse_negative(rule, ctx)?,
+            span,
+        })
+    }
+}
 impl Parse for Atom {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         match line.as_rule() {
             Rule::literal => {
+                let span = span(&line, ctx);
                 let rule = line.into_inner().next().ok_or(missing("atom:literal"))?;
-                return Ok(Self::Literal(Literal::parse(rule)?));
-            }
-            Rule::ident | Rule::ID_anon => {
-                return Ok(Self::Ident(Ident::parse(line)?));
-            }
-            rule => {
-                return Err(ParseError::InvalidRuleErrorOneOf {
-                    expected: vec![Rule::literal, Rule::ident],
-                    actual: rule,
+                Ok(Self::Literal {
+                    literal: Literal::parse(rule, ctx)?,
+                    span,
                 })
             }
+            Rule::ident | Rule::ID_anon => Ok(Self::Ident(Ident::parse(line, ctx)?)),
+            rule => Err(ParseError::InvalidRuleErrorOneOf {
+                expected: vec![Rule::literal, Rule::ident],
+                actual: rule,
+            }),
         }
     }
 }
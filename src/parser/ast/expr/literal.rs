@@ -1,63 +1,465 @@
 use pest::iterators::Pair;
-use snailquote::unescape;
+use serde::Serialize;
 
 use crate::{
+    next,
     parser::{
-        ast::Parse,
-        error::{missing, ParseError, ParseResult},
+        ast::{context::ParseContext, span, Parse},
+        error::{bad_fromstr, ParseError, ParseResult},
     },
     validate_rule, Rule,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Literal {
-    Integer(i32),
-    Float(f32),
+    Integer {
+        value: i64,
+        radix: Radix,
+        suffix: Option<NumberSuffix>,
+    },
+    Float {
+        value: f64,
+        suffix: Option<NumberSuffix>,
+    },
     String(String),
-    Char(String),
+    Char(char),
     Bool(bool),
 }
+impl PartialEq for Literal {
+    /// Numbers compare by value, however they were written.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer { value: a, .. }, Self::Integer { value: b, .. }) => a == b,
+            (Self::Float { value: a, .. }, Self::Float { value: b, .. }) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Char(a), Self::Char(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// The base an integer literal was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+impl Radix {
+    pub fn base(self) -> u32 {
+        match self {
+            Self::Binary => 2,
+            Self::Octal => 8,
+            Self::Decimal => 10,
+            Self::Hexadecimal => 16,
+        }
+    }
+}
+
+/// A type suffix on a number literal, e.g. the `i8` in `10i8`. Values are
+/// still `int`s and `float`s; the suffix bounds (or rounds) the literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum NumberSuffix {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    F32,
+    F64,
+}
+impl NumberSuffix {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        }
+    }
+
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// The smallest and largest integer this suffix allows.
+    fn bounds(self) -> (i64, i64) {
+        match self {
+            Self::I8 => (i8::MIN.into(), i8::MAX.into()),
+            Self::I16 => (i16::MIN.into(), i16::MAX.into()),
+            Self::I32 => (i32::MIN.into(), i32::MAX.into()),
+            Self::U8 => (0, u8::MAX.into()),
+            Self::U16 => (0, u16::MAX.into()),
+            Self::U32 => (0, u32::MAX.into()),
+            Self::I64 | Self::F32 | Self::F64 => (i64::MIN, i64::MAX),
+        }
+    }
+
+    fn parse(line: &Pair<Rule>) -> ParseResult<Self> {
+        Ok(match line.as_str() {
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "f32" => Self::F32,
+            "f64" => Self::F64,
+            other => return Err(bad_fromstr(other.to_owned(), "NumberSuffix::parse")),
+        })
+    }
+}
+
+impl Literal {
+    /// Parses a `number` written after a unary `-`. Like rustc, the sign is
+    /// folded in before range checking, so `-128i8` is accepted.
+    pub fn parse_negative(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
+        validate_rule!(line.as_rule(), number);
+        parse_number(line, ctx, true)
+    }
+
+    /// The name of the native type this literal belongs to.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Integer { .. } => "int",
+            Self::Float { .. } => "float",
+            Self::String(_) => "str",
+            Self::Char(_) => "char",
+            Self::Bool(_) => "bool",
+        }
+    }
+}
 impl Parse for Literal {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         match line.as_rule() {
-            Rule::number => {
-                let raw = line.as_str().to_owned();
-                let maybe_int = raw.parse::<i32>();
-                if let Ok(int) = maybe_int {
-                    return Ok(Self::Integer(int));
-                }
-
-                return Ok(Self::Float(raw.parse::<f32>()?));
-            }
+            Rule::number => parse_number(line, ctx, false),
             Rule::string => {
-                let inner = line
+                let text = line
                     .into_inner()
-                    .next()
-                    .ok_or(missing("literal:string(raw_string)"))?;
-                validate_rule!(inner.as_rule(), raw_string);
-                let raw = inner.as_str().to_owned();
-                return Ok(Self::String(raw));
+                    .map(|part| decode_text(part, ctx))
+                    .collect::<ParseResult<String>>()?;
+                Ok(Self::String(text))
             }
             Rule::chr => {
-                let inner = line
-                    .into_inner()
-                    .next()
-                    .ok_or(missing("literal:chr(raw_chr)"))?;
-                validate_rule!(inner.as_rule(), raw_chr);
-                let raw = unescape(inner.as_str())?.to_owned();
-                return Ok(Self::Char(raw));
-            }
-            Rule::bool => match line.as_str() {
-                "true" => return Ok(Self::Bool(true)),
-                "false" => return Ok(Self::Bool(false)),
-                _ => unreachable!(),
-            },
-            rule => {
-                return Err(ParseError::InvalidRuleErrorOneOf {
-                    expected: vec![Rule::number, Rule::string, Rule::chr, Rule::bool],
-                    actual: rule,
-                })
+                let span = span(&line, ctx);
+                let mut chars = String::new();
+                for part in line.into_inner() {
+                    match part.as_rule() {
+                        Rule::escape => chars.push(decode_escape(part, ctx)?),
+                        _ => {
+                            validate_rule!(part.as_rule(), raw_chr);
+                            chars.push_str(part.as_str());
+                        }
+                    }
+                }
+
+                let mut scalars = chars.chars();
+                match (scalars.next(), scalars.next()) {
+                    (Some(chr), None) => Ok(Self::Char(chr)),
+                    (None, _) => Err(ParseError::InvalidCharError {
+                        reason: "empty character literal",
+                        span,
+                    }),
+                    (Some(_), Some(_)) => Err(ParseError::InvalidCharError {
+                        reason: "character literals must hold exactly one Unicode scalar value",
+                        span,
+                    }),
+                }
             }
+            Rule::bool => Ok(Self::Bool(line.as_str() == "true")),
+            rule => Err(ParseError::InvalidRuleErrorOneOf {
+                expected: vec![Rule::number, Rule::string, Rule::chr, Rule::bool],
+                actual: rule,
+            }),
         }
     }
 }
+
+/// Parses a `number`, negated if `negative`, checking it fits the type its
+/// suffix (or lack of one) gives it.
+fn parse_number(line: Pair<Rule>, ctx: &ParseContext, negative: bool) -> ParseResult<Literal> {
+    let span = span(&line, ctx);
+    let mut rules = line.into_inner();
+    let digits = next!(rules, "literal:number(digits)");
+    let suffix = rules
+        .next()
+        .map(|suffix| NumberSuffix::parse(&suffix))
+        .transpose()?;
+    let text = digits.as_str().replace('_', "");
+    let overflow = |ty: &'static str| ParseError::NumberOverflow {
+        ty,
+        span: span.clone(),
+    };
+
+    if digits.as_rule() == Rule::float || suffix.is_some_and(NumberSuffix::is_float) {
+        let value = text.parse::<f64>()?;
+        let value = if negative { -value } else { value };
+        let value = match suffix {
+            Some(NumberSuffix::F32) if (value as f32).is_infinite() => return Err(overflow("f32")),
+            // Round through f32's shortest decimal form, so `0.1f32` stays
+            // `0.1` rather than becoming `0.10000000149011612`
+            Some(NumberSuffix::F32) => (value as f32).to_string().parse::<f64>()?,
+            _ if value.is_infinite() => return Err(overflow("float")),
+            _ => value,
+        };
+        return Ok(Literal::Float { value, suffix });
+    }
+
+    let (radix, text) = match digits.as_rule() {
+        Rule::radix_int => match &text[..2] {
+            "0x" => (Radix::Hexadecimal, &text[2..]),
+            "0o" => (Radix::Octal, &text[2..]),
+            _ => (Radix::Binary, &text[2..]),
+        },
+        _ => {
+            validate_rule!(digits.as_rule(), decimal_int);
+            (Radix::Decimal, &text[..])
+        }
+    };
+    let ty = suffix.map_or("int", NumberSuffix::as_str);
+    let (min, max) = suffix.map_or((i64::MIN, i64::MAX), NumberSuffix::bounds);
+    let value = i128::from_str_radix(text, radix.base())
+        .ok()
+        .map(|value| if negative { -value } else { value })
+        .and_then(|value| i64::try_from(value).ok())
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| overflow(ty))?;
+    Ok(Literal::Integer {
+        value,
+        radix,
+        suffix,
+    })
+}
+
+/// The text a non-interpolated part of a string literal stands for.
+pub fn decode_text(part: Pair<Rule>, ctx: &ParseContext) -> ParseResult<String> {
+    match part.as_rule() {
+        Rule::raw_string => Ok(part.as_str().to_owned()),
+        Rule::brace_escape => Ok(part.as_str()[..1].to_owned()),
+        Rule::escape => Ok(decode_escape(part, ctx)?.to_string()),
+        Rule::lone_brace => Err(ParseError::LoneBrace {
+            brace: part.as_str().chars().next().unwrap_or_default(),
+            span: span(&part, ctx),
+        }),
+        // Strings with `{expr}` parts are `Expression::Interpolation`s, which
+        // only expressions can hold.
+        Rule::interpolation | Rule::bad_interpolation => Err(ParseError::InterpolatedPattern {
+            span: span(&part, ctx),
+        }),
+        rule => Err(ParseError::InvalidRuleErrorOneOf {
+            expected: vec![
+                Rule::raw_string,
+                Rule::brace_escape,
+                Rule::escape,
+                Rule::lone_brace,
+            ],
+            actual: rule,
+        }),
+    }
+}
+
+/// Decodes an `escape` such as `\n`, `\x41` or `\u{1F600}`.
+pub fn decode_escape(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<char> {
+    validate_rule!(line.as_rule(), escape);
+    let span = span(&line, ctx);
+    let invalid = |reason| ParseError::InvalidEscape {
+        escape: span.content.clone(),
+        reason,
+        span: span.clone(),
+    };
+
+    let kind = next!(line.into_inner(), "literal:escape(kind)");
+    match kind.as_rule() {
+        Rule::predefined => Ok(match kind.as_str() {
+            "n" => '\n',
+            "r" => '\r',
+            "t" => '\t',
+            "0" => '\0',
+            quoted => quoted.chars().next().unwrap_or_default(),
+        }),
+        Rule::byte => match u8::from_str_radix(&kind.as_str()[1..], 16) {
+            Ok(byte) if byte.is_ascii() => Ok(byte as char),
+            _ => Err(invalid("byte escapes must be at most `\\x7F`")),
+        },
+        Rule::unicode => {
+            let hex = next!(kind.into_inner(), "literal:escape(unicode-hex)");
+            u32::from_str_radix(hex.as_str(), 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| invalid("not a Unicode scalar value"))
+        }
+        _ => Err(invalid("unknown escape sequence")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_str,
+        parser::{
+            ast::{
+                expr::{atom::Atom, Expression},
+                statement::Statement,
+            },
+            session::Session,
+        },
+    };
+
+    use super::{Literal, NumberSuffix, Radix};
+
+    const PREFIX: &str = "fn f => ";
+
+    /// What `source` parses to as the body of a function.
+    fn literal(source: &str) -> Literal {
+        let module = parse_str(&format!("{}{}\n", PREFIX, source)).expect("literal parses");
+        let [Statement::Expression(expr)] = module.functions[0].body.as_slice() else {
+            panic!("{:?}", module.functions[0].body);
+        };
+        let Expression::Atom(Atom::Literal { literal, .. }) = &**expr else {
+            panic!("{:?}", expr);
+        };
+        literal.clone()
+    }
+
+    /// The first error parsing `source` as the body of a function, and where
+    /// in `source` it points.
+    fn error(source: &str) -> (String, usize) {
+        let output = Session::new().parse_str_partial(&format!("{}{}\n", PREFIX, source));
+        let diagnostic = output.diagnostics.first().expect("literal is rejected");
+        let start = diagnostic
+            .labels
+            .iter()
+            .find(|label| label.primary)
+            .map_or(0, |label| label.span.start);
+        (diagnostic.message.clone(), start - PREFIX.len())
+    }
+
+    #[test]
+    fn string_escapes_decode() {
+        assert_eq!(
+            literal(r#""a\n\t\r\\\"\'\0|\x41|\u{e9}|\u{1F600}|{{}}""#),
+            Literal::String("a\n\t\r\\\"'\0|A|\u{e9}|\u{1F600}|{}".to_owned())
+        );
+        assert_eq!(literal(r#""""#), Literal::String(String::new()));
+    }
+
+    #[test]
+    fn invalid_escapes_point_at_the_escape() {
+        assert_eq!(
+            error(r#""ab\q""#),
+            (
+                "Invalid escape `\\q`: unknown escape sequence".to_owned(),
+                3
+            )
+        );
+        assert_eq!(
+            error(r#""\x80""#),
+            (
+                "Invalid escape `\\x80`: byte escapes must be at most `\\x7F`".to_owned(),
+                1
+            )
+        );
+        assert_eq!(
+            error(r#""a \u{D800}""#),
+            (
+                "Invalid escape `\\u{D800}`: not a Unicode scalar value".to_owned(),
+                3
+            )
+        );
+    }
+
+    #[test]
+    fn chars_hold_one_scalar_value() {
+        assert_eq!(literal("'a'"), Literal::Char('a'));
+        assert_eq!(literal("'\u{e9}'"), Literal::Char('\u{e9}'));
+        assert_eq!(literal("'\u{1F600}'"), Literal::Char('\u{1F600}'));
+        assert_eq!(literal(r"'\n'"), Literal::Char('\n'));
+        assert_eq!(literal(r"'\''"), Literal::Char('\''));
+        assert_eq!(literal(r"'\u{1F600}'"), Literal::Char('\u{1F600}'));
+        assert_eq!(literal("'\"'"), Literal::Char('"'));
+    }
+
+    #[test]
+    fn chars_reject_anything_else() {
+        assert_eq!(
+            error("''"),
+            (
+                "Invalid character literal: empty character literal".to_owned(),
+                0
+            )
+        );
+        let many = "Invalid character literal: character literals must hold exactly one Unicode scalar value";
+        assert_eq!(error("'ab'"), (many.to_owned(), 0));
+        // `e` followed by a combining acute accent is one grapheme, but two
+        // scalar values.
+        assert_eq!(error("'e\u{301}'"), (many.to_owned(), 0));
+        assert_eq!(error(r"'\n\n'"), (many.to_owned(), 0));
+    }
+
+    #[test]
+    fn integers_keep_their_radix_and_suffix() {
+        let integer = |source| match literal(source) {
+            Literal::Integer {
+                value,
+                radix,
+                suffix,
+            } => (value, radix, suffix),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(integer("1_000"), (1000, Radix::Decimal, None));
+        assert_eq!(integer("0xff"), (255, Radix::Hexadecimal, None));
+        assert_eq!(integer("0o17"), (15, Radix::Octal, None));
+        assert_eq!(integer("0b1010_1010"), (170, Radix::Binary, None));
+        assert_eq!(
+            integer("9223372036854775807"),
+            (i64::MAX, Radix::Decimal, None)
+        );
+        assert_eq!(
+            integer("-9223372036854775808"),
+            (i64::MIN, Radix::Decimal, None)
+        );
+        assert_eq!(
+            integer("255u8"),
+            (255, Radix::Decimal, Some(NumberSuffix::U8))
+        );
+        assert_eq!(
+            integer("-128i8"),
+            (-128, Radix::Decimal, Some(NumberSuffix::I8))
+        );
+    }
+
+    #[test]
+    fn floats_round_to_their_suffix() {
+        let float = |source| match literal(source) {
+            Literal::Float { value, suffix } => (value, suffix),
+            other => panic!("{:?}", other),
+        };
+        assert_eq!(float("1.5"), (1.5, None));
+        assert_eq!(float("1e3"), (1000.0, None));
+        assert_eq!(float("0.1f32"), (0.1, Some(NumberSuffix::F32)));
+        assert_eq!(float("2f64"), (2.0, Some(NumberSuffix::F64)));
+    }
+
+    #[test]
+    fn numbers_out_of_range_are_rejected() {
+        let overflow = |ty: &str| format!("Literal out of range for `{}`", ty);
+        assert_eq!(error("9223372036854775808"), (overflow("int"), 0));
+        assert_eq!(error("-9223372036854775809"), (overflow("int"), 1));
+        assert_eq!(error("0x1_0000_0000_0000_0000"), (overflow("int"), 0));
+        assert_eq!(error("256u8"), (overflow("u8"), 0));
+        assert_eq!(error("-256u8"), (overflow("u8"), 1));
+        assert_eq!(error("-129i8"), (overflow("i8"), 1));
+        assert_eq!(error("1e400"), (overflow("float"), 0));
+        assert_eq!(error("1e39f32"), (overflow("f32"), 0));
+    }
+}
@@ -1,16 +1,26 @@
 use itertools::Itertools;
 use log::trace;
 use pest::iterators::Pair;
+use serde::Serialize;
 
 use crate::{
+    diagnostic::{Diagnostic, Label},
     next,
-    parser::error::{missing, ParseResult},
+    parser::error::{missing, recovered_syntax_error, ParseError, ParseResult},
     validate_rule, Rule,
 };
 
-use self::{atom::Atom, operator::Operator, pratt::PRATT_PARSER};
+use self::{
+    atom::Atom,
+    literal::{decode_text, Literal},
+    operator::Operator,
+    pratt::PRATT_PARSER,
+};
 
-use super::{ident::Ident, Parse};
+use super::{
+    context::ParseContext, function_parameter::FunctionParameter, ident::Ident, span,
+    type_expr::TypeExpr, Parse, Span,
+};
 
 pub mod atom;
 pub mod literal;
@@ -20,58 +30,132 @@ pub mod pratt;
 type Primary = ParseResult<Expression>;
 type SubExp = Box<Expression>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DoBranch {
     pub condition: SubExp,
     pub behavior: SubExp,
 }
 
-#[derive(Debug, Clone)]
+/// A `pattern => behavior` branch of an [`Expression::Match`].
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchBranch {
+    pub pattern: FunctionParameter,
+    pub behavior: SubExp,
+}
+
+/// A piece of an interpolated string.
+#[derive(Debug, Clone, Serialize)]
+pub enum StringPart {
+    Text(String),
+    Expression(SubExp),
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub enum Expression {
     Atom(Atom),
     BinaryOperation {
         lhs: SubExp,
         operator: Operator,
         rhs: SubExp,
+        span: Span,
     },
     PrefixOperation {
         operator: Operator,
         rhs: SubExp,
+        span: Span,
     },
     PostfixOperation {
         lhs: SubExp,
         operator: Operator,
+        span: Span,
     },
     Call {
         lhs: SubExp,
         params: Option<SubExp>,
+        span: Span,
     },
     Assignment {
+        /// Whether this introduces a new binding (`let`).
+        declare: bool,
         name: Ident,
-        typ: Option<Ident>,
+        typ: Option<TypeExpr>,
         value: Option<SubExp>,
+        span: Span,
     },
     Do {
         branches: Vec<DoBranch>,
         default_branch: DoBranch,
+        span: Span,
+    },
+    /// `do scrutinee { pattern => behavior ... }`: evaluates the behavior of
+    /// the first branch whose pattern matches `scrutinee`.
+    Match {
+        scrutinee: SubExp,
+        branches: Vec<MatchBranch>,
+        span: Span,
+    },
+    /// A string literal with embedded `{expr}`s.
+    Interpolation {
+        parts: Vec<StringPart>,
+        span: Span,
+    },
+    /// `receiver:name(params)`, calling `name` with `receiver` as its first
+    /// argument. A leading `:name(params)` has no receiver.
+    Pipe {
+        receiver: Option<SubExp>,
+        name: Ident,
+        params: Option<SubExp>,
+        span: Span,
     },
 }
 impl Expression {
     pub fn boxed(self) -> Box<Self> {
         Box::new(self)
     }
-    pub fn parse_boxed(line: Pair<Rule>) -> ParseResult<Box<Self>> {
-        Ok(Box::new(Self::parse(line)?))
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Atom(atom) => atom.span(),
+            Self::BinaryOperation { span, .. } => span.clone(),
+            Self::PrefixOperation { span, .. } => span.clone(),
+            Self::PostfixOperation { span, .. } => span.clone(),
+            Self::Call { span, .. } => span.clone(),
+            Self::Assignment { span, .. } => span.clone(),
+            Self::Do { span, .. } => span.clone(),
+            Self::Match { span, .. } => span.clone(),
+            Self::Pipe { span, .. } => span.clone(),
+            Self::Interpolation { span, .. } => span.clone(),
+        }
+    }
+    /// Call arguments are parsed as a single comma expression; this flattens
+    /// `a, b, c` back into its operands.
+    pub fn arguments(&self) -> Vec<&Self> {
+        match self {
+            Self::BinaryOperation {
+                lhs,
+                operator: Operator::Comma,
+                rhs,
+                ..
+            } => {
+                let mut args = lhs.arguments();
+                args.extend(rhs.arguments());
+                args
+            }
+            expr => vec![expr],
+        }
+    }
+    pub fn parse_boxed(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Box<Self>> {
+        Ok(Box::new(Self::parse(line, ctx)?))
     }
 }
 impl Expression {
-    fn parse_do(rule: Pair<Rule>) -> Primary {
+    fn parse_do(rule: Pair<Rule>, ctx: &ParseContext) -> Primary {
         trace!("[Start] expr:parse-do");
         validate_rule!(rule.as_rule(), do_expr, field_definition);
 
+        let span = span(&rule, ctx);
         let rules = rule.into_inner();
 
-        let branches = rules
+        let mut branches = rules
             .chunks(2)
             .into_iter()
             .map(|mut branch| {
@@ -79,45 +163,123 @@ impl Expression {
                 let behavior = next!(branch, "expr-do(branch-behavior)");
 
                 Ok(DoBranch {
-                    condition: Self::parse_boxed(condition)?,
-                    behavior: Self::parse_boxed(behavior)?,
+                    condition: Self::parse_boxed(condition, ctx)?,
+                    behavior: Self::parse_boxed(behavior, ctx)?,
                 })
             })
             .collect::<ParseResult<Vec<_>>>()?;
 
-        let is_default_branch = |branch: &&DoBranch| {
-            let Self::Atom(ref atom) = *branch.condition else { return false };
+        let is_default_branch = |branch: &DoBranch| {
+            let Self::Atom(ref atom) = *branch.condition else {
+                return false;
+            };
 
-            let Atom::Ident(ident) = atom else { return false };
+            let Atom::Ident(ident) = atom else {
+                return false;
+            };
 
-            let Ident::Identifier { name, .. } = ident else { return false };
+            let Ident::Identifier { name, .. } = ident else {
+                return false;
+            };
 
             name == "_"
         };
 
-        let default_branch = branches
+        // Branches are tried in order and `_` always matches, so the first
+        // `_` ends the chain and anything after it can never run.
+        let default_at = branches
             .iter()
-            .find(is_default_branch)
-            .ok_or(missing("expr-do(no-default)"))?
-            .clone();
-        let branches = branches
-            .into_iter()
-            .filter(|branch| !is_default_branch(&branch))
-            .collect::<Vec<_>>();
+            .position(is_default_branch)
+            .ok_or(ParseError::MissingDefaultBranch { span: span.clone() })?;
+        let mut rest = branches.split_off(default_at).into_iter();
+        let default_branch = rest.next().ok_or(missing("expr-do(default)"))?;
+        for branch in rest {
+            let branch_span = branch.condition.span();
+            if is_default_branch(&branch) {
+                ctx.report(
+                    ParseError::DuplicateDefaultBranch {
+                        span: branch_span,
+                        previous: default_branch.condition.span(),
+                    }
+                    .diagnostic(),
+                );
+            } else {
+                ctx.report(
+                    Diagnostic::warning("unreachable `do` branch")
+                        .with_label(Label::primary(branch_span, "never tried"))
+                        .with_label(Label::secondary(
+                            default_branch.condition.span(),
+                            "any value matches this branch first",
+                        )),
+                );
+            }
+        }
 
         trace!("[EndOf] expr:parse-do");
         Ok(Self::Do {
             branches,
             default_branch,
+            span,
         })
     }
 
-    fn parse_assignment(rule: Pair<Rule>) -> Primary {
+    fn parse_match(rule: Pair<Rule>, ctx: &ParseContext) -> Primary {
+        trace!("[Start] expr:parse-match");
+        validate_rule!(rule.as_rule(), do_match);
+
+        let span = span(&rule, ctx);
+        let mut rules = rule.into_inner();
+        let scrutinee = Self::parse_boxed(next!(rules, "expr-match(scrutinee)"), ctx)?;
+
+        let branches = rules
+            .chunks(2)
+            .into_iter()
+            .map(|mut branch| {
+                let pattern = next!(branch, "expr-match(branch-pattern)");
+                let behavior = next!(branch, "expr-match(branch-behavior)");
+
+                Ok(MatchBranch {
+                    pattern: FunctionParameter::parse(pattern, ctx)?,
+                    behavior: Self::parse_boxed(behavior, ctx)?,
+                })
+            })
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        // Branches are tried in order, like function clauses.
+        for (at, branch) in branches.iter().enumerate() {
+            let earlier = branches[..at]
+                .iter()
+                .find(|earlier| earlier.pattern.subsumes(&branch.pattern));
+            if let Some(earlier) = earlier {
+                ctx.report(
+                    Diagnostic::warning("unreachable `do` branch")
+                        .with_label(Label::primary(branch.pattern.span(), "never tried"))
+                        .with_label(Label::secondary(
+                            earlier.pattern.span(),
+                            "every value it matches is matched here first",
+                        )),
+                );
+            }
+        }
+
+        trace!("[EndOf] expr:parse-match");
+        Ok(Self::Match {
+            scrutinee,
+            branches,
+            span,
+        })
+    }
+
+    fn parse_assignment(rule: Pair<Rule>, ctx: &ParseContext) -> Primary {
         trace!("[Start] expr:parse-assignment");
         validate_rule!(rule.as_rule(), assignment, field_definition);
 
-        let mut rules = rule.into_inner();
+        let span = span(&rule, ctx);
+        let mut rules = rule.into_inner().peekable();
 
+        let declare = rules
+            .next_if(|rule| matches!(rule.as_rule(), Rule::declare))
+            .is_some();
         let ident = next!(rules, "expr-assignment(ident)");
         let type_or_value = next!(rules, "expr-assignment(type_or_value)");
         let (typ, value) = if matches!(type_or_value.as_rule(), Rule::expr) {
@@ -127,74 +289,225 @@ impl Expression {
         };
 
         let assignment = Self::Assignment {
-            name: Ident::parse(ident)?,
-            typ: typ.map(|t| Ident::parse_expect_type(t)).transpose()?,
-            value: value.map(|v| Self::parse_boxed(v)).transpose()?,
+            declare,
+            name: Ident::parse(ident, ctx)?,
+            typ: typ.map(|t| TypeExpr::parse(t, ctx)).transpose()?,
+            value: value.map(|v| Self::parse_boxed(v, ctx)).transpose()?,
+            span,
         };
 
         trace!("[EndOf] expr:parse-assignment");
         Ok(assignment)
     }
 
-    fn map_primary(primary: Pair<Rule>) -> Primary {
+    /// The arguments of a `call_params` node, if it has any.
+    fn parse_call_params(params: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Option<SubExp>> {
+        validate_rule!(params.as_rule(), call_params);
+        // call_params > parenthesized_expr > expr?
+        Ok(params
+            .into_inner()
+            .next()
+            .and_then(|parenthesized| parenthesized.into_inner().next())
+            .map(|params| Self::parse(params, ctx))
+            .transpose()?
+            .map(Self::boxed))
+    }
+
+    fn parse_pipe(receiver: Option<Expression>, rule: Pair<Rule>, ctx: &ParseContext) -> Primary {
+        trace!("[Start] expr:parse-pipe");
+        validate_rule!(rule.as_rule(), pipe, leading_pipe);
+
+        let start = receiver
+            .as_ref()
+            .map_or(rule.as_span().start(), |receiver| receiver.span().start);
+        let span = ctx.span_between(start, rule.as_span().end());
+        let mut rules = rule.into_inner();
+        let name = Ident::parse(next!(rules, "expr-pipe(name)"), ctx)?;
+        let params = rules
+            .next()
+            .map(|params| Self::parse_call_params(params, ctx))
+            .transpose()?
+            .flatten();
+
+        trace!("[EndOf] expr:parse-pipe");
+        Ok(Self::Pipe {
+            receiver: receiver.map(Self::boxed),
+            name,
+            params,
+            span,
+        })
+    }
+
+    /// Whether `line`, a `literal`, is a string with `{expr}` parts.
+    fn is_interpolated(line: &Pair<Rule>) -> bool {
+        line.clone()
+            .into_inner()
+            .filter(|literal| matches!(literal.as_rule(), Rule::string))
+            .flat_map(|string| string.into_inner())
+            .any(|part| {
+                matches!(
+                    part.as_rule(),
+                    Rule::interpolation | Rule::bad_interpolation
+                )
+            })
+    }
+
+    fn parse_interpolation(rule: Pair<Rule>, ctx: &ParseContext) -> Primary {
+        trace!("[Start] expr:parse-interpolation");
+        validate_rule!(rule.as_rule(), literal);
+
+        let span = span(&rule, ctx);
+        let string = next!(rule.into_inner(), "expr-interpolation(string)");
+        let mut parts = vec![];
+        for part in string.into_inner() {
+            match part.as_rule() {
+                Rule::interpolation => {
+                    let expr = next!(part.into_inner(), "expr-interpolation(expr)");
+                    parts.push(StringPart::Expression(Self::parse_boxed(expr, ctx)?));
+                    continue;
+                }
+                Rule::bad_interpolation => {
+                    return Err(ParseError::Recovered {
+                        diagnostic: Box::new(recovered_syntax_error(
+                            &part,
+                            Rule::whole_interpolation,
+                            ctx,
+                        )),
+                    });
+                }
+                _ => {}
+            }
+
+            let text = decode_text(part, ctx)?;
+            match parts.last_mut() {
+                Some(StringPart::Text(previous)) => previous.push_str(&text),
+                _ => parts.push(StringPart::Text(text)),
+            }
+        }
+
+        trace!("[EndOf] expr:parse-interpolation");
+        Ok(Self::Interpolation { parts, span })
+    }
+
+    /// Where the number literals a unary `-` folds into start: those right
+    /// after a `minus` with no tighter postfix, which only fit their type
+    /// once negated, like `-128i8`. Any other `-` stays an operator.
+    fn negative_literals(pairs: &[Pair<Rule>], ctx: &ParseContext) -> Vec<usize> {
+        let needs_sign = |literal: &Pair<Rule>| {
+            literal.as_rule() == Rule::literal
+                && literal
+                    .clone()
+                    .into_inner()
+                    .all(|inner| inner.as_rule() == Rule::number)
+                && literal
+                    .clone()
+                    .into_inner()
+                    .next()
+                    .is_some_and(|number| Literal::parse(number, ctx).is_err())
+        };
+        let binds_tighter = |op: Option<&Pair<Rule>>| {
+            op.is_some_and(|op| {
+                matches!(
+                    op.as_rule(),
+                    Rule::post_inc
+                        | Rule::post_dec
+                        | Rule::field_access
+                        | Rule::call_params
+                        | Rule::pipe
+                )
+            })
+        };
+        (0..pairs.len().saturating_sub(1))
+            .filter(|&i| {
+                pairs[i].as_rule() == Rule::minus
+                    && needs_sign(&pairs[i + 1])
+                    && !binds_tighter(pairs.get(i + 2))
+            })
+            .map(|i| pairs[i + 1].as_span().start())
+            .collect()
+    }
+
+    fn map_primary(primary: Pair<Rule>, negative: &[usize], ctx: &ParseContext) -> Primary {
         let rule = primary.as_rule();
         trace!("[Start] map-primary({:?})", rule);
         let primary = match primary.as_rule() {
-            Rule::expr | Rule::infix_expr => Expression::parse(primary)?,
-            Rule::parenthesized_expr => Self::parse(next!(
-                primary.into_inner(),
-                "map-primary(parenthesized-child)"
-            ))?,
-            Rule::assignment => Self::parse_assignment(primary)?,
-            Rule::do_expr => Self::parse_do(primary)?,
-            _ => Self::Atom(Atom::parse(primary)?),
+            Rule::literal if negative.contains(&primary.as_span().start()) => {
+                Self::Atom(Atom::parse_negative(primary, ctx)?)
+            }
+            Rule::expr | Rule::infix_expr => Expression::parse(primary, ctx)?,
+            Rule::parenthesized_expr => Self::parse(
+                next!(primary.into_inner(), "map-primary(parenthesized-child)"),
+                ctx,
+            )?,
+            Rule::assignment => Self::parse_assignment(primary, ctx)?,
+            Rule::do_expr => Self::parse_do(primary, ctx)?,
+            Rule::do_match => Self::parse_match(primary, ctx)?,
+            Rule::leading_pipe => Self::parse_pipe(None, primary, ctx)?,
+            Rule::literal if Self::is_interpolated(&primary) => {
+                Self::parse_interpolation(primary, ctx)?
+            }
+            _ => Self::Atom(Atom::parse(primary, ctx)?),
         };
 
         trace!("[EndOf] map-primary({:?})", rule);
         Ok(primary)
     }
 
-    fn map_infix(lhs: Primary, op: Pair<Rule>, rhs: Primary) -> Primary {
+    fn map_infix(lhs: Primary, op: Pair<Rule>, rhs: Primary, ctx: &ParseContext) -> Primary {
         trace!("[Start] map-infix");
-        let operator = Operator::parse(op)?;
+        let operator = Operator::parse(op, ctx)?;
+        let (lhs, rhs) = (lhs?, rhs?);
         let primary = Self::BinaryOperation {
-            lhs: Box::new(lhs?),
+            span: ctx.span_between(lhs.span().start, rhs.span().end),
+            lhs: Box::new(lhs),
             operator,
-            rhs: Box::new(rhs?),
+            rhs: Box::new(rhs),
         };
         trace!("[EndOf] map-infix");
         Ok(primary)
     }
-    fn map_postfix(lhs: Primary, op: Pair<Rule>) -> Primary {
+    fn map_postfix(lhs: Primary, op: Pair<Rule>, ctx: &ParseContext) -> Primary {
         trace!("[Start] map-postfix");
+        let lhs = lhs?;
+        let span = ctx.span_between(lhs.span().start, op.as_span().end());
         match op.as_rule() {
             Rule::call_params => {
                 return Ok(Self::Call {
-                    lhs: lhs?.boxed(),
-                    params: op
-                        .into_inner()
-                        .next()
-                        .map(Self::map_primary)
-                        .transpose()?
-                        .map(Self::boxed),
-                });
+                    lhs: lhs.boxed(),
+                    params: Self::parse_call_params(op, ctx)?,
+                    span,
+                })
             }
+            Rule::pipe => return Self::parse_pipe(Some(lhs), op, ctx),
             _ => {}
         }
 
-        let operator = Operator::parse(op)?;
+        let operator = Operator::parse(op, ctx)?;
         let primary = Self::PostfixOperation {
             operator,
-            lhs: Box::new(lhs?),
+            lhs: Box::new(lhs),
+            span,
         };
         trace!("[EndOf] map-postfix");
         Ok(primary)
     }
-    fn map_prefix(op: Pair<Rule>, rhs: Primary) -> Primary {
+    fn map_prefix(op: Pair<Rule>, rhs: Primary, negative: &[usize], ctx: &ParseContext) -> Primary {
         trace!("[Start] map-prefix");
-        let operator = Operator::parse(op)?;
+        let start = op.as_span().start();
+        let rhs = rhs?;
+        if let Self::Atom(Atom::Literal { literal, span }) = &rhs {
+            if op.as_rule() == Rule::minus && negative.contains(&span.start) {
+                trace!("[EndOf] map-prefix");
+                return Ok(Self::Atom(Atom::Literal {
+                    literal: literal.clone(),
+                    span: ctx.span_between(start, span.end),
+                }));
+            }
+        }
+        let operator = Operator::parse(op, ctx)?;
         let primary = Self::PrefixOperation {
-            rhs: Box::new(rhs?),
+            span: ctx.span_between(start, rhs.span().end),
+            rhs: Box::new(rhs),
             operator,
         };
         trace!("[EndOf] map-prefix");
@@ -202,7 +515,7 @@ impl Expression {
     }
 }
 impl Parse for Expression {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         trace!("[Start] parse-expr({:?})", line.as_rule());
         let rule = line.as_rule();
         validate_rule!(
@@ -216,16 +529,58 @@ impl Parse for Expression {
         );
 
         match rule {
-            Rule::literal | Rule::ident | Rule::ID_anon => Ok(Self::Atom(Atom::parse(line)?)),
-            Rule::parenthesized_expr => {
-                Self::parse(next!(line.into_inner(), "map-primary(parenthesized-child)"))
+            Rule::literal if Self::is_interpolated(&line) => Self::parse_interpolation(line, ctx),
+            Rule::literal | Rule::ident | Rule::ID_anon => Ok(Self::Atom(Atom::parse(line, ctx)?)),
+            Rule::parenthesized_expr => Self::parse(
+                next!(line.into_inner(), "map-primary(parenthesized-child)"),
+                ctx,
+            ),
+            _ => {
+                let pairs = line.into_inner().collect_vec();
+                let negative = Self::negative_literals(&pairs, ctx);
+                let expression = PRATT_PARSER
+                    .map_primary(|primary| Self::map_primary(primary, &negative, ctx))
+                    .map_infix(|lhs, op, rhs| Self::map_infix(lhs, op, rhs, ctx))
+                    .map_postfix(|lhs, op| Self::map_postfix(lhs, op, ctx))
+                    .map_prefix(|op, rhs| Self::map_prefix(op, rhs, &negative, ctx))
+                    .parse(pairs.into_iter());
+                expression
             }
-            _ => PRATT_PARSER
-                .map_primary(Self::map_primary)
-                .map_infix(Self::map_infix)
-                .map_postfix(Self::map_postfix)
-                .map_prefix(Self::map_prefix)
-                .parse(line.into_inner()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::session::Session;
+
+    /// The message of every diagnostic reported while parsing `source`.
+    fn messages(source: &str) -> Vec<String> {
+        Session::new()
+            .parse_str_partial(source)
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn do_needs_a_default_branch() {
+        assert_eq!(
+            messages("fn f (x int) => do {\n    x > 0 => 1\n}\n"),
+            ["`do` expression has no `_` branch"]
+        );
+    }
+
+    #[test]
+    fn do_reports_extra_default_branches() {
+        let source = "fn f (x int) => do {\n    _ => 1\n    x > 0 => 2\n    _ => 3\n}\n";
+        assert_eq!(
+            messages(source),
+            [
+                "unreachable `do` branch",
+                "`do` expression has more than one `_` branch"
+            ]
+        );
+    }
+}
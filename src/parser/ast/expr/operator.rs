@@ -1,15 +1,18 @@
+use std::fmt::Display;
+
 use log::trace;
 use pest::iterators::Pair;
+use serde::Serialize;
 
 use crate::{
     parser::{
-        ast::Parse,
+        ast::{context::ParseContext, Parse},
         error::{ParseError, ParseResult},
     },
     Rule,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Operator {
     // add
     Add,
@@ -59,14 +62,56 @@ pub enum Operator {
     Assign,
     // comma
     Comma,
+    // range | range_to | range_from
+    Range,
+    // range_inclusive | range_to_inclusive
+    RangeInclusive,
 }
 
+impl Operator {
+    /// The source text of this operator.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Pow => "**",
+            Self::Mod => "%",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::BitAnd => "&",
+            Self::BitOr => "|",
+            Self::BitXor => "^",
+            Self::FieldAccess => ".",
+            Self::Eq => "==",
+            Self::Neq => "!=",
+            Self::Greater => ">",
+            Self::Lesser => "<",
+            Self::GreaterEq => ">=",
+            Self::LesserEq => "<=",
+            Self::Inc => "++",
+            Self::Dec => "--",
+            Self::Not => "!",
+            Self::BitNot => "~",
+            Self::Assign => "=",
+            Self::Comma => ",",
+            Self::Range => "..",
+            Self::RangeInclusive => "..=",
+        }
+    }
+}
+impl Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
 impl Parse for Operator {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, _ctx: &ParseContext) -> ParseResult<Self> {
         trace!("create-operator({:?})", line.as_rule());
         match line.as_rule() {
-            Rule::add => Ok(Self::Add),
-            Rule::subtract => Ok(Self::Subtract),
+            Rule::add | Rule::plus => Ok(Self::Add),
+            Rule::subtract | Rule::minus => Ok(Self::Subtract),
             Rule::multiply => Ok(Self::Multiply),
             Rule::divide => Ok(Self::Divide),
             Rule::pow => Ok(Self::Pow),
@@ -89,39 +134,46 @@ impl Parse for Operator {
             Rule::bit_not => Ok(Self::BitNot),
             Rule::assign => Ok(Self::Assign),
             Rule::comma => Ok(Self::Comma),
-            rule => {
-                return Err(ParseError::InvalidRuleErrorOneOf {
-                    expected: vec![
-                        Rule::add,
-                        Rule::subtract,
-                        Rule::multiply,
-                        Rule::divide,
-                        Rule::pow,
-                        Rule::r#mod,
-                        Rule::and,
-                        Rule::or,
-                        Rule::bit_and,
-                        Rule::bit_or,
-                        Rule::bit_xor,
-                        Rule::field_access,
-                        Rule::eq,
-                        Rule::neq,
-                        Rule::greater,
-                        Rule::lesser,
-                        Rule::greater_eq,
-                        Rule::lesser_eq,
-                        Rule::inc,
-                        Rule::post_inc,
-                        Rule::dec,
-                        Rule::post_dec,
-                        Rule::not,
-                        Rule::bit_not,
-                        Rule::assign,
-                        Rule::comma,
-                    ],
-                    actual: rule,
-                })
-            }
+            Rule::range | Rule::range_to | Rule::range_from => Ok(Self::Range),
+            Rule::range_inclusive | Rule::range_to_inclusive => Ok(Self::RangeInclusive),
+            rule => Err(ParseError::InvalidRuleErrorOneOf {
+                expected: vec![
+                    Rule::add,
+                    Rule::plus,
+                    Rule::subtract,
+                    Rule::minus,
+                    Rule::multiply,
+                    Rule::divide,
+                    Rule::pow,
+                    Rule::r#mod,
+                    Rule::and,
+                    Rule::or,
+                    Rule::bit_and,
+                    Rule::bit_or,
+                    Rule::bit_xor,
+                    Rule::field_access,
+                    Rule::eq,
+                    Rule::neq,
+                    Rule::greater,
+                    Rule::lesser,
+                    Rule::greater_eq,
+                    Rule::lesser_eq,
+                    Rule::inc,
+                    Rule::post_inc,
+                    Rule::dec,
+                    Rule::post_dec,
+                    Rule::not,
+                    Rule::bit_not,
+                    Rule::assign,
+                    Rule::comma,
+                    Rule::range,
+                    Rule::range_to,
+                    Rule::range_from,
+                    Rule::range_inclusive,
+                    Rule::range_to_inclusive,
+                ],
+                actual: rule,
+            }),
         }
     }
 }
@@ -8,6 +8,12 @@ lazy_static::lazy_static! {
         PrattParser::new()
             .op(Op::infix(Rule::comma, Left))
             .op(Op::infix(Rule::assign, Right))
+            .op(Op::infix(Rule::range, Left)
+                | Op::infix(Rule::range_inclusive, Left)
+                | Op::prefix(Rule::range_to)
+                | Op::prefix(Rule::range_to_inclusive)
+                | Op::postfix(Rule::range_from))
+
             .op(Op::infix(Rule::or, Left))
             .op(Op::infix(Rule::and, Left))
             .op(Op::infix(Rule::bit_or, Left))
@@ -25,20 +31,23 @@ lazy_static::lazy_static! {
                 | Op::infix(Rule::subtract, Left))
 
             .op(Op::infix(Rule::multiply, Left)
-                | Op::infix(Rule::divide, Left))
+                | Op::infix(Rule::divide, Left)
+                | Op::infix(Rule::r#mod, Left))
 
-            .op(Op::infix(Rule::pow, Right))
+            // `-2 ** 2` is `-(2 ** 2)`
             .op(Op::prefix(Rule::inc)
                 | Op::prefix(Rule::dec)
                 | Op::prefix(Rule::plus)
                 | Op::prefix(Rule::minus)
                 | Op::prefix(Rule::not)
                 | Op::prefix(Rule::bit_not))
+            .op(Op::infix(Rule::pow, Right))
 
             .op(Op::postfix(Rule::post_inc)
                 | Op::postfix(Rule::post_dec)
                 | Op::infix(Rule::field_access, Left)
-                | Op::postfix(Rule::call_params))
+                | Op::postfix(Rule::call_params)
+                | Op::postfix(Rule::pipe))
 
     };
 }
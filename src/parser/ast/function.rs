@@ -1,19 +1,70 @@
 use log::trace;
 use pest::iterators::Pair;
+use serde::Serialize;
 
 use crate::{next, parser::error::ParseResult, validate_rule, Rule};
 
-use super::{function_parameter::FunctionParameter, ident::Ident, statement::Statement, Parse};
+use super::{
+    context::ParseContext,
+    function_parameter::FunctionParameter,
+    ident::Ident,
+    span,
+    statement::Statement,
+    type_expr::{parse_type_params, TypeExpr},
+    Parse, Span,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Function {
+    /// The `///` comment lines above the function.
+    pub docs: Vec<String>,
     pub func_name: Ident,
+    /// The type parameters in `fn first<T> (xs T*) T`.
+    pub generics: Vec<Ident>,
     pub params: Vec<FunctionParameter>,
-    pub return_type: Option<Ident>,
+    pub return_type: Option<TypeExpr>,
     pub body: Vec<Statement>,
+    /// The whole definition, from `fn` to the end of its body.
+    pub span: Span,
+}
+impl Function {
+    /// Whether every call `other` accepts is already accepted by `self`.
+    pub fn subsumes(&self, other: &Self) -> bool {
+        self.params.len() == other.params.len()
+            && self
+                .params
+                .iter()
+                .zip(&other.params)
+                .all(|(param, other)| param.subsumes(other))
+    }
+}
+
+/// Every top-level clause sharing one function name, in source order.
+///
+/// A call runs the first clause whose parameters match its arguments.
+#[derive(Debug, Clone)]
+pub struct FunctionDefinition<'a> {
+    pub name: String,
+    pub clauses: Vec<&'a Function>,
+}
+impl<'a> FunctionDefinition<'a> {
+    /// Clauses that can never be selected, each paired with the earlier
+    /// clause that matches every call it would.
+    pub fn unreachable_clauses(&self) -> Vec<(&'a Function, &'a Function)> {
+        self.clauses
+            .iter()
+            .enumerate()
+            .filter_map(|(index, clause)| {
+                self.clauses[..index]
+                    .iter()
+                    .find(|earlier| earlier.subsumes(clause))
+                    .map(|earlier| (*clause, *earlier))
+            })
+            .collect()
+    }
 }
 impl Parse for Function {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         trace!("[Start] parse-function");
 
         let rule = line.as_rule();
@@ -22,42 +73,52 @@ impl Parse for Function {
         trace!("[EndOf:1] validate-rule");
 
         trace!("[Start:2] get-rules");
+        let span = span(&line, ctx);
         let mut rules = line.into_inner();
+        let docs = ctx.docs_before(span.start);
         let ident = next!(rules, "function(ident)");
+        let generics = match rules.peek() {
+            Some(generics) if generics.as_rule() == Rule::type_params => {
+                rules.next();
+                parse_type_params(generics, ctx)?
+            }
+            _ => vec![],
+        };
         let params = next!(rules, "function(params)");
         let maybe_return_type = next!(rules, "function(return-type-or-body)");
-        let (return_type, body) = if matches!(maybe_return_type.as_rule(), Rule::ident) {
+        let (return_type, body) = if matches!(maybe_return_type.as_rule(), Rule::type_expr) {
             (Some(maybe_return_type), next!(rules, "function(body)"))
         } else {
             (None, maybe_return_type)
         };
         trace!("[EndOf:2] get-rules");
 
-        trace!("[Start:3] parse-params");
-        let params = params.into_inner();
-        let params = params
-            .map(FunctionParameter::parse)
-            .collect::<ParseResult<Vec<_>>>()?;
-        trace!("[EndOf:3] parse-params");
-
-        trace!("[Start:4] parse-body");
-        validate_rule!(body.as_rule(), stmts);
-        let body = body.into_inner();
-        let body = body
-            .map(Statement::parse)
-            .collect::<ParseResult<Vec<_>>>()?;
-        trace!("[EndOf:4] parse-body");
-
-        trace!("[Start:5] construct-function");
-        let function = Self {
-            func_name: Ident::parse(ident)?,
-            params,
-            return_type: return_type.map(Ident::parse).transpose()?,
-            body,
-        };
-        trace!("[EndOf:5] construct-function");
+        ctx.with_type_params(&generics, || {
+            trace!("[Start:3] parse-params");
+            let params = params.into_inner();
+            let params = params
+                .map(|param| FunctionParameter::parse(param, ctx))
+                .collect::<ParseResult<Vec<_>>>()?;
+            trace!("[EndOf:3] parse-params");
+
+            trace!("[Start:4] parse-body");
+            let body = Statement::parse_block(body, ctx)?;
+            trace!("[EndOf:4] parse-body");
+
+            trace!("[Start:5] construct-function");
+            let function = Self {
+                docs,
+                func_name: Ident::parse(ident, ctx)?,
+                generics: generics.clone(),
+                params,
+                return_type: return_type.map(|ty| TypeExpr::parse(ty, ctx)).transpose()?,
+                body,
+                span,
+            };
+            trace!("[EndOf:5] construct-function");
 
-        trace!("[EndOf] parse-function");
-        Ok(function)
+            trace!("[EndOf] parse-function");
+            Ok(function)
+        })
     }
 }
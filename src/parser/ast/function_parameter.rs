@@ -1,19 +1,170 @@
 use log::trace;
 use pest::iterators::Pair;
+use serde::Serialize;
 
-use crate::{next, parser::error::ParseResult, validate_rule, Rule};
+use crate::{
+    next,
+    parser::error::{ParseError, ParseResult},
+    validate_rule, Rule,
+};
 
-use super::{ident::Ident, Parse};
+use super::{
+    context::ParseContext,
+    expr::literal::Literal,
+    ident::Ident,
+    span,
+    type_expr::{is_plain_name, TypeExpr},
+    Parse, Span,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub enum FunctionParameter {
-    NamedAndTyped { name: Ident, ty: Ident },
-    NamedDynamic { name: Ident },
-    Anonymous { ty: Ident },
+    NamedAndTyped {
+        name: Ident,
+        ty: TypeExpr,
+    },
+    NamedDynamic {
+        name: Ident,
+    },
+    Anonymous {
+        ty: TypeExpr,
+    },
+    /// Only matches arguments equal to `literal`.
+    Literal {
+        literal: Literal,
+        span: Span,
+    },
+    /// `_`: matches any argument without binding it.
+    Wildcard {
+        span: Span,
+    },
+    /// `head:tail`: matches a non-empty sequence, binding its first element
+    /// and the rest.
+    Cons {
+        head: Ident,
+        tail: Ident,
+        ty: Option<TypeExpr>,
+        span: Span,
+    },
+    /// `[]`: only matches an empty sequence.
+    Empty {
+        ty: Option<TypeExpr>,
+        span: Span,
+    },
+    /// `Circle(r)`: matches a value of that variant whose fields match
+    /// `fields`. A variant without fields is written without parentheses.
+    Variant {
+        name: Ident,
+        fields: Vec<FunctionParameter>,
+        span: Span,
+    },
+}
+impl FunctionParameter {
+    /// The type annotation on this parameter, if any.
+    pub fn ty(&self) -> Option<&TypeExpr> {
+        match self {
+            Self::NamedAndTyped { ty, .. } | Self::Anonymous { ty } => Some(ty),
+            Self::Cons { ty, .. } | Self::Empty { ty, .. } => ty.as_ref(),
+            Self::NamedDynamic { .. }
+            | Self::Literal { .. }
+            | Self::Wildcard { .. }
+            | Self::Variant { .. } => None,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Self::NamedAndTyped { name, .. } | Self::NamedDynamic { name } => name.span(),
+            Self::Anonymous { ty } => ty.span(),
+            Self::Literal { span, .. }
+            | Self::Wildcard { span }
+            | Self::Cons { span, .. }
+            | Self::Empty { span, .. }
+            | Self::Variant { span, .. } => span.clone(),
+        }
+    }
+
+    /// The names this parameter binds, in order.
+    pub fn bindings(&self) -> Vec<&Ident> {
+        match self {
+            Self::NamedAndTyped { name, .. } | Self::NamedDynamic { name } => vec![name],
+            Self::Cons { head, tail, .. } => vec![head, tail],
+            Self::Variant { fields, .. } => fields.iter().flat_map(Self::bindings).collect(),
+            Self::Anonymous { .. }
+            | Self::Literal { .. }
+            | Self::Wildcard { .. }
+            | Self::Empty { .. } => vec![],
+        }
+    }
+
+    /// The type this parameter restricts its argument to, if any.
+    pub fn type_name(&self) -> Option<String> {
+        match self {
+            Self::NamedAndTyped { ty, .. } | Self::Anonymous { ty } => Some(ty.to_string()),
+            Self::Literal { literal, .. } => Some(literal.type_name().to_owned()),
+            Self::Cons { ty, .. } | Self::Empty { ty, .. } => ty.as_ref().map(TypeExpr::to_string),
+            Self::NamedDynamic { .. } | Self::Wildcard { .. } | Self::Variant { .. } => None,
+        }
+    }
+
+    /// Whether every argument `other` accepts is also accepted by `self`.
+    pub fn subsumes(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::NamedDynamic { .. } | Self::Wildcard { .. }, _) => true,
+            (Self::Literal { literal, .. }, Self::Literal { literal: other, .. }) => {
+                literal == other
+            }
+            (Self::Literal { .. }, _) => false,
+            (Self::Cons { .. }, Self::Cons { .. }) | (Self::Empty { .. }, Self::Empty { .. }) => {
+                self.type_name().is_none() || self.type_name() == other.type_name()
+            }
+            (Self::Cons { .. } | Self::Empty { .. }, _) => false,
+            (
+                Self::Variant { name, fields, .. },
+                Self::Variant {
+                    name: other,
+                    fields: others,
+                    ..
+                },
+            ) => {
+                name.to_string() == other.to_string()
+                    && fields.len() == others.len()
+                    && fields.iter().zip(others).all(|(a, b)| a.subsumes(b))
+            }
+            (Self::Variant { .. }, _) | (_, Self::Variant { .. }) => false,
+            _ => self.type_name().is_some() && self.type_name() == other.type_name(),
+        }
+    }
+}
+
+impl FunctionParameter {
+    /// A variant pattern, checked against the variant's definition.
+    fn variant(
+        name: Ident,
+        fields: Vec<Self>,
+        span: Span,
+        ctx: &ParseContext,
+    ) -> ParseResult<Self> {
+        let Some(info) = ctx.variant(&name) else {
+            return Err(ParseError::UnknownVariant {
+                ident: name.to_string(),
+                span: name.span(),
+            });
+        };
+        if info.arity != fields.len() {
+            return Err(ParseError::VariantArity {
+                expected: info.arity,
+                actual: fields.len(),
+                span,
+                definition: info.span,
+            });
+        }
+        Ok(Self::Variant { name, fields, span })
+    }
 }
 
 impl Parse for FunctionParameter {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         trace!("[Start] parse-parameter");
 
         let rule = line.as_rule();
@@ -23,28 +174,85 @@ impl Parse for FunctionParameter {
         trace!("[EndOf:1] validate-rule");
 
         trace!("[Start:2] get-rules");
+        let span = span(&line, ctx);
         let mut rules = line.into_inner();
         let p1 = next!(rules, "function-parameter(arg1)");
-        let p1 = Ident::parse(p1)?;
-
-        let (name, ty) = if p1.is_type() {
-            // type
-            (None, Some(p1))
-        } else {
-            if let Some(p2) = rules.next() {
-                // name type
-                let p2 = Ident::parse_expect_type(p2)?;
-                (Some(p1), Some(p2))
+        if matches!(p1.as_rule(), Rule::literal | Rule::minus) {
+            let negative = p1.as_rule() == Rule::minus;
+            let p1 = if negative {
+                next!(rules, "function-parameter(negative-literal)")
             } else {
+                p1
+            };
+            let literal = next!(p1.into_inner(), "function-parameter(literal)");
+            let literal = if negative {
+                Literal::parse_negative(literal, ctx)?
+            } else {
+                Literal::parse(literal, ctx)?
+            };
+            trace!("[EndOf:2] get-rules");
+            trace!("[EndOf] parse-parameter");
+            return Ok(Self::Literal { literal, span });
+        }
+        if p1.as_rule() == Rule::variant_pattern {
+            let mut parts = p1.into_inner();
+            let name = next!(parts, "function-parameter(variant-name)");
+            let fields = parts
+                .map(|field| Self::parse(field, ctx))
+                .collect::<ParseResult<Vec<_>>>()?;
+            trace!("[EndOf:2] get-rules");
+            trace!("[EndOf] parse-parameter");
+            return Self::variant(Ident::parse(name, ctx)?, fields, span, ctx);
+        }
+        if matches!(p1.as_rule(), Rule::cons_pattern | Rule::empty_pattern) {
+            let ty = rules
+                .next()
+                .map(|ty| TypeExpr::parse(ty, ctx))
+                .transpose()?;
+            let parameter = match p1.as_rule() {
+                Rule::cons_pattern => {
+                    let mut names = p1.into_inner();
+                    let head = next!(names, "function-parameter(cons-head)");
+                    let tail = next!(names, "function-parameter(cons-tail)");
+                    Self::Cons {
+                        head: Ident::parse(head, ctx)?,
+                        tail: Ident::parse(tail, ctx)?,
+                        ty,
+                        span,
+                    }
+                }
+                _ => Self::Empty { ty, span },
+            };
+            trace!("[EndOf:2] get-rules");
+            trace!("[EndOf] parse-parameter");
+            return Ok(parameter);
+        }
+        let (name, ty) = match p1.as_rule() {
+            Rule::ident => {
+                // name type
+                let ty = next!(rules, "function-parameter(type)");
+                (
+                    Some(Ident::parse(p1, ctx)?),
+                    Some(TypeExpr::parse(ty, ctx)?),
+                )
+            }
+            _ if is_plain_name(&p1, ctx) => {
                 // name
-                (Some(p1), None)
+                let name = next!(p1.into_inner(), "function-parameter(name)");
+                (Some(Ident::parse(name, ctx)?), None)
             }
+            // type
+            _ => (None, Some(TypeExpr::parse(p1, ctx)?)),
         };
 
         trace!("[EndOf:2] get-rules");
 
         trace!("[Start:3] construct-parameter");
         let parameter = match (name, ty) {
+            (Some(name), None) if name.to_string() == "_" => Self::Wildcard { span },
+            (Some(name), None) if ctx.variant(&name).is_some() => {
+                return Self::variant(name, vec![], span, ctx)
+            }
             (Some(name), Some(ty)) => Self::NamedAndTyped { name, ty },
             (Some(name), None) => Self::NamedDynamic { name },
             (None, Some(ty)) => Self::Anonymous { ty },
@@ -56,3 +264,58 @@ impl Parse for FunctionParameter {
         Ok(parameter)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_str;
+
+    use super::FunctionParameter;
+
+    /// The parameters of a function taking `params`.
+    fn params(params: &str) -> Vec<FunctionParameter> {
+        let source = format!("type O = Some(int) | None\n\nfn f ({}) => 1\n", params);
+        let mut module = parse_str(&source).expect("source parses");
+        module.functions.remove(0).params
+    }
+
+    /// Whether the first of `params` subsumes the second.
+    fn subsumes(params: &str) -> bool {
+        let params = self::params(params);
+        params[0].subsumes(&params[1])
+    }
+
+    #[test]
+    fn literals_subsume_only_themselves() {
+        assert!(subsumes("1, 1"));
+        assert!(!subsumes("1, 2"));
+        assert!(!subsumes("1, n int"));
+        assert!(!subsumes("1, n"));
+    }
+
+    #[test]
+    fn typed_parameters_subsume_literals_of_their_type() {
+        assert!(subsumes("n int, 1"));
+        assert!(subsumes("n int, m int"));
+        assert!(subsumes("int, m int"));
+        assert!(!subsumes("n int, \"a\""));
+        assert!(!subsumes("n int, s str"));
+        assert!(!subsumes("n int, m"));
+    }
+
+    #[test]
+    fn dynamic_parameters_subsume_everything() {
+        for other in ["1", "n int", "m", "_", "x:xs", "[]", "Some(1)"] {
+            assert!(subsumes(&format!("n, {}", other)), "{}", other);
+            assert!(subsumes(&format!("_, {}", other)), "{}", other);
+        }
+    }
+
+    #[test]
+    fn patterns_subsume_narrower_patterns() {
+        assert!(subsumes("x:xs, y:ys int[]"));
+        assert!(!subsumes("x:xs int[], y:ys"));
+        assert!(subsumes("Some(n), Some(1)"));
+        assert!(!subsumes("Some(1), Some(n)"));
+        assert!(!subsumes("Some(n), None"));
+    }
+}
@@ -1,9 +1,12 @@
-use log::{error, trace};
+use serde::Serialize;
+use std::fmt::Display;
+
+use log::trace;
 use pest::iterators::Pair;
 
 use crate::{
     parser::{
-        ast::{context::ParseContext, span},
+        ast::{context::ParseContext, keyword::Keyword, span},
         error::{bad_fromstr, ParseError, ParseResult},
     },
     Rule,
@@ -11,7 +14,7 @@ use crate::{
 
 use super::{Parse, Span};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum ReservedIdent {
     Slf,
 }
@@ -24,14 +27,14 @@ impl TryFrom<String> for ReservedIdent {
     type Error = ParseError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "self" => Ok(Self::Slf),
+        match Keyword::lookup(&value) {
+            Some(Keyword::Slf) => Ok(Self::Slf),
             _ => Err(bad_fromstr(value, "reserved-ident-tryfrom")),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub enum Ident {
     Identifier {
         name: String,
@@ -51,18 +54,27 @@ pub enum Ident {
         ident: ReservedIdent,
     },
 }
-impl ToString for Ident {
-    fn to_string(&self) -> String {
+impl Display for Ident {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Identifier { name, .. } => name.clone(),
-            Self::Type { name, .. } => name.clone(),
-            Self::Native { name, .. } => name.clone(),
-            Self::Reserved { name, .. } => name.clone(),
+            // Only raw identifiers can be named after a keyword
+            Self::Identifier { name, .. } if Keyword::lookup(name).is_some_and(needs_raw) => {
+                write!(f, "r#{}", name)
+            }
+            Self::Identifier { name, .. } => write!(f, "{}", name),
+            Self::Type { name, .. } => write!(f, "{}", name),
+            Self::Native { name, .. } => write!(f, "{}", name),
+            Self::Reserved { name, .. } => write!(f, "{}", name),
         }
     }
 }
+/// Whether an identifier spelled like `keyword` must be written `r#keyword`.
+fn needs_raw(keyword: Keyword) -> bool {
+    !keyword.is_contextual() || keyword == Keyword::Slf
+}
+
 impl Ident {
-    fn ident(name: String, span: Span) -> ParseResult<Self> {
+    fn from_name(name: String, span: Span) -> ParseResult<Self> {
         if ReservedIdent::is_reserved(&name) {
             Ok(Self::Reserved {
                 name: name.clone(),
@@ -91,22 +103,22 @@ impl Ident {
         }
     }
 
-    pub fn parse_expect_type(line: Pair<Rule>) -> ParseResult<Self> {
-        error!("[Start] parse-ident-expect-type");
-        let this = Self::parse(line)?;
+    pub fn parse_expect_type(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
+        trace!("[Start] parse-ident-expect-type");
+        let this = Self::parse(line, ctx)?;
         if !this.is_type() {
-            error!("[EndOf] parse-ident-expect-type: invalid ident kind");
+            trace!("[EndOf] parse-ident-expect-type: invalid ident kind");
             return Err(ParseError::ExpectedType {
                 ident: this.to_string(),
                 span: this.span(),
             });
         }
-        error!("[EndOf] parse-ident-expect-type");
+        trace!("[EndOf] parse-ident-expect-type");
         Ok(this)
     }
 }
 impl Parse for Ident {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         trace!("[Start] parse-ident");
 
         let rule = line.as_rule();
@@ -122,7 +134,7 @@ impl Parse for Ident {
             trace!("[EndOf] parse-ident");
             Ok(Self::Native {
                 name: ident,
-                span: span(&line),
+                span: span(&line, ctx),
             })
         } else if matches!(rule, Rule::ID_anon) {
             trace!("[EndOf:1] validate-rule (ID_anon)");
@@ -132,33 +144,52 @@ impl Parse for Ident {
             trace!("[EndOf:2] construct-anonymous");
 
             trace!("[EndOf] parse-ident");
-            Ok(Self::ident(ident, span(&line))?)
+            Ok(Self::from_name(ident, span(&line, ctx))?)
         } else if matches!(rule, Rule::ident) {
             trace!("[EndOf:1] validate-rule (ident)");
 
             trace!("[Start:2] get-name");
-            let name = line.as_str().to_owned();
+            let text = line.as_str();
+            let (name, raw) = match text.strip_prefix("r#") {
+                Some(name) => (name.to_owned(), true),
+                None => (text.to_owned(), false),
+            };
+            if let Some(keyword) = Keyword::strict(&name).filter(|_| !raw) {
+                trace!("[EndOf:2] get-name: keyword");
+                return Err(ParseError::KeywordAsIdentifier {
+                    keyword: keyword.as_str(),
+                    span: span(&line, ctx),
+                });
+            }
+            if raw && Keyword::lookup(&name).is_some() {
+                trace!("[EndOf:2] get-name: raw keyword");
+                trace!("[EndOf] parse-ident");
+                return Ok(Self::Identifier {
+                    name,
+                    span: span(&line, ctx),
+                });
+            }
             trace!("[EndOf:2] get-name");
 
             trace!("[Start:2] get-kind");
-            let type_information = ParseContext::is_type(&name)?;
+            let type_information = ctx.is_type(&name);
             let name = match type_information {
                 None => {
                     trace!("[EndOf:2] get-kind:Identifier");
-                    Self::ident(name, span(&line))?
+                    Self::from_name(name, span(&line, ctx))?
                 }
                 Some(info) if info.is_native => {
                     trace!("[EndOf:2] get-kind:Native");
                     Self::Native {
                         name,
-                        span: span(&line),
+                        span: span(&line, ctx),
                     }
                 }
                 Some(_) => {
                     trace!("[EndOf:2] get-kind:Type");
                     Self::Type {
                         name,
-                        span: span(&line),
+                        span: span(&line, ctx),
                     }
                 }
             };
@@ -170,10 +201,10 @@ impl Parse for Ident {
                 "[EndOf] invalid-rule: Expected ident or native, got {:?}",
                 rule
             );
-            return ParseResult::Err(ParseError::InvalidRuleError {
+            ParseResult::Err(ParseError::InvalidRuleError {
                 expected: Rule::ident,
                 actual: rule,
-            });
+            })
         }
     }
 }
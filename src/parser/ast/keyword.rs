@@ -0,0 +1,139 @@
+use serde::Serialize;
+
+/// Every word with a meaning of its own in the language.
+///
+/// Strict keywords can only be used as names when written as raw
+/// identifiers (`r#type`). Contextual keywords are only special in some
+/// positions and are ordinary identifiers everywhere else. The lists mirror
+/// the grammar's `strict_keyword` and `contextual_keyword` rules; the tests
+/// below keep them in step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Keyword {
+    Let,
+    Fn,
+    Type,
+    If,
+    Else,
+    True,
+    False,
+    /// `do { ... }`
+    Do,
+    /// The receiver of a method.
+    Slf,
+}
+impl Keyword {
+    pub const ALL: [Self; 9] = [
+        Self::Let,
+        Self::Fn,
+        Self::Type,
+        Self::If,
+        Self::Else,
+        Self::True,
+        Self::False,
+        Self::Do,
+        Self::Slf,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Let => "let",
+            Self::Fn => "fn",
+            Self::Type => "type",
+            Self::If => "if",
+            Self::Else => "else",
+            Self::True => "true",
+            Self::False => "false",
+            Self::Do => "do",
+            Self::Slf => "self",
+        }
+    }
+
+    pub fn is_contextual(self) -> bool {
+        matches!(self, Self::Do | Self::Slf)
+    }
+
+    /// The keyword spelled `word`, if any.
+    pub fn lookup(word: &str) -> Option<Self> {
+        Some(match word {
+            "let" => Self::Let,
+            "fn" => Self::Fn,
+            "type" => Self::Type,
+            "if" => Self::If,
+            "else" => Self::Else,
+            "true" => Self::True,
+            "false" => Self::False,
+            "do" => Self::Do,
+            "self" => Self::Slf,
+            _ => return None,
+        })
+    }
+
+    /// The strict keyword spelled `word`, if any.
+    pub fn strict(word: &str) -> Option<Self> {
+        Self::lookup(word).filter(|keyword| !keyword.is_contextual())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pest::Parser;
+
+    use crate::{FNSParser, Rule};
+
+    use super::*;
+
+    /// Whether the grammar's `rule` matches all of `word`.
+    fn matches_whole(rule: Rule, word: &str) -> bool {
+        FNSParser::parse(rule, word)
+            .ok()
+            .and_then(|mut pairs| pairs.next())
+            .is_some_and(|pair| pair.as_str() == word)
+    }
+
+    /// The quoted words of the grammar rule `rule`.
+    fn grammar_words(rule: &str) -> Vec<&'static str> {
+        let header = format!("{} =", rule);
+        include_str!("../../fns.pest")
+            .lines()
+            .find(|line| line.starts_with(&header))
+            .expect("rule is in the grammar")
+            .split('"')
+            .skip(1)
+            .step_by(2)
+            .collect()
+    }
+
+    #[test]
+    fn keywords_agree_with_the_grammar() {
+        for keyword in Keyword::ALL {
+            let word = keyword.as_str();
+            assert_eq!(Keyword::lookup(word), Some(keyword));
+            assert_eq!(
+                matches_whole(Rule::contextual_keyword, word),
+                keyword.is_contextual(),
+                "{}",
+                word
+            );
+            assert_eq!(
+                matches_whole(Rule::strict_keyword, word),
+                !keyword.is_contextual(),
+                "{}",
+                word
+            );
+        }
+        for word in grammar_words("strict_keyword") {
+            assert!(Keyword::strict(word).is_some(), "{}", word);
+        }
+        for word in grammar_words("contextual_keyword") {
+            assert!(Keyword::lookup(word).is_some_and(Keyword::is_contextual));
+        }
+        let strict = Keyword::ALL.map(|keyword| Keyword::strict(keyword.as_str()).is_some());
+        assert_eq!(
+            strict,
+            [true, true, true, true, true, true, true, false, false]
+        );
+        for word in ["letter", "iffy", "fnord", "done", "selfish", "r#type", ""] {
+            assert_eq!(Keyword::lookup(word), None, "{}", word);
+        }
+    }
+}
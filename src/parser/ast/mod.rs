@@ -1,6 +1,12 @@
 use pest::iterators::Pair;
+use serde::Serialize;
 
-use crate::Rule;
+use crate::{
+    diagnostic::source::{FileId, LineCol, SourceMap},
+    Rule,
+};
+
+use self::context::ParseContext;
 
 use super::error::ParseResult;
 
@@ -9,25 +15,36 @@ pub mod expr;
 pub mod function;
 pub mod function_parameter;
 pub mod ident;
+pub mod keyword;
+pub mod module;
 pub mod statement;
 pub mod type_definition;
+pub mod type_expr;
 
 pub trait Parse: Sized {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self>;
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self>;
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct Span {
     pub content: String,
     pub start: usize,
     pub end: usize,
+    pub file: FileId,
+}
+impl Span {
+    /// The start and end line/column of this span, if its file is known to `sources`.
+    pub fn location(&self, sources: &SourceMap) -> Option<(LineCol, LineCol)> {
+        sources.location(self)
+    }
 }
 
-pub fn span(line: &Pair<Rule>) -> Span {
+pub fn span(line: &Pair<Rule>, ctx: &ParseContext) -> Span {
     let s = line.as_span();
     Span {
         content: s.as_str().to_owned(),
         end: s.end(),
         start: s.start(),
+        file: ctx.file,
     }
 }
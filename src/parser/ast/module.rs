@@ -0,0 +1,265 @@
+use log::trace;
+use pest::{iterators::Pair, Parser};
+use serde::Serialize;
+
+use crate::{
+    diagnostic::{Diagnostic, Label},
+    next,
+    parser::error::{recovered_syntax_error, ParseResult},
+    validate_rule, FNSParser, Rule,
+};
+
+use super::{
+    context::{ParseContext, TypeInformation, VariantInformation},
+    function::{Function, FunctionDefinition},
+    span,
+    type_definition::TypeDefinition,
+    Parse, Span,
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Module {
+    pub functions: Vec<Function>,
+    pub types: Vec<TypeDefinition>,
+    /// Placeholders for top-level items that failed to parse, one span per
+    /// item. Like `functions` and `types`, these are kept by kind so later
+    /// passes can walk the items they care about; [`Module::items`] puts all
+    /// three back in source order.
+    pub errors: Vec<Span>,
+    /// Every comment in the file, in order, except the doc comments kept on
+    /// the items they document.
+    pub comments: Vec<Comment>,
+}
+
+/// A top-level item of a [`Module`], as listed by [`Module::items`].
+#[derive(Debug, Clone, Copy)]
+pub enum Item<'a> {
+    Function(&'a Function),
+    Type(&'a TypeDefinition),
+    /// An item that failed to parse, kept as its source text.
+    Error(&'a Span),
+}
+impl Item<'_> {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Function(function) => &function.span,
+            Self::Type(ty) => &ty.span,
+            Self::Error(span) => span,
+        }
+    }
+}
+
+/// A `//`, `///` or `/* */` comment.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comment {
+    pub span: Span,
+    /// Whether code comes before the comment on its line.
+    pub trailing: bool,
+    /// Whether a blank line comes after the comment.
+    pub detached: bool,
+}
+impl Comment {
+    /// The text of a `///` comment, without the slashes and the space after
+    /// them.
+    pub fn doc(&self) -> Option<&str> {
+        let text = self.span.content.strip_prefix("///")?;
+        match text.starts_with('/') {
+            true => None,
+            false => Some(text.strip_prefix(' ').unwrap_or(text)),
+        }
+    }
+}
+
+impl Module {
+    /// Finds the comments in `source`.
+    pub fn find_comments(source: &str, ctx: &ParseContext) -> Vec<Comment> {
+        let Ok(mut rules) = FNSParser::parse(Rule::comments, source) else {
+            return vec![];
+        };
+        rules
+            .next()
+            .into_iter()
+            .flat_map(Pair::into_inner)
+            .filter(|rule| matches!(rule.as_rule(), Rule::comment | Rule::doc_comment))
+            .map(|comment| {
+                let span = span(&comment, ctx);
+                let before = source[..span.start].rsplit('\n').next();
+                let after = source[span.end..].split('\n').nth(1);
+                Comment {
+                    trailing: before.is_some_and(|line| !line.trim().is_empty()),
+                    detached: after.is_some_and(|line| line.trim().is_empty()),
+                    span,
+                }
+            })
+            .collect()
+    }
+
+    /// Warns about doc comments no item claimed.
+    fn report_stray_docs(&self, ctx: &ParseContext) {
+        for comment in self.comments.iter().filter(|c| c.doc().is_some()) {
+            ctx.report(
+                Diagnostic::warning("doc comment does not document anything")
+                    .with_label(Label::primary(
+                        comment.span.clone(),
+                        "not followed by an item",
+                    ))
+                    .with_note("use `//` for an ordinary comment"),
+            );
+        }
+    }
+
+    /// Every top-level item, including those that failed to parse, in source
+    /// order.
+    pub fn items(&self) -> Vec<Item<'_>> {
+        let mut items = self
+            .functions
+            .iter()
+            .map(Item::Function)
+            .chain(self.types.iter().map(Item::Type))
+            .chain(self.errors.iter().map(Item::Error))
+            .collect::<Vec<_>>();
+        items.sort_by_key(|item| item.span().start);
+        items
+    }
+
+    /// Groups `functions` by name, in order of first appearance.
+    pub fn definitions(&self) -> Vec<FunctionDefinition<'_>> {
+        let mut definitions: Vec<FunctionDefinition> = vec![];
+        for function in &self.functions {
+            let name = function.func_name.to_string();
+            match definitions.iter_mut().find(|def| def.name == name) {
+                Some(definition) => definition.clauses.push(function),
+                None => definitions.push(FunctionDefinition {
+                    name,
+                    clauses: vec![function],
+                }),
+            }
+        }
+        definitions
+    }
+
+    /// Warns about function clauses shadowed by an earlier, broader clause.
+    fn report_unreachable_clauses(&self, ctx: &ParseContext) {
+        for definition in self.definitions() {
+            for (clause, earlier) in definition.unreachable_clauses() {
+                ctx.report(
+                    Diagnostic::warning(format!("unreachable clause of `{}`", definition.name))
+                        .with_label(Label::primary(clause.func_name.span(), "never called"))
+                        .with_label(Label::secondary(
+                            earlier.func_name.span(),
+                            "this clause matches every call first",
+                        ))
+                        .with_note("clauses are tried in order; move more specific ones first"),
+                );
+            }
+        }
+    }
+
+    /// Registers the name of every `type_definition` in `file` with `ctx`,
+    /// along with the variants of sum types.
+    ///
+    /// This runs before any item is parsed, so types and variants may be
+    /// referenced before the point at which they are defined. Duplicate
+    /// definitions are reported to `ctx`; the first definition wins.
+    pub fn register_types(file: &Pair<Rule>, ctx: &mut ParseContext) -> ParseResult<()> {
+        trace!("[Start] register-types");
+
+        trace!("[Start:1] validate-rule");
+        validate_rule!(file.as_rule(), file);
+        trace!("[EndOf:1] validate-rule");
+
+        trace!("[Start:2] collect-type-names");
+        for item in file.clone().into_inner() {
+            if !matches!(item.as_rule(), Rule::type_definition) {
+                continue;
+            }
+
+            let mut rules = item.into_inner();
+            let name = next!(rules, "register-types(typedef-name)");
+            let generics = rules
+                .peek()
+                .filter(|rule| rule.as_rule() == Rule::type_params)
+                .map_or(0, |params| params.into_inner().count());
+            let info = TypeInformation {
+                is_native: false,
+                span: span(&name, ctx),
+                generics,
+            };
+            if let Err(e) = ctx.add_type(name.as_str().to_owned(), info) {
+                ctx.report(e.diagnostic());
+            }
+
+            for variant in rules.filter(|rule| rule.as_rule() == Rule::variant) {
+                let mut parts = variant.into_inner();
+                let ident = next!(parts, "register-types(variant-name)");
+                let info = VariantInformation {
+                    ty: name.as_str().to_owned(),
+                    arity: parts.count(),
+                    span: span(&ident, ctx),
+                };
+                if let Err(e) = ctx.add_variant(ident.as_str().to_owned(), info) {
+                    ctx.report(e.diagnostic());
+                }
+            }
+        }
+        trace!("[EndOf:2] collect-type-names");
+
+        trace!("[EndOf] register-types");
+        Ok(())
+    }
+}
+impl Parse for Module {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
+        trace!("[Start] parse-module");
+
+        trace!("[Start:1] validate-rule");
+        validate_rule!(line.as_rule(), file);
+        trace!("[EndOf:1] validate-rule");
+
+        trace!("[Start:2] parse-items");
+        let mut module = Self::default();
+        for item in line.into_inner() {
+            let item_span = span(&item, ctx);
+            let parsed = match item.as_rule() {
+                Rule::function => Function::parse(item, ctx).map(|f| module.functions.push(f)),
+                Rule::type_definition => {
+                    TypeDefinition::parse(item, ctx).map(|t| module.types.push(t))
+                }
+                Rule::error_item => {
+                    trace!("recover-item: syntax");
+                    let rule = if item.as_str().starts_with("type") {
+                        Rule::whole_type_definition
+                    } else {
+                        Rule::whole_function
+                    };
+                    // Its docs belong to the broken item, not to nothing
+                    ctx.docs_before(item_span.start);
+                    ctx.report(recovered_syntax_error(&item, rule, ctx));
+                    module.errors.push(item_span);
+                    continue;
+                }
+                Rule::EOI => continue,
+                _ => unreachable!(),
+            };
+
+            if let Err(e) = parsed {
+                trace!("recover-item: {}", e);
+                ctx.report(e.diagnostic());
+                module.errors.push(item_span);
+            }
+        }
+        trace!("[EndOf:2] parse-items");
+
+        trace!("[Start:3] check-clauses");
+        module.report_unreachable_clauses(ctx);
+        trace!("[EndOf:3] check-clauses");
+
+        trace!("[Start:4] collect-comments");
+        module.comments = ctx.unclaimed_comments();
+        module.report_stray_docs(ctx);
+        trace!("[EndOf:4] collect-comments");
+
+        trace!("[EndOf] parse-module");
+        Ok(module)
+    }
+}
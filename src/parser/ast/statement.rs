@@ -1,43 +1,147 @@
 use log::trace;
 use pest::iterators::Pair;
+use serde::Serialize;
 
 use crate::{
-    parser::error::{missing, ParseResult},
+    next,
+    parser::error::{missing, recovered_syntax_error, ParseResult},
     validate_rule, Rule,
 };
 
-use super::{expr::Expression, ident::Ident, Parse};
+use super::{
+    context::ParseContext, expr::Expression, ident::Ident, span, type_expr::TypeExpr, Parse, Span,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub enum Statement {
     Nop,
     Expression(Box<Expression>),
     // TODO: Maybe merge assn & decl
     Assignment {
         ident: Ident,
-        typ: Option<Ident>,
+        typ: Option<TypeExpr>,
         value: Box<Expression>,
     },
     Declaration {
         ident: Ident,
-        typ: Ident,
+        typ: TypeExpr,
+    },
+    If {
+        condition: Box<Expression>,
+        then_branch: Vec<Statement>,
+        else_ifs: Vec<ElseIf>,
+        else_branch: Option<Vec<Statement>>,
+        /// The `else { ... }` holding `else_branch`.
+        else_span: Option<Span>,
+        span: Span,
     },
+    /// Placeholder for a statement that failed to parse.
+    Error {
+        span: Span,
+    },
+}
+/// An `else if (condition) { ... }` arm of a [`Statement::If`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ElseIf {
+    pub condition: Box<Expression>,
+    pub body: Vec<Statement>,
+}
+
+impl Statement {
+    /// Parses the statements of a `stmts` block, recovering from bad ones.
+    pub fn parse_block(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Vec<Self>> {
+        validate_rule!(line.as_rule(), stmts);
+        Ok(line
+            .into_inner()
+            .map(|stmt| Self::parse_or_recover(stmt, ctx))
+            .collect())
+    }
+
+    /// The expression inside a `parenthesized_expr` condition.
+    fn parse_condition(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Box<Expression>> {
+        validate_rule!(line.as_rule(), parenthesized_expr);
+        Expression::parse_boxed(next!(line.into_inner(), "if(condition)"), ctx)
+    }
+
+    fn parse_if(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
+        trace!("[Start] statement:parse-if");
+        validate_rule!(line.as_rule(), conditional_statement);
+
+        let span = span(&line, ctx);
+        let mut rules = line.into_inner();
+        let condition = Self::parse_condition(next!(rules, "if(condition)"), ctx)?;
+        let then_branch = Self::parse_block(next!(rules, "if(then-branch)"), ctx)?;
+
+        let mut else_ifs = vec![];
+        let mut else_branch = None;
+        let mut else_span = None;
+        for rule in rules {
+            match rule.as_rule() {
+                Rule::else_if => {
+                    let mut rules = rule.into_inner();
+                    else_ifs.push(ElseIf {
+                        condition: Self::parse_condition(next!(rules, "else-if(condition)"), ctx)?,
+                        body: Self::parse_block(next!(rules, "else-if(body)"), ctx)?,
+                    });
+                }
+                _ => {
+                    validate_rule!(rule.as_rule(), else_branch);
+                    else_span = Some(super::span(&rule, ctx));
+                    let body = next!(rule.into_inner(), "else(body)");
+                    else_branch = Some(Self::parse_block(body, ctx)?);
+                }
+            }
+        }
+
+        trace!("[EndOf] statement:parse-if");
+        Ok(Self::If {
+            condition,
+            then_branch,
+            else_ifs,
+            else_branch,
+            else_span,
+            span,
+        })
+    }
+
+    /// Parses `line`, reporting any error to `ctx` and substituting
+    /// [`Statement::Error`] so the rest of the body can still be parsed.
+    pub fn parse_or_recover(line: Pair<Rule>, ctx: &ParseContext) -> Self {
+        let span = span(&line, ctx);
+
+        if matches!(line.as_rule(), Rule::error_stmt) {
+            trace!("recover-statement: syntax");
+            ctx.report(recovered_syntax_error(&line, Rule::whole_stmt, ctx));
+            return Self::Error { span };
+        }
+
+        match Self::parse(line, ctx) {
+            Ok(stmt) => stmt,
+            Err(e) => {
+                trace!("recover-statement: {}", e);
+                ctx.report(e.diagnostic());
+                Self::Error { span }
+            }
+        }
+    }
 }
 impl Parse for Statement {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         trace!("[Start] parse-statement");
 
         trace!("[Start:1] validate-rule");
-        validate_rule!(line.as_rule(), stmt, expr);
+        validate_rule!(line.as_rule(), stmt, expr, conditional_statement);
         trace!("[EndOf:1] validate-rule");
 
         let stmt = match line.as_rule() {
-            Rule::expr => Self::Expression(Expression::parse_boxed(line)?),
+            Rule::expr => Self::Expression(Expression::parse_boxed(line, ctx)?),
+            Rule::conditional_statement => Self::parse_if(line, ctx)?,
             Rule::stmt => {
                 let rule = line.into_inner().next().ok_or(missing("stmt(root)"))?;
                 match rule.as_rule() {
-                    Rule::expr => Self::Expression(Expression::parse_boxed(rule)?),
+                    Rule::expr => Self::Expression(Expression::parse_boxed(rule, ctx)?),
                     Rule::nop => Self::Nop,
+                    Rule::conditional_statement => Self::parse_if(rule, ctx)?,
                     _ => unreachable!(),
                 }
             }
@@ -48,3 +152,38 @@ impl Parse for Statement {
         Ok(stmt)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::session::{ParseOutput, Session};
+
+    use super::Statement;
+
+    fn parse(source: &str) -> ParseOutput {
+        Session::new().parse_str_partial(source)
+    }
+
+    #[test]
+    fn else_ifs_chain_before_one_else() {
+        let output = parse(
+            "fn f (n int) => {\n    if (n < 0) {\n        1\n    } else if (n == 0) {\n        2\n    } else if (n < 9) {\n        3\n    } else {\n        4\n    }\n}\n",
+        );
+        assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+        let [Statement::If {
+            else_ifs,
+            else_branch,
+            ..
+        }] = output.module.functions[0].body.as_slice()
+        else {
+            panic!("{:?}", output.module.functions[0].body);
+        };
+        assert_eq!(else_ifs.len(), 2);
+        assert!(else_branch.is_some());
+    }
+
+    #[test]
+    fn else_cannot_repeat() {
+        let output = parse("fn f (n int) => {\n    if (n < 0) {\n        1\n    } else {\n        2\n    } else {\n        3\n    }\n}\n");
+        assert!(output.has_errors());
+    }
+}
@@ -1,18 +1,87 @@
 use log::trace;
 use pest::iterators::Pair;
+use serde::Serialize;
 
 use crate::{next, parser::error::ParseResult, validate_rule, Rule};
 
-use super::{expr::Expression, function::Function, ident::Ident, Parse};
-
-#[derive(Debug)]
+use super::{
+    context::ParseContext,
+    expr::Expression,
+    function::Function,
+    ident::Ident,
+    span,
+    type_expr::{parse_type_params, TypeExpr},
+    Parse, Span,
+};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct TypeDefinition {
+    /// The `///` comment lines above the type.
+    pub docs: Vec<String>,
     pub name: Ident,
-    pub fields: Vec<Expression>,
+    /// The type parameters in `type Pair<A, B>`.
+    pub generics: Vec<Ident>,
+    pub fields: Vec<FieldDefinition>,
     pub methods: Vec<Function>,
+    /// The alternatives of a sum type, `type Shape = Circle(float) | ...`.
+    /// Empty for record types.
+    pub variants: Vec<VariantDefinition>,
+    /// The whole definition, from `type` to its closing brace or last
+    /// variant.
+    pub span: Span,
+}
+
+/// One alternative of a sum type: `Rect(float, float)`, or `Empty` for a
+/// variant without fields.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantDefinition {
+    pub name: Ident,
+    pub fields: Vec<TypeExpr>,
+    pub span: Span,
+}
+
+/// A field of a [`TypeDefinition`].
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDefinition {
+    /// The `///` comment lines above the field.
+    pub docs: Vec<String>,
+    /// An [`Expression::Assignment`] of the field's default value, if any.
+    pub definition: Expression,
 }
 impl TypeDefinition {
-    fn parse_field_definition(line: Pair<Rule>) -> ParseResult<Expression> {
+    /// Whether this is a sum type rather than a record.
+    pub fn is_enum(&self) -> bool {
+        !self.variants.is_empty()
+    }
+
+    fn parse_variant(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<VariantDefinition> {
+        trace!("[Start] parse-variant");
+
+        trace!("[Start:1] validate-rule");
+        validate_rule!(line.as_rule(), variant);
+        trace!("[EndOf:1] validate-rule");
+
+        trace!("[Start:2] get-rules");
+        let span = span(&line, ctx);
+        let mut rules = line.into_inner();
+        let name = next!(rules, "variant(name)");
+        trace!("[EndOf:2] get-rules");
+
+        trace!("[Start:3] parse-variant");
+        let name = Ident::parse(name, ctx)?;
+        let fields = rules
+            .map(|field| TypeExpr::parse(field, ctx))
+            .collect::<ParseResult<Vec<_>>>()?;
+        trace!("[EndOf:3] parse-variant");
+
+        trace!("[EndOf] parse-variant");
+        Ok(VariantDefinition { name, fields, span })
+    }
+
+    fn parse_field_definition(
+        line: Pair<Rule>,
+        ctx: &ParseContext,
+    ) -> ParseResult<FieldDefinition> {
         trace!("[Start] parse-field-definition");
 
         trace!("[Start:1] validate-rule");
@@ -21,8 +90,11 @@ impl TypeDefinition {
 
         trace!("[Start:2] get-rules");
 
+        let end = line.as_span().end();
         let mut rules = line.into_inner();
         let name = next!(rules, "field-definition(ident)");
+        let docs = ctx.docs_before(name.as_span().start());
+        let span = ctx.span_between(name.as_span().start(), end);
         let type_or_value = next!(rules, "field-definition(type_or_value)");
         let (typ, value) = if matches!(type_or_value.as_rule(), Rule::expr) {
             (None, Some(type_or_value))
@@ -32,27 +104,38 @@ impl TypeDefinition {
         trace!("[EndOf:2] get-rules");
 
         trace!("[Start:3] parse-name");
-        let name = Ident::parse(name)?;
+        let name = Ident::parse(name, ctx)?;
         trace!("[EndOf:3] parse-name");
 
         trace!("[Start:4] parse-type");
-        let typ = typ.map(Ident::parse_expect_type).transpose()?;
+        let typ = typ.map(|ty| TypeExpr::parse(ty, ctx)).transpose()?;
         trace!("[EndOf:4] parse-type");
 
         trace!("[Start:5] parse-value");
-        let value = value.map(Expression::parse_boxed).transpose()?;
+        let value = value
+            .map(|value| Expression::parse_boxed(value, ctx))
+            .transpose()?;
         trace!("[EndOf:5] parse-value");
 
         trace!("[Start:5] construct-type-def");
-        let assignment = Expression::Assignment { name, typ, value };
+        let assignment = Expression::Assignment {
+            declare: false,
+            name,
+            typ,
+            value,
+            span,
+        };
         trace!("[EndOf:5] construct-type-def");
 
         trace!("[EndOf] parse-field-definition");
-        Ok(assignment)
+        Ok(FieldDefinition {
+            docs,
+            definition: assignment,
+        })
     }
 }
 impl Parse for TypeDefinition {
-    fn parse(line: Pair<Rule>) -> ParseResult<Self> {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
         trace!("[Start] parse-type-def");
 
         trace!("[Start:1] validate-rule");
@@ -60,14 +143,25 @@ impl Parse for TypeDefinition {
         trace!("[EndOf:1] validate-rule");
 
         trace!("[Start:2] get-rules");
+        let span = span(&line, ctx);
         let mut rules = line.into_inner();
+        let docs = ctx.docs_before(span.start);
         let name = next!(rules, "typedef(name)");
+        let generics = match rules.peek() {
+            Some(generics) if generics.as_rule() == Rule::type_params => {
+                rules.next();
+                parse_type_params(generics, ctx)?
+            }
+            _ => vec![],
+        };
         let internals = rules.collect::<Vec<_>>();
         let mut fields = Vec::<Pair<Rule>>::new();
         let mut methods = Vec::<Pair<Rule>>::new();
+        let mut variants = Vec::<Pair<Rule>>::new();
         for internal in internals {
             match internal.as_rule() {
                 Rule::function => methods.push(internal),
+                Rule::variant => variants.push(internal),
                 Rule::field_definition => fields.push(internal),
                 _ => unreachable!(),
             }
@@ -75,30 +169,45 @@ impl Parse for TypeDefinition {
         trace!("[EndOf:2] get-rules");
 
         trace!("[Start:3] parse-name");
-        let name = Ident::parse(name)?;
+        let name = Ident::parse(name, ctx)?;
         trace!("[EndOf:3] parse-name");
 
-        trace!("[Start:4] parse-fields");
-        let fields = fields
-            .into_iter()
-            .map(Self::parse_field_definition)
-            .collect::<ParseResult<Vec<_>>>()?;
-        trace!("[EndOf:4] parse-fields");
-
-        trace!("[Start:5] parse-methods");
-        let methods = methods
-            .into_iter()
-            .map(Function::parse)
-            .collect::<ParseResult<Vec<_>>>()?;
-        trace!("[EndOf:5] parse-methods");
-
-        trace!("[Start:5] construct-type-def");
+        let (fields, methods, variants) = ctx.with_type_params(&generics, || {
+            trace!("[Start:4] parse-fields");
+            let fields = fields
+                .into_iter()
+                .map(|field| Self::parse_field_definition(field, ctx))
+                .collect::<ParseResult<Vec<_>>>()?;
+            trace!("[EndOf:4] parse-fields");
+
+            trace!("[Start:5] parse-methods");
+            let methods = methods
+                .into_iter()
+                .map(|method| Function::parse(method, ctx))
+                .collect::<ParseResult<Vec<_>>>()?;
+            trace!("[EndOf:5] parse-methods");
+
+            trace!("[Start:6] parse-variants");
+            let variants = variants
+                .into_iter()
+                .map(|variant| Self::parse_variant(variant, ctx))
+                .collect::<ParseResult<Vec<_>>>()?;
+            trace!("[EndOf:6] parse-variants");
+
+            ParseResult::Ok((fields, methods, variants))
+        })?;
+
+        trace!("[Start:7] construct-type-def");
         let this = Self {
+            docs,
             name,
+            generics,
             fields,
             methods,
+            variants,
+            span,
         };
-        trace!("[EndOf:5] construct-type-def");
+        trace!("[EndOf:7] construct-type-def");
 
         trace!("[EndOf] parse-type-def");
         Ok(this)
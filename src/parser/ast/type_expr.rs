@@ -0,0 +1,253 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+use log::trace;
+use pest::iterators::Pair;
+use serde::Serialize;
+
+use crate::{
+    next,
+    parser::error::{ParseError, ParseResult},
+    validate_rule, Rule,
+};
+
+use super::{context::ParseContext, ident::Ident, Parse, Span};
+
+/// A type as written in a type position, e.g. `Point`, `int[]`, `str*` or
+/// `Pair<int, str>`.
+#[derive(Debug, Clone, Serialize)]
+pub enum TypeExpr {
+    /// A user-defined `type`, with the type arguments of a generic one.
+    /// A generic type written without arguments leaves them to inference.
+    Named {
+        name: String,
+        args: Vec<TypeExpr>,
+        span: Span,
+    },
+    /// A built-in type such as `int`.
+    Native { name: String, span: Span },
+    /// A type parameter of the enclosing `type` or `fn`, such as `T`.
+    Param { name: String, span: Span },
+    /// `T[]`: a fixed sequence of `T`.
+    Array { element: Box<TypeExpr>, span: Span },
+    /// `T*`: a lazily produced sequence of `T`.
+    Generator { element: Box<TypeExpr>, span: Span },
+}
+impl TypeExpr {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Named { span, .. }
+            | Self::Native { span, .. }
+            | Self::Param { span, .. }
+            | Self::Array { span, .. }
+            | Self::Generator { span, .. } => span.clone(),
+        }
+    }
+
+    /// The type named by `base`, checking it takes as many type arguments as
+    /// `args` gives it. `span` covers both.
+    fn base(base: Ident, args: Vec<TypeExpr>, span: Span, ctx: &ParseContext) -> ParseResult<Self> {
+        let name = base.to_string();
+        let (expected, definition) = match ctx.type_param(&name) {
+            Some(definition) => (0, definition),
+            None => ctx
+                .is_type(&name)
+                .map_or((0, Span::default()), |info| (info.generics, info.span)),
+        };
+        if !args.is_empty() && args.len() != expected {
+            return Err(ParseError::TypeArity {
+                expected,
+                actual: args.len(),
+                span: base.span(),
+                definition,
+            });
+        }
+
+        Ok(match base {
+            _ if ctx.type_param(&name).is_some() => Self::Param { name, span },
+            Ident::Native { .. } => Self::Native { name, span },
+            _ => Self::Named { name, args, span },
+        })
+    }
+
+    /// The sequence element type, for arrays and generators.
+    pub fn element(&self) -> Option<&TypeExpr> {
+        match self {
+            Self::Array { element, .. } | Self::Generator { element, .. } => Some(element),
+            Self::Named { .. } | Self::Native { .. } | Self::Param { .. } => None,
+        }
+    }
+}
+impl Display for TypeExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named { name, args, .. } if !args.is_empty() => {
+                write!(f, "{}<{}>", name, args.iter().join(", "))
+            }
+            Self::Named { name, .. } | Self::Native { name, .. } | Self::Param { name, .. } => {
+                write!(f, "{}", name)
+            }
+            Self::Array { element, .. } => write!(f, "{}[]", element),
+            Self::Generator { element, .. } => write!(f, "{}*", element),
+        }
+    }
+}
+impl Parse for TypeExpr {
+    fn parse(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Self> {
+        trace!("[Start] parse-type-expr");
+
+        trace!("[Start:1] validate-rule");
+        validate_rule!(line.as_rule(), type_expr);
+        trace!("[EndOf:1] validate-rule");
+
+        trace!("[Start:2] parse-base");
+        let start = line.as_span().start();
+        let mut rules = line.into_inner();
+        let base = Ident::parse_expect_type(next!(rules, "type-expr(base)"), ctx)?;
+        trace!("[EndOf:2] parse-base");
+
+        trace!("[Start:3] parse-type-args");
+        let (args, end) = match rules.peek() {
+            Some(args) if args.as_rule() == Rule::type_args => {
+                rules.next();
+                let end = args.as_span().end();
+                let args = args
+                    .into_inner()
+                    .map(|arg| Self::parse(arg, ctx))
+                    .collect::<ParseResult<Vec<_>>>()?;
+                (args, end)
+            }
+            _ => (vec![], base.span().end),
+        };
+        let mut ty = Self::base(base, args, ctx.span_between(start, end), ctx)?;
+        trace!("[EndOf:3] parse-type-args");
+
+        trace!("[Start:4] parse-suffixes");
+        for suffix in rules {
+            let span = ctx.span_between(start, suffix.as_span().end());
+            let element = Box::new(ty);
+            ty = match suffix.as_rule() {
+                Rule::array_suffix => Self::Array { element, span },
+                _ => {
+                    validate_rule!(suffix.as_rule(), generator_suffix);
+                    Self::Generator { element, span }
+                }
+            };
+        }
+        trace!("[EndOf:4] parse-suffixes");
+
+        trace!("[EndOf] parse-type-expr");
+        Ok(ty)
+    }
+}
+
+/// The names declared by `line`, a `type_params` list such as `<A, B>`.
+pub fn parse_type_params(line: Pair<Rule>, ctx: &ParseContext) -> ParseResult<Vec<Ident>> {
+    validate_rule!(line.as_rule(), type_params);
+    let mut params = Vec::<Ident>::new();
+    for param in line.into_inner() {
+        let param = Ident::parse(param, ctx)?;
+        if let Some(previous) = params.iter().find(|p| p.to_string() == param.to_string()) {
+            return Err(ParseError::DuplicateTypeParameter {
+                ident: param.to_string(),
+                span: param.span(),
+                previous: previous.span(),
+            });
+        }
+        params.push(param);
+    }
+    Ok(params)
+}
+
+/// Whether `line`, a `type_expr`, is a single identifier that does not name a
+/// type, and so is really a binding name in a parameter list.
+pub fn is_plain_name(line: &Pair<Rule>, ctx: &ParseContext) -> bool {
+    let mut rules = line.clone().into_inner();
+    match (rules.next(), rules.next()) {
+        (Some(ident), None) => ctx.is_type(ident.as_str()).is_none(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pest::Parser;
+
+    use crate::{
+        parser::{
+            ast::{context::ParseContext, Parse},
+            error::ParseResult,
+            session::Session,
+        },
+        FNSParser, Rule,
+    };
+
+    use super::TypeExpr;
+
+    /// `source` parsed as a type expression, which must match all of it.
+    fn type_expr(source: &str) -> ParseResult<TypeExpr> {
+        let pair = FNSParser::parse(Rule::type_expr, source)
+            .expect("a type expression")
+            .next()
+            .expect("a type expression");
+        assert_eq!(pair.as_str(), source);
+        let mut ctx = ParseContext::new();
+        ctx.source = source.into();
+        TypeExpr::parse(pair, &ctx)
+    }
+
+    /// Whether a parameter of type `ty` is rejected.
+    fn rejected(ty: &str) -> bool {
+        Session::new()
+            .parse_str_partial(&format!("fn f (x {}) => 0\n", ty))
+            .has_errors()
+    }
+
+    #[test]
+    fn suffixes_wrap_the_type_before_them() {
+        let ty = type_expr("int*").expect("generator parses");
+        let TypeExpr::Generator { element, span } = &ty else {
+            panic!("{:?}", ty);
+        };
+        assert!(matches!(**element, TypeExpr::Native { .. }));
+        assert_eq!(span.content, "int*");
+
+        let ty = type_expr("int[]").expect("array parses");
+        let TypeExpr::Array { element, span } = &ty else {
+            panic!("{:?}", ty);
+        };
+        assert!(matches!(**element, TypeExpr::Native { .. }));
+        assert_eq!(span.content, "int[]");
+    }
+
+    #[test]
+    fn suffixes_nest_left_to_right() {
+        let ty = type_expr("int[]*").expect("generator of arrays parses");
+        let TypeExpr::Generator { element, .. } = &ty else {
+            panic!("{:?}", ty);
+        };
+        let TypeExpr::Array { span, .. } = &**element else {
+            panic!("{:?}", element);
+        };
+        assert_eq!(span.content, "int[]");
+        assert_eq!(ty.to_string(), "int[]*");
+
+        let ty = type_expr("int*[]").expect("array of generators parses");
+        let TypeExpr::Array { element, .. } = &ty else {
+            panic!("{:?}", ty);
+        };
+        assert!(matches!(**element, TypeExpr::Generator { .. }));
+        assert_eq!(ty.to_string(), "int*[]");
+    }
+
+    #[test]
+    fn malformed_type_expressions_are_rejected() {
+        assert!(!rejected("int[]*"));
+        for ty in [
+            "int[", "int]", "[]int", "int[ ]", "int *", "int<>", "int<int>",
+        ] {
+            assert!(rejected(ty), "`{}` is accepted", ty);
+        }
+        assert!(type_expr("str<int>").is_err());
+    }
+}
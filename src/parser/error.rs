@@ -1,14 +1,25 @@
-use std::num::ParseFloatError;
+use std::{io, num::ParseFloatError};
 
-use snailquote::UnescapeError;
 use thiserror::Error;
 
-use crate::Rule;
+use pest::{iterators::Pair, Parser};
 
-use super::ast::Span;
+use crate::{
+    diagnostic::{source::FileId, Diagnostic, Label},
+    FNSParser, Rule,
+};
+
+use super::ast::{context::ParseContext, span, Span};
 
 #[derive(Debug, Error)]
 pub enum ParseError {
+    #[error("Syntax error: {error}")]
+    SyntaxError {
+        error: Box<pest::error::Error<Rule>>,
+        file: FileId,
+    },
+    #[error("Failed to read source: {0}")]
+    IoError(#[from] io::Error),
     #[error("Invalid rule. Expected {expected:?} got {actual:?}")]
     InvalidRuleError { expected: Rule, actual: Rule },
     #[error("Invalid rule. Expected {expected:?} got {actual:?}")]
@@ -18,39 +29,198 @@ pub enum ParseError {
     #[error("Error parsing float: {0}")]
     ParseFloatError(#[from] ParseFloatError),
     #[error("Invalid character literal: {reason}")]
-    InvalidCharError { reason: &'static str },
-    #[error("Failed to unescape string")]
-    UnescapeError(#[from] UnescapeError),
-    #[error("General context error: {message} ({origin})")]
-    ContextError {
-        message: &'static str,
-        origin: &'static str,
+    InvalidCharError { reason: &'static str, span: Span },
+    #[error("Literal out of range for `{ty}`")]
+    NumberOverflow { ty: &'static str, span: Span },
+    #[error("Unmatched `{brace}` in string")]
+    LoneBrace { brace: char, span: Span },
+    #[error("Patterns cannot interpolate strings")]
+    InterpolatedPattern { span: Span },
+    #[error("Invalid escape `{escape}`: {reason}")]
+    InvalidEscape {
+        escape: String,
+        reason: &'static str,
+        span: Span,
+    },
+    #[error("`{ident}` is a built-in type")]
+    NativeType { ident: String, span: Span },
+    #[error("Duplicate type definition `{ident}`")]
+    DuplicateType {
+        ident: String,
+        span: Span,
+        previous: Span,
+    },
+    #[error("Duplicate variant `{ident}`")]
+    DuplicateVariant {
+        ident: String,
+        span: Span,
+        previous: Span,
     },
-    #[error("Duplicate type definition")]
-    DuplicateType { ident: String },
+    #[error("Duplicate type parameter `{ident}`")]
+    DuplicateTypeParameter {
+        ident: String,
+        span: Span,
+        previous: Span,
+    },
+    #[error("No variant named `{ident}`")]
+    UnknownVariant { ident: String, span: Span },
+    #[error(
+        "Variant `{}` has {expected} field(s), but the pattern has {actual}",
+        definition.content
+    )]
+    VariantArity {
+        expected: usize,
+        actual: usize,
+        span: Span,
+        definition: Span,
+    },
+    #[error(
+        "Type `{}` takes {expected} type argument(s), but {actual} were given",
+        span.content
+    )]
+    TypeArity {
+        expected: usize,
+        actual: usize,
+        /// The name of the type, where it is used.
+        span: Span,
+        /// Empty for built-in types.
+        definition: Span,
+    },
+    #[error("`do` expression has no `_` branch")]
+    MissingDefaultBranch { span: Span },
+    #[error("`do` expression has more than one `_` branch")]
+    DuplicateDefaultBranch { span: Span, previous: Span },
     #[error("Failed to convert from string")]
     FromStrError {
         reason: String,
         origin: &'static str,
     },
+    #[error("`{keyword}` is a keyword; did you mean `r#{keyword}`?")]
+    KeywordAsIdentifier { keyword: &'static str, span: Span },
     #[error("Expected type, got identifier")]
     ExpectedType { ident: String, span: Span },
+    /// A syntax error found by reparsing part of a node; see
+    /// [`recovered_syntax_error`].
+    #[error("{}", .diagnostic.message)]
+    Recovered { diagnostic: Box<Diagnostic> },
+    #[error("Parsing failed with {} error(s)", .diagnostics.iter().filter(|d| d.is_error()).count())]
+    Failed { diagnostics: Vec<Diagnostic> },
 }
 
-pub fn missing(slug: &'static str) -> ParseError {
-    ParseError::MissingItem { slug }
-}
-pub fn context_uninitialized(origin: &'static str) -> ParseError {
-    ParseError::ContextError {
-        message: "Context uninitialized",
-        origin,
+impl ParseError {
+    /// Converts this error into a [`Diagnostic`], labelling any spans it carries.
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::SyntaxError { error, file } => Diagnostic::from_pest(error, *file),
+            Self::Recovered { diagnostic } => (**diagnostic).clone(),
+            Self::NativeType { span, .. } => {
+                Diagnostic::error(self).with_label(Label::primary(span.clone(), "redefined here"))
+            }
+            Self::DuplicateType { span, previous, .. }
+            | Self::DuplicateVariant { span, previous, .. }
+            | Self::DuplicateTypeParameter { span, previous, .. } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "redefined here"))
+                .with_label(Label::secondary(
+                    previous.clone(),
+                    "previously defined here",
+                )),
+            Self::UnknownVariant { span, .. } => {
+                Diagnostic::error(self).with_label(Label::primary(span.clone(), "not a variant"))
+            }
+            Self::VariantArity {
+                expected,
+                span,
+                definition,
+                ..
+            } => Diagnostic::error(self)
+                .with_label(Label::primary(
+                    span.clone(),
+                    format!("expected {} field(s)", expected),
+                ))
+                .with_label(Label::secondary(definition.clone(), "variant defined here")),
+            Self::TypeArity {
+                expected,
+                span,
+                definition,
+                ..
+            } => {
+                let diagnostic = Diagnostic::error(self).with_label(Label::primary(
+                    span.clone(),
+                    format!("expected {} type argument(s)", expected),
+                ));
+                match definition.content.is_empty() {
+                    true => diagnostic,
+                    false => diagnostic
+                        .with_label(Label::secondary(definition.clone(), "type defined here")),
+                }
+            }
+            Self::ExpectedType { span, .. } => {
+                Diagnostic::error(self).with_label(Label::primary(span.clone(), "not a type"))
+            }
+            Self::KeywordAsIdentifier { span, .. } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "keyword used as a name")),
+            Self::InvalidCharError { span, .. } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "not a single character"))
+                .with_note("use double quotes for a string"),
+            Self::NumberOverflow { ty, span } => Diagnostic::error(self).with_label(
+                Label::primary(span.clone(), format!("does not fit in `{ty}`")),
+            ),
+            Self::LoneBrace { brace, span } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "unmatched brace"))
+                .with_note(match brace {
+                    '{' => "write `{{` for a literal `{`, or close the interpolation with `}`",
+                    _ => "write `}}` for a literal `}`",
+                }),
+            Self::InterpolatedPattern { span } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "interpolation"))
+                .with_note("write `{{` for a literal `{`, or match the value in a `do` branch"),
+            Self::InvalidEscape { span, .. } => {
+                Diagnostic::error(self).with_label(Label::primary(span.clone(), "invalid escape"))
+            }
+            Self::MissingDefaultBranch { span } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "no fallback branch"))
+                .with_note("add a `_ => ...` branch to handle the remaining cases"),
+            Self::DuplicateDefaultBranch { span, previous } => Diagnostic::error(self)
+                .with_label(Label::primary(span.clone(), "extra `_` branch"))
+                .with_label(Label::secondary(previous.clone(), "first `_` branch here")),
+            _ => Diagnostic::error(self),
+        }
+    }
+
+    /// Every diagnostic carried by this error.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            Self::Failed { diagnostics } => diagnostics.clone(),
+            _ => vec![self.diagnostic()],
+        }
     }
 }
-pub fn unexpected_lock(origin: &'static str) -> ParseError {
-    ParseError::ContextError {
-        message: "Context locked - this shouldn't be possible.",
-        origin,
+
+/// Builds the diagnostic for a recovery node (`error_item`, `error_stmt` or
+/// `bad_interpolation`).
+///
+/// The node's text is re-parsed as `rule`, a `whole_*` rule for what the
+/// grammar failed to match there, so the report carries pest's own
+/// expectation message at the point that text stops parsing.
+pub fn recovered_syntax_error(line: &Pair<Rule>, rule: Rule, ctx: &ParseContext) -> Diagnostic {
+    let node = span(line, ctx);
+    let source = format!("{}\n", line.as_str());
+
+    let Err(error) = FNSParser::parse(rule, &source) else {
+        return Diagnostic::error("syntax error")
+            .with_label(Label::primary(node, format!("expected {:?}", rule)));
+    };
+
+    let mut diagnostic = Diagnostic::from_pest(&error, ctx.file);
+    for label in diagnostic.labels.iter_mut() {
+        label.span.start = (node.start + label.span.start).min(node.end);
+        label.span.end = (node.start + label.span.end).min(node.end);
     }
+    diagnostic
+}
+
+pub fn missing(slug: &'static str) -> ParseError {
+    ParseError::MissingItem { slug }
 }
 pub fn bad_fromstr(reason: String, origin: &'static str) -> ParseError {
     ParseError::FromStrError { reason, origin }
@@ -1,6 +1,6 @@
 #[macro_export]
 macro_rules! next {
     ($rules:expr, $slug:literal) => {
-        $rules.next().ok_or(crate::parser::error::missing($slug))?
+        $rules.next().ok_or($crate::parser::error::missing($slug))?
     };
 }
@@ -0,0 +1,343 @@
+use std::{fs, path::Path};
+
+use pest::Parser;
+
+use crate::{
+    diagnostic::{source::SourceMap, Diagnostic},
+    FNSParser, Rule,
+};
+
+use super::{
+    ast::{context::ParseContext, module::Module, Parse},
+    error::{missing, ParseError, ParseResult},
+};
+
+/// The result of a parse that recovers from errors.
+///
+/// `module` holds everything that could be parsed, with placeholders where
+/// items or statements failed; `diagnostics` describes each failure.
+#[derive(Debug, Default)]
+pub struct ParseOutput {
+    pub module: Module,
+    pub diagnostics: Vec<Diagnostic>,
+}
+impl ParseOutput {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    /// The module, or [`ParseError::Failed`] if any errors were reported.
+    pub fn into_result(self) -> ParseResult<Module> {
+        if self.has_errors() {
+            return Err(ParseError::Failed {
+                diagnostics: self.diagnostics,
+            });
+        }
+        Ok(self.module)
+    }
+}
+
+/// A parsing session.
+///
+/// Every file parsed through a session is recorded in its [`SourceMap`] for
+/// diagnostics. Each parse starts from a fresh [`ParseContext`], so a file
+/// never observes the types of another, or of an earlier parse of itself.
+#[derive(Default)]
+pub struct Session {
+    context: ParseContext,
+    sources: SourceMap,
+}
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn context(&self) -> &ParseContext {
+        &self.context
+    }
+
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    pub fn parse_str(&mut self, source: &str) -> ParseResult<Module> {
+        self.parse_str_partial(source).into_result()
+    }
+
+    pub fn parse_file<P: AsRef<Path>>(&mut self, path: P) -> ParseResult<Module> {
+        self.parse_file_partial(path)?.into_result()
+    }
+
+    pub fn parse_str_partial(&mut self, source: &str) -> ParseOutput {
+        self.parse_named_partial("<input>", source)
+    }
+
+    pub fn parse_file_partial<P: AsRef<Path>>(&mut self, path: P) -> ParseResult<ParseOutput> {
+        let source = fs::read_to_string(&path)?;
+        Ok(self.parse_named_partial(path.as_ref().display(), &source))
+    }
+
+    /// Parse `source`, reporting locations within it under `name`.
+    ///
+    /// Parsing continues past malformed items and statements; see [`ParseOutput`].
+    pub fn parse_named_partial<N: ToString>(&mut self, name: N, source: &str) -> ParseOutput {
+        let file = self.sources.add(name, source);
+        self.context = ParseContext::new();
+        self.context.file = file;
+        self.context.source = source.into();
+
+        let module = match self.parse_root(source) {
+            Ok(module) => module,
+            Err(e) => {
+                self.context.report(e.diagnostic());
+                Module::default()
+            }
+        };
+
+        ParseOutput {
+            module,
+            diagnostics: self.context.take_diagnostics(),
+        }
+    }
+
+    fn parse_root(&mut self, source: &str) -> ParseResult<Module> {
+        let root = FNSParser::parse(Rule::file, source)
+            .map_err(|error| ParseError::SyntaxError {
+                error: Box::new(error),
+                file: self.context.file,
+            })?
+            .next()
+            .ok_or(missing("file(root)"))?;
+
+        self.context
+            .set_comments(Module::find_comments(source, &self.context));
+        Module::register_types(&root, &mut self.context)?;
+        Module::parse(root, &self.context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::ast::module::Item;
+
+    use super::{ParseOutput, Session};
+
+    fn parse(source: &str) -> ParseOutput {
+        Session::new().parse_str_partial(source)
+    }
+
+    /// The names of the functions that parsed.
+    fn functions(output: &ParseOutput) -> Vec<String> {
+        output
+            .module
+            .functions
+            .iter()
+            .map(|function| function.func_name.to_string())
+            .collect()
+    }
+
+    /// Where each diagnostic's primary label starts.
+    fn carets(output: &ParseOutput) -> Vec<usize> {
+        output
+            .diagnostics
+            .iter()
+            .flat_map(|diagnostic| diagnostic.labels.iter())
+            .filter(|label| label.primary)
+            .map(|label| label.span.start)
+            .collect()
+    }
+
+    #[test]
+    fn bad_statements_are_skipped() {
+        let output = parse("fn main => {\n    let x = (1\n    println(x)\n}\n\nfn other => 2\n");
+        assert_eq!(carets(&output), ["fn main => {\n    let x = (1".len()]);
+        assert_eq!(functions(&output), ["main", "other"]);
+    }
+
+    #[test]
+    fn braces_in_quoted_text_stay_in_the_bad_statement() {
+        let output = parse("fn main => {\n    println(\"}\", '}')\n    1\n}\n\nfn other => 2\n");
+        assert_eq!(output.diagnostics.len(), 1, "{:?}", output.diagnostics);
+        assert_eq!(functions(&output), ["main", "other"]);
+    }
+
+    #[test]
+    fn bad_items_report_where_they_stop_parsing() {
+        let output = parse("fn main => foo(1\n\nfn other => 2\n");
+        assert_eq!(carets(&output), ["fn main => foo(1".len()]);
+        assert_eq!(functions(&output), ["other"]);
+    }
+
+    #[test]
+    fn bad_items_keep_their_place() {
+        let output =
+            parse("fn a => 1\n\nfn b => (\n\ntype P {\n    x int\n}\n\ntype Q = \n\nfn c => 3\n");
+        let items = output
+            .module
+            .items()
+            .iter()
+            .map(|item| match item {
+                Item::Function(function) => function.func_name.to_string(),
+                Item::Type(ty) => ty.name.to_string(),
+                Item::Error(span) => format!("error `{}`", span.content.trim_end()),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            ["a", "error `fn b => (`", "P", "error `type Q =`", "c"]
+        );
+    }
+
+    #[test]
+    fn lone_braces_in_strings_suggest_doubling() {
+        let output = parse("fn main => println(\"a } b\", \"{x\")\n");
+        let messages = output
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["Unmatched `}` in string"]);
+        assert_eq!(carets(&output), ["fn main => println(\"a ".len()]);
+        assert!(output.diagnostics[0].notes[0].contains("`}}`"));
+    }
+
+    #[test]
+    fn bad_interpolations_report_the_expression() {
+        let output = parse("fn main => println(\"a {1 +} b\")\n");
+        assert_eq!(output.diagnostics.len(), 1, "{:?}", output.diagnostics);
+        assert!(output.diagnostics[0].message.contains("syntax error"));
+        assert_eq!(carets(&output), ["fn main => println(\"a {1 +".len()]);
+    }
+
+    #[test]
+    fn patterns_cannot_interpolate() {
+        let output = parse("fn f (\"a{1}\") => 1\n");
+        let messages = output
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["Patterns cannot interpolate strings"]);
+        assert_eq!(carets(&output), ["fn f (\"a".len()]);
+    }
+
+    #[test]
+    fn files_without_items_parse() {
+        for source in ["", "\n\n", "// hi", "// hi\n", "/* a */\n\n/// b\n"] {
+            let output = parse(source);
+            assert!(
+                !output.has_errors(),
+                "{:?}: {:?}",
+                source,
+                output.diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn stray_doc_comments_are_comments() {
+        let source = "/// f\n// plain\n\nfn f => {\n    /// not here\n    1\n}\n/// nor here\n";
+        let output = parse(source);
+        assert!(!output.has_errors(), "{:?}", output.diagnostics);
+        assert_eq!(
+            carets(&output),
+            [
+                source.find("/// not").unwrap(),
+                source.find("/// nor").unwrap()
+            ]
+        );
+        assert_eq!(output.module.functions[0].docs, ["f"]);
+        let comments = output
+            .module
+            .comments
+            .iter()
+            .map(|comment| comment.span.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(comments, ["// plain", "/// not here", "/// nor here"]);
+    }
+
+    #[test]
+    fn type_arguments_match_the_definition() {
+        let source = "type Pair<A, B> {\n    first A\n    second B\n}\n\nfn a (p Pair<int>) => p\nfn b (n int<str>) => n\nfn c<T> (t T<int>) => t\nfn d (p Pair) => p\nfn e (p Pair<int, str>) => p\n";
+        let output = parse(source);
+        let messages = output
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "Type `Pair` takes 2 type argument(s), but 1 were given",
+                "Type `int` takes 0 type argument(s), but 1 were given",
+                "Type `T` takes 0 type argument(s), but 1 were given"
+            ]
+        );
+        assert_eq!(
+            carets(&output),
+            [
+                source.find("Pair<int>").unwrap(),
+                source.find("int<str>").unwrap(),
+                source.find("T<int>").unwrap()
+            ]
+        );
+        assert_eq!(functions(&output), ["d", "e"]);
+    }
+
+    #[test]
+    fn clauses_after_a_broader_one_are_unreachable() {
+        let source = "fn fib (int) => 1\nfn fib (1) => 1\nfn fib (0) => 1\n\nfn g (0) => 1\nfn g (n int) => n\nfn g (1) => 2\n";
+        let output = parse(source);
+        assert!(!output.has_errors(), "{:?}", output.diagnostics);
+        let messages = output
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "unreachable clause of `fib`",
+                "unreachable clause of `fib`",
+                "unreachable clause of `g`"
+            ]
+        );
+        assert_eq!(
+            carets(&output),
+            [
+                source.find("fib (1)").unwrap(),
+                source.find("fib (0)").unwrap(),
+                source.find("g (1)").unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_in_one_session_are_independent() {
+        let mut session = Session::new();
+        let source = "type P {\n    x int\n}\n";
+        for name in ["a.fn", "a.fn", "b.fn"] {
+            let output = session.parse_named_partial(name, source);
+            assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+        }
+        let output = session.parse_str_partial("fn f (p P) => p\n");
+        assert!(output.has_errors());
+    }
+
+    #[test]
+    fn sessions_parse_on_separate_threads() {
+        let threads = ["P", "Q"].map(|ty| {
+            std::thread::spawn(move || {
+                let source = format!("type {} {{\n    x int\n}}\n\nfn f (v {}) => v.x\n", ty, ty);
+                let output = Session::new().parse_str_partial(&source);
+                (output.diagnostics.len(), functions(&output))
+            })
+        });
+        for thread in threads {
+            assert_eq!(
+                thread.join().expect("parse runs"),
+                (0, vec!["f".to_owned()])
+            );
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use thiserror::Error;
+
+use crate::{
+    diagnostic::{Diagnostic, Label},
+    parser::ast::Span,
+};
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("Cannot find `{name}` in this scope")]
+    Undefined {
+        name: String,
+        span: Span,
+        /// A visible name close to `name`, and where it is defined unless it
+        /// is a builtin.
+        similar: Option<(String, Option<Span>)>,
+    },
+    #[error("`{name}` is defined more than once")]
+    Duplicate {
+        name: String,
+        span: Span,
+        previous: Span,
+    },
+    #[error("`{name}` shadows an earlier binding")]
+    Shadowed {
+        name: String,
+        span: Span,
+        previous: Span,
+    },
+}
+impl ResolveError {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Undefined { span, .. }
+            | Self::Duplicate { span, .. }
+            | Self::Shadowed { span, .. } => span,
+        }
+    }
+
+    /// Shadowing is allowed, so it is only worth a warning.
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Self::Shadowed { .. })
+    }
+
+    /// Converts this error into a [`Diagnostic`], labelling both spans it carries.
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::Undefined { similar, .. } => {
+                let diagnostic = Diagnostic::error(self)
+                    .with_label(Label::primary(self.span().clone(), "not found"));
+                match similar {
+                    Some((name, Some(span))) => diagnostic
+                        .with_label(Label::secondary(
+                            span.clone(),
+                            format!("`{}` is defined here", name),
+                        ))
+                        .with_note(format!("did you mean `{}`?", name)),
+                    Some((name, None)) => diagnostic.with_note(format!("did you mean `{}`?", name)),
+                    None => diagnostic,
+                }
+            }
+            Self::Duplicate { previous, .. } => Diagnostic::error(self)
+                .with_label(Label::primary(self.span().clone(), "defined again here"))
+                .with_label(Label::secondary(previous.clone(), "first defined here")),
+            Self::Shadowed { previous, .. } => Diagnostic::warning(self)
+                .with_label(Label::primary(
+                    self.span().clone(),
+                    "shadows it from here on",
+                ))
+                .with_label(Label::secondary(previous.clone(), "earlier binding")),
+        }
+    }
+}
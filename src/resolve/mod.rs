@@ -0,0 +1,724 @@
+//! Name resolution: links every identifier to the definition it refers to.
+//!
+//! Lookup follows the interpreter. Each function clause is its own frame,
+//! holding `self`, the parameters and the `let`s of its body; blocks, `do`
+//! branches and `if` branches open nested scopes within it, and the names a
+//! `do` pattern binds live in its branch's scope. A name not bound in the
+//! frame is looked up among the functions, then the types and variants, then
+//! the builtins. Field defaults see no locals at all.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use log::trace;
+
+use crate::{
+    interp::builtins::Builtin,
+    parser::ast::{
+        expr::{atom::Atom, operator::Operator, DoBranch, Expression, MatchBranch, StringPart},
+        function::Function,
+        function_parameter::FunctionParameter,
+        ident::Ident,
+        module::Module,
+        statement::Statement,
+        type_definition::TypeDefinition,
+        Span,
+    },
+};
+
+use self::error::ResolveError;
+
+pub mod error;
+
+/// Identifies a [`Definition`] within a [`Resolution`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefKind {
+    /// Every clause of a function.
+    Function,
+    /// A `type`, called as its constructor.
+    Type,
+    /// A variant of a sum type.
+    Variant,
+    /// Every clause of a method of one type.
+    Method,
+    Builtin,
+    Param,
+    /// A `let` binding.
+    Local,
+    /// The receiver of a method.
+    SelfValue,
+}
+
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub name: String,
+    pub kind: DefKind,
+    /// `None` for builtins.
+    pub span: Option<Span>,
+}
+
+/// Every definition in a module, and what each identifier refers to.
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub definitions: Vec<Definition>,
+    /// The definition each resolved identifier refers to, by its span.
+    pub uses: HashMap<Span, DefId>,
+    /// The local each parameter, `let` and pattern introduces, by the span
+    /// of its name. `self` is keyed by the span of its method's name, and
+    /// `_` by that of its annotation.
+    pub bindings: HashMap<Span, DefId>,
+}
+impl Resolution {
+    pub fn definition(&self, id: DefId) -> &Definition {
+        &self.definitions[id.0]
+    }
+
+    /// What `ident` refers to, if it was resolved.
+    pub fn use_of(&self, ident: &Ident) -> Option<DefId> {
+        self.uses.get(&ident.span()).copied()
+    }
+
+    /// The definition `ident` refers to, if it was resolved.
+    pub fn resolved(&self, ident: &Ident) -> Option<&Definition> {
+        self.use_of(ident).map(|id| self.definition(id))
+    }
+
+    /// The local introduced at `span`; see [`Resolution::bindings`].
+    pub fn binding(&self, span: &Span) -> Option<DefId> {
+        self.bindings.get(span).copied()
+    }
+
+    fn add(&mut self, name: String, kind: DefKind, span: Option<Span>) -> DefId {
+        self.definitions.push(Definition { name, kind, span });
+        DefId(self.definitions.len() - 1)
+    }
+}
+
+/// The result of resolving a module.
+#[derive(Debug)]
+pub struct ResolveOutput {
+    pub resolution: Resolution,
+    pub errors: Vec<ResolveError>,
+}
+impl ResolveOutput {
+    pub fn has_errors(&self) -> bool {
+        self.errors.iter().any(ResolveError::is_error)
+    }
+}
+
+/// Resolves every identifier in `module`.
+pub fn resolve(module: &Module) -> ResolveOutput {
+    trace!("[Start] resolve");
+    let mut resolver = Resolver::new(module);
+    resolver.resolve_module();
+    trace!("[EndOf] resolve");
+    ResolveOutput {
+        resolution: resolver.resolution,
+        errors: resolver.errors,
+    }
+}
+
+struct Resolver<'m> {
+    module: &'m Module,
+    resolution: Resolution,
+    /// Functions, types, variants and the builtins used so far.
+    globals: HashMap<String, DefId>,
+    /// Methods by name, which a pipe may call on its receiver.
+    methods: HashMap<String, DefId>,
+    /// Bindings of the frame being resolved, innermost scope last.
+    scopes: Vec<HashMap<String, DefId>>,
+    errors: Vec<ResolveError>,
+}
+impl<'m> Resolver<'m> {
+    fn new(module: &'m Module) -> Self {
+        Self {
+            module,
+            resolution: Resolution::default(),
+            globals: HashMap::new(),
+            methods: HashMap::new(),
+            scopes: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn resolve_module(&mut self) {
+        self.define_items();
+        for function in &self.module.functions {
+            self.resolve_clause(function, false);
+        }
+        for ty in &self.module.types {
+            self.resolve_fields(ty);
+            for method in &ty.methods {
+                self.resolve_clause(method, true);
+            }
+        }
+    }
+
+    /// Defines every function, type, variant and method, so items may be used
+    /// before the point at which they are defined.
+    fn define_items(&mut self) {
+        for definition in self.module.definitions() {
+            let span = definition.clauses[0].func_name.span();
+            let id = self
+                .resolution
+                .add(definition.name.clone(), DefKind::Function, Some(span));
+            self.globals.insert(definition.name, id);
+        }
+
+        for ty in &self.module.types {
+            let name = ty.name.to_string();
+            match self
+                .globals
+                .get(&name)
+                .map(|id| self.resolution.definition(*id))
+            {
+                // Duplicate types are reported by the parser already.
+                Some(Definition {
+                    kind: DefKind::Type,
+                    ..
+                }) => {}
+                Some(previous) => self.errors.push(ResolveError::Duplicate {
+                    name,
+                    span: ty.name.span(),
+                    previous: previous.span.clone().unwrap_or_default(),
+                }),
+                None => {
+                    let id = self
+                        .resolution
+                        .add(name.clone(), DefKind::Type, Some(ty.name.span()));
+                    self.globals.insert(name, id);
+                }
+            }
+            self.define_members(ty);
+        }
+
+        for ty in &self.module.types {
+            for variant in &ty.variants {
+                self.define_variant(&variant.name);
+            }
+        }
+    }
+
+    /// Variants share a namespace with functions and types.
+    fn define_variant(&mut self, name: &Ident) {
+        match self
+            .globals
+            .get(&name.to_string())
+            .map(|id| self.resolution.definition(*id))
+        {
+            // Duplicate variants are reported by the parser already.
+            Some(Definition {
+                kind: DefKind::Variant,
+                ..
+            }) => {}
+            Some(previous) => self.errors.push(ResolveError::Duplicate {
+                name: name.to_string(),
+                span: name.span(),
+                previous: previous.span.clone().unwrap_or_default(),
+            }),
+            None => {
+                let id = self
+                    .resolution
+                    .add(name.to_string(), DefKind::Variant, Some(name.span()));
+                self.globals.insert(name.to_string(), id);
+            }
+        }
+    }
+
+    /// Defines the methods of `ty`, checking that each member name is used
+    /// once: a field hides a method of the same name.
+    fn define_members(&mut self, ty: &TypeDefinition) {
+        let mut members: HashMap<String, Span> = HashMap::new();
+        for field in &ty.fields {
+            let Expression::Assignment { name, .. } = &field.definition else {
+                continue;
+            };
+            self.define_member(&mut members, name);
+        }
+
+        let mut clauses: HashMap<String, DefId> = HashMap::new();
+        for method in &ty.methods {
+            let name = method.func_name.to_string();
+            // Further clauses of a method are not duplicates.
+            if clauses.contains_key(&name) {
+                continue;
+            }
+            self.define_member(&mut members, &method.func_name);
+            let id =
+                self.resolution
+                    .add(name.clone(), DefKind::Method, Some(method.func_name.span()));
+            clauses.insert(name.clone(), id);
+            self.methods.entry(name).or_insert(id);
+        }
+    }
+
+    fn define_member(&mut self, members: &mut HashMap<String, Span>, name: &Ident) {
+        match members.get(&name.to_string()) {
+            Some(previous) => self.errors.push(ResolveError::Duplicate {
+                name: name.to_string(),
+                span: name.span(),
+                previous: previous.clone(),
+            }),
+            None => {
+                members.insert(name.to_string(), name.span());
+            }
+        }
+    }
+
+    /// Field defaults are evaluated without any locals in scope.
+    fn resolve_fields(&mut self, ty: &TypeDefinition) {
+        self.scopes = vec![HashMap::new()];
+        for field in &ty.fields {
+            if let Expression::Assignment {
+                value: Some(value), ..
+            } = &field.definition
+            {
+                self.resolve_expr(value);
+            }
+        }
+    }
+
+    /// Resolves `function` in a frame of its own, binding `self` for methods.
+    fn resolve_clause(&mut self, function: &Function, method: bool) {
+        trace!("[Start] resolve:clause({})", function.func_name);
+        self.scopes = vec![HashMap::new()];
+        if method {
+            let id = self.resolution.add(
+                "self".to_owned(),
+                DefKind::SelfValue,
+                Some(function.func_name.span()),
+            );
+            self.bind("self", id);
+            self.resolution
+                .bindings
+                .insert(function.func_name.span(), id);
+        }
+
+        for param in &function.params {
+            self.define_pattern(param, Self::define_param);
+        }
+
+        self.resolve_body(&function.body);
+        trace!("[EndOf] resolve:clause({})", function.func_name);
+    }
+
+    /// Defines the names `param` binds with `define`, resolving the variants
+    /// it matches on.
+    fn define_pattern(&mut self, param: &FunctionParameter, define: fn(&mut Self, &Ident)) {
+        match param {
+            FunctionParameter::NamedAndTyped { name, .. }
+            | FunctionParameter::NamedDynamic { name } => define(self, name),
+            FunctionParameter::Cons { head, tail, .. } => {
+                define(self, head);
+                define(self, tail);
+            }
+            FunctionParameter::Anonymous { ty } => {
+                // Any number of parameters may be anonymous; `_` is the last.
+                let id = self
+                    .resolution
+                    .add("_".to_owned(), DefKind::Param, Some(ty.span()));
+                self.bind("_", id);
+                self.resolution.bindings.insert(ty.span(), id);
+            }
+            FunctionParameter::Variant { name, fields, .. } => {
+                self.use_name(name);
+                for field in fields {
+                    self.define_pattern(field, define);
+                }
+            }
+            FunctionParameter::Literal { .. }
+            | FunctionParameter::Wildcard { .. }
+            | FunctionParameter::Empty { .. } => {}
+        }
+    }
+
+    fn define_param(&mut self, name: &Ident) {
+        if let Some(previous) = self.local(&name.to_string()) {
+            self.errors.push(ResolveError::Duplicate {
+                name: name.to_string(),
+                span: name.span(),
+                previous: self.span_of(previous),
+            });
+            return;
+        }
+        let id = self
+            .resolution
+            .add(name.to_string(), DefKind::Param, Some(name.span()));
+        self.bind(name.to_string(), id);
+        self.resolution.bindings.insert(name.span(), id);
+    }
+
+    /// Defines a `let` binding, which hides any earlier one of the same name
+    /// in the frame.
+    fn define_local(&mut self, name: &Ident) {
+        if let Some(previous) = self.local(&name.to_string()) {
+            self.errors.push(ResolveError::Shadowed {
+                name: name.to_string(),
+                span: name.span(),
+                previous: self.span_of(previous),
+            });
+        }
+        let id = self
+            .resolution
+            .add(name.to_string(), DefKind::Local, Some(name.span()));
+        self.bind(name.to_string(), id);
+        self.resolution.bindings.insert(name.span(), id);
+    }
+
+    fn bind<S: ToString>(&mut self, name: S, id: DefId) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), id);
+        }
+    }
+
+    fn resolve_body(&mut self, body: &[Statement]) {
+        for stmt in body {
+            self.resolve_statement(stmt);
+        }
+    }
+
+    /// Resolves `body` in a nested scope, so its `let`s do not escape.
+    fn resolve_block(&mut self, body: &[Statement]) {
+        self.scopes.push(HashMap::new());
+        self.resolve_body(body);
+        self.scopes.pop();
+    }
+
+    fn resolve_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Nop | Statement::Error { .. } => {}
+            Statement::Expression(expr) => self.resolve_expr(expr),
+            // The value is evaluated before the name is bound.
+            Statement::Assignment { ident, value, .. } => {
+                self.resolve_expr(value);
+                self.define_local(ident);
+            }
+            Statement::Declaration { ident, .. } => self.define_local(ident),
+            Statement::If {
+                condition,
+                then_branch,
+                else_ifs,
+                else_branch,
+                ..
+            } => {
+                self.resolve_expr(condition);
+                self.resolve_block(then_branch);
+                for else_if in else_ifs {
+                    self.resolve_expr(&else_if.condition);
+                    self.resolve_block(&else_if.body);
+                }
+                if let Some(body) = else_branch {
+                    self.resolve_block(body);
+                }
+            }
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Atom(Atom::Literal { .. }) => {}
+            Expression::Atom(Atom::Ident(ident)) => self.use_name(ident),
+            // Fields and methods are looked up on the value at runtime.
+            Expression::BinaryOperation {
+                lhs,
+                operator: Operator::FieldAccess,
+                ..
+            } => self.resolve_expr(lhs),
+            Expression::BinaryOperation { lhs, rhs, .. } => {
+                self.resolve_expr(lhs);
+                self.resolve_expr(rhs);
+            }
+            Expression::PrefixOperation { rhs, .. } => self.resolve_expr(rhs),
+            Expression::PostfixOperation { lhs, .. } => self.resolve_expr(lhs),
+            Expression::Call { lhs, params, .. } => {
+                self.resolve_expr(lhs);
+                if let Some(params) = params {
+                    self.resolve_expr(params);
+                }
+            }
+            Expression::Interpolation { parts, .. } => {
+                for part in parts {
+                    if let StringPart::Expression(expr) = part {
+                        self.resolve_expr(expr);
+                    }
+                }
+            }
+            Expression::Pipe {
+                receiver,
+                name,
+                params,
+                ..
+            } => {
+                if let Some(receiver) = receiver {
+                    self.resolve_expr(receiver);
+                }
+                if let Some(params) = params {
+                    self.resolve_expr(params);
+                }
+                // The receiver's type is unknown here, so any method will do.
+                let method = self.methods.get(&name.to_string()).copied();
+                match (self.lookup(name), method) {
+                    (None, Some(id)) if receiver.is_some() => {
+                        self.resolution.uses.insert(name.span(), id);
+                    }
+                    _ => self.use_name(name),
+                }
+            }
+            Expression::Assignment {
+                declare,
+                name,
+                value,
+                ..
+            } => {
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+                if *declare {
+                    self.define_local(name);
+                    return;
+                }
+                // Only locals can be reassigned.
+                match self.local(&name.to_string()) {
+                    Some(id) => {
+                        self.resolution.uses.insert(name.span(), id);
+                    }
+                    None => self.undefined(name),
+                }
+            }
+            Expression::Do {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for DoBranch {
+                    condition,
+                    behavior,
+                } in branches
+                {
+                    self.resolve_expr(condition);
+                    self.resolve_scoped(behavior);
+                }
+                self.resolve_scoped(&default_branch.behavior);
+            }
+            Expression::Match {
+                scrutinee,
+                branches,
+                ..
+            } => {
+                self.resolve_expr(scrutinee);
+                for MatchBranch { pattern, behavior } in branches {
+                    self.scopes.push(HashMap::new());
+                    self.define_pattern(pattern, Self::define_local);
+                    self.resolve_expr(behavior);
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+
+    /// Like [`Resolver::resolve_block`], for a single expression.
+    fn resolve_scoped(&mut self, expr: &Expression) {
+        self.scopes.push(HashMap::new());
+        self.resolve_expr(expr);
+        self.scopes.pop();
+    }
+
+    /// Records what `ident` refers to, or reports it undefined.
+    fn use_name(&mut self, ident: &Ident) {
+        match self.lookup(ident) {
+            Some(id) => {
+                self.resolution.uses.insert(ident.span(), id);
+            }
+            None => self.undefined(ident),
+        }
+    }
+
+    fn undefined(&mut self, ident: &Ident) {
+        let name = ident.to_string();
+        let similar = self.similar(&name).map(|(candidate, id)| {
+            let span = id.and_then(|id| self.resolution.definition(id).span.clone());
+            (candidate, span)
+        });
+        self.errors.push(ResolveError::Undefined {
+            name,
+            span: ident.span(),
+            similar,
+        });
+    }
+
+    /// Looks `ident` up in the frame, then among the functions, types,
+    /// variants and builtins.
+    fn lookup(&mut self, ident: &Ident) -> Option<DefId> {
+        let name = ident.to_string();
+        if let Some(id) = self.local(&name) {
+            return Some(id);
+        }
+        if let Some(id) = self.globals.get(&name) {
+            return Some(*id);
+        }
+        let builtin = Builtin::lookup(&name)?;
+        let id = self
+            .resolution
+            .add(builtin.name().to_owned(), DefKind::Builtin, None);
+        self.globals.insert(name, id);
+        Some(id)
+    }
+
+    fn local(&self, name: &str) -> Option<DefId> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .copied()
+    }
+
+    fn span_of(&self, id: DefId) -> Span {
+        self.resolution
+            .definition(id)
+            .span
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// The visible name closest to `name`, if any is close enough to be a
+    /// likely typo. Builtins have no definition.
+    fn similar(&self, name: &str) -> Option<(String, Option<DefId>)> {
+        let locals = self
+            .scopes
+            .iter()
+            .flat_map(|scope| scope.iter().map(|(name, id)| (name.clone(), Some(*id))));
+        let globals = self
+            .globals
+            .iter()
+            .map(|(name, id)| (name.clone(), Some(*id)));
+        let builtins = Builtin::ALL
+            .into_iter()
+            .map(|builtin| (builtin.name().to_owned(), None));
+
+        // Short names are too close to too many others.
+        let limit = name.chars().count() / 3;
+        locals
+            .chain(globals)
+            // Builtins looked up earlier are globals too.
+            .filter(|(_, id)| {
+                id.is_none_or(|id| self.resolution.definition(id).kind != DefKind::Builtin)
+            })
+            .chain(builtins)
+            .map(|(candidate, id)| (edit_distance(name, &candidate), candidate, id))
+            .filter(|(distance, _, _)| (1..=limit).contains(distance))
+            .sorted_by(|(a, x, _), (b, y, _)| a.cmp(b).then_with(|| x.cmp(y)))
+            .map(|(_, candidate, id)| (candidate, id))
+            .next()
+    }
+}
+
+/// The number of single character insertions, deletions and substitutions
+/// that turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect_vec();
+    let mut previous = (0..=b.len()).collect_vec();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_str;
+
+    use super::{resolve, DefKind, ResolveOutput};
+
+    fn resolved(source: &str) -> ResolveOutput {
+        resolve(&parse_str(source).expect("source parses"))
+    }
+
+    /// The message of every error and warning in `source`.
+    fn errors(source: &str) -> Vec<String> {
+        resolved(source)
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn undefined_names_suggest_similar_ones() {
+        let source = "fn length (xs) => 0\n\nfn main => {\n    let total = lenght(1)\n    totl + nothing\n}\n";
+        let output = resolved(source);
+        let notes = output
+            .errors
+            .iter()
+            .map(|error| (error.to_string(), error.diagnostic().notes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            [
+                (
+                    "Cannot find `lenght` in this scope".to_owned(),
+                    vec!["did you mean `length`?".to_owned()]
+                ),
+                (
+                    "Cannot find `totl` in this scope".to_owned(),
+                    vec!["did you mean `total`?".to_owned()]
+                ),
+                ("Cannot find `nothing` in this scope".to_owned(), vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn branch_locals_do_not_escape() {
+        let source = "fn f (c bool) => {\n    if (c) {\n        let y = 1\n    }\n    do {\n        c => let z = 2\n        _ => 3\n    }\n    y + z\n}\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Cannot find `y` in this scope",
+                "Cannot find `z` in this scope"
+            ]
+        );
+    }
+
+    #[test]
+    fn names_are_defined_once() {
+        let source = "type P {\n    x int\n\n    fn x => 1\n}\n\nfn f (a, a) => a\n";
+        let output = resolved(source);
+        assert!(output.has_errors());
+        assert_eq!(
+            errors(source),
+            [
+                "`x` is defined more than once",
+                "`a` is defined more than once"
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowing_warns_and_rebinds() {
+        let source = "fn f (x int) => {\n    let x = x + 1\n    x\n}\n";
+        let output = resolved(source);
+        assert!(!output.has_errors());
+        assert_eq!(errors(source), ["`x` shadows an earlier binding"]);
+
+        // The `x` in the initializer is the parameter; the last is the `let`.
+        let uses = |at: usize| {
+            let (_, &id) = output
+                .resolution
+                .uses
+                .iter()
+                .find(|(span, _)| span.start == at)
+                .expect("use resolved");
+            output.resolution.definition(id).kind
+        };
+        assert_eq!(uses(source.find("x + 1").unwrap()), DefKind::Param);
+        assert_eq!(uses(source.rfind('x').unwrap()), DefKind::Local);
+    }
+}
@@ -0,0 +1,154 @@
+use thiserror::Error;
+
+use crate::{
+    diagnostic::{Diagnostic, Label},
+    parser::ast::{expr::operator::Operator, Span},
+};
+
+use super::types::{Constraint, Type};
+
+#[derive(Debug, Error)]
+pub enum TypeError {
+    #[error("Mismatched types: expected `{expected}`, found `{found}`")]
+    Mismatch {
+        expected: Type,
+        found: Type,
+        span: Span,
+        /// Where the expected type comes from, and why.
+        origin: Option<(Span, &'static str)>,
+    },
+    #[error("`if` without `else` has no `{expected}` when its condition is false")]
+    MissingElse { expected: Type, span: Span },
+    #[error("`{construct}` condition must be `bool`, found `{found}`")]
+    NonBoolCondition {
+        construct: &'static str,
+        found: Type,
+        span: Span,
+    },
+    #[error("Cannot apply `{operator}` to `{lhs}` and `{rhs}`")]
+    InvalidOperands {
+        operator: Operator,
+        lhs: Type,
+        rhs: Type,
+        span: Span,
+    },
+    #[error("Cannot apply `{operator}` to `{operand}`")]
+    InvalidOperand {
+        operator: Operator,
+        operand: Type,
+        span: Span,
+    },
+    #[error("`{callee}` expects {expected} argument(s), got {actual}")]
+    ArityMismatch {
+        callee: String,
+        /// The accepted argument counts, e.g. `1 or 2`.
+        expected: String,
+        actual: usize,
+        span: Span,
+        definition: Option<Span>,
+    },
+    #[error("No clause of `{callee}` accepts ({args})")]
+    NoMatchingClause {
+        callee: String,
+        args: String,
+        span: Span,
+    },
+    #[error("Value of type `{ty}` is not callable")]
+    NotCallable { ty: Type, span: Span },
+    #[error("Type `{ty}` has no field or method `{field}`")]
+    UnknownField { ty: Type, field: String, span: Span },
+    #[error("Type `{found}` does not satisfy `{constraint}`")]
+    Unsatisfied {
+        constraint: Constraint,
+        found: Type,
+        span: Span,
+        /// The use of a generic parameter that requires `constraint`.
+        required: Span,
+    },
+    #[error("`{callee}` expects a sequence, found `{found}`")]
+    NotSequence {
+        callee: String,
+        found: Type,
+        span: Span,
+    },
+    #[error("`{ty}.to_string` must have type `fn() str`, found `{found}`")]
+    ToString { ty: String, found: Type, span: Span },
+    #[error("Non-exhaustive patterns: `{missing}` not covered")]
+    NonExhaustive {
+        missing: String,
+        /// `clause` or `branch`.
+        case: &'static str,
+        span: Span,
+    },
+}
+impl TypeError {
+    pub fn span(&self) -> &Span {
+        match self {
+            Self::Mismatch { span, .. }
+            | Self::MissingElse { span, .. }
+            | Self::NonBoolCondition { span, .. }
+            | Self::InvalidOperands { span, .. }
+            | Self::InvalidOperand { span, .. }
+            | Self::ArityMismatch { span, .. }
+            | Self::NoMatchingClause { span, .. }
+            | Self::NotCallable { span, .. }
+            | Self::UnknownField { span, .. }
+            | Self::Unsatisfied { span, .. }
+            | Self::NotSequence { span, .. }
+            | Self::ToString { span, .. }
+            | Self::NonExhaustive { span, .. } => span,
+        }
+    }
+
+    /// Converts this error into a [`Diagnostic`], labelling the spans it carries.
+    pub fn diagnostic(&self) -> Diagnostic {
+        let primary = match self {
+            Self::Mismatch { expected, .. } => format!("expected `{}`", expected),
+            Self::MissingElse { .. } => "evaluates to `()` if no branch runs".to_owned(),
+            Self::NonBoolCondition { .. } => "expected `bool`".to_owned(),
+            Self::InvalidOperands { .. } | Self::InvalidOperand { .. } => {
+                "unsupported operand types".to_owned()
+            }
+            Self::ArityMismatch { actual, .. } => format!("called with {} argument(s)", actual),
+            Self::NoMatchingClause { .. } => "no clause matches these argument types".to_owned(),
+            Self::NotCallable { .. } => "called here".to_owned(),
+            Self::UnknownField { ty, .. } => format!("not a field or method of `{}`", ty),
+            Self::Unsatisfied { constraint, .. } => format!("`{}` required", constraint),
+            Self::NotSequence { .. } => "not a sequence".to_owned(),
+            Self::ToString { ty, .. } => format!("shows `{}` values as text", ty),
+            Self::NonExhaustive { missing, .. } => format!("`{}` not covered", missing),
+        };
+        let diagnostic =
+            Diagnostic::error(self).with_label(Label::primary(self.span().clone(), primary));
+
+        match self {
+            Self::Mismatch {
+                origin: Some((span, reason)),
+                ..
+            } => diagnostic.with_label(Label::secondary(span.clone(), reason)),
+            Self::ArityMismatch {
+                definition: Some(span),
+                ..
+            } => diagnostic.with_label(Label::secondary(span.clone(), "defined here")),
+            Self::Unsatisfied {
+                constraint,
+                required,
+                ..
+            } => diagnostic
+                .with_label(Label::secondary(required.clone(), "required by this use"))
+                .with_note(format!(
+                    "`{}` is satisfied by {}",
+                    constraint,
+                    constraint.members()
+                )),
+            Self::MissingElse { expected, .. } => {
+                diagnostic.with_note(format!("add an `else` branch producing `{}`", expected))
+            }
+            Self::NonExhaustive { case, .. } => diagnostic.with_note(format!(
+                "add a {} handling it, or one with a `_` pattern",
+                case
+            )),
+            _ => diagnostic,
+        }
+    }
+}
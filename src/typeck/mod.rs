@@ -0,0 +1,2406 @@
+//! Static type checking of a parsed [`Module`].
+//!
+//! Types flow up from literals and annotations: an unannotated `let` takes the
+//! type of its value, and an unannotated parameter starts out as a type
+//! variable. Uses that need one exact type pin the variable down, e.g. being
+//! passed to an `int` parameter or used as a condition; uses that work for
+//! several types, such as `x * 2`, only [constrain](Constraint) it.
+//!
+//! Variables still free once a clause is checked make it generic, as in
+//! Hindley-Milner let-polymorphism: every call instantiates them afresh, so
+//! `fn double (x) => x * 2` works for both `int`s and `float`s, and its
+//! constraints are checked against the arguments. A parameter that is called
+//! is inferred to be a function, `fn app (f, x) => f(x)` taking `fn(A) B`.
+//!
+//! Only what the checker cannot see is `Unknown` and accepted: unannotated
+//! fields, values of types that failed to check, and calls it cannot
+//! resolve. Their uses are left to the interpreter.
+//!
+//! Type parameters declared with `type Pair<A, B>` or `fn first<T>` stand
+//! only for themselves inside the definition. Each constructor call, call
+//! and method call replaces them with fresh variables, or with the type
+//! arguments of the receiver; values are boxed, so the interpreter erases
+//! them.
+//!
+//! The clauses of a function that matches on variants, and the branches of a
+//! `do` with a scrutinee, must together handle every value; see [`patterns`].
+//!
+//! Names are looked up in the [`Resolution`] of the module, so each local is
+//! typed once, by the definition it refers to.
+
+use std::{collections::HashMap, fmt::Display, mem};
+
+use itertools::Itertools;
+use log::trace;
+
+use crate::{
+    format::{self, literal_source},
+    interp::builtins::Builtin,
+    parser::ast::{
+        expr::{
+            atom::Atom, literal::Literal, operator::Operator, DoBranch, Expression, MatchBranch,
+            StringPart,
+        },
+        function::Function,
+        function_parameter::FunctionParameter,
+        ident::Ident,
+        module::Module,
+        statement::Statement,
+        type_definition::{TypeDefinition, VariantDefinition},
+        type_expr::TypeExpr,
+        Span,
+    },
+    resolve::{DefId, DefKind, Definition, Resolution},
+};
+
+use self::{
+    error::TypeError,
+    patterns::Coverage,
+    types::{Callee, Constraint, Type},
+};
+
+pub mod error;
+pub mod patterns;
+pub mod types;
+
+/// The result of type checking a module.
+#[derive(Debug)]
+pub struct TypeckOutput {
+    pub errors: Vec<TypeError>,
+    /// The inferred signature of every function clause and method, in source
+    /// order.
+    pub signatures: Vec<Signature>,
+}
+
+/// The inferred type of a function clause, printed like its definition with
+/// generic parameters: `fn double<A> (x A) A where A: Num`.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    /// `Type.name` for methods.
+    pub name: String,
+    pub generics: Vec<(String, Vec<Constraint>)>,
+    pub params: Vec<String>,
+    pub ret: Type,
+}
+impl Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fn {}", self.name)?;
+        if !self.generics.is_empty() {
+            write!(
+                f,
+                "<{}>",
+                self.generics.iter().map(|(name, _)| name).join(", ")
+            )?;
+        }
+        if !self.params.is_empty() {
+            write!(f, " ({})", self.params.join(", "))?;
+        }
+        write!(f, " {}", self.ret)?;
+
+        let bounds = self
+            .generics
+            .iter()
+            .filter(|(_, constraints)| !constraints.is_empty())
+            .map(|(name, constraints)| format!("{}: {}", name, constraints.iter().join(" + ")))
+            .collect_vec();
+        if !bounds.is_empty() {
+            write!(f, " where {}", bounds.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Type checks every function, method and field default in `module`, whose
+/// names `resolution` links to their definitions.
+pub fn check(module: &Module, resolution: &Resolution) -> TypeckOutput {
+    trace!("[Start] typeck");
+    let mut checker = Checker::new(module, resolution);
+    checker.check_module();
+    let signatures = checker.signatures_in_order();
+    trace!("[EndOf] typeck");
+    TypeckOutput {
+        errors: checker.errors,
+        signatures,
+    }
+}
+
+/// A local binding.
+#[derive(Debug, Clone)]
+struct Binding {
+    ty: Type,
+    span: Span,
+}
+
+/// What a parameter accepts.
+#[derive(Debug, Clone)]
+struct Param {
+    /// `Unknown` if any argument is accepted.
+    ty: Type,
+    /// `head:tail` and `[]` patterns only match sequences.
+    sequence: bool,
+    span: Span,
+    /// Whether `ty` was inferred rather than written.
+    inferred: bool,
+    /// For a variant pattern with no annotations or literals in it, the type
+    /// it is a variant of: it is chosen by the variant a value holds, whatever
+    /// the types inside.
+    variant_of: Option<String>,
+}
+
+/// The checked signature of one function clause.
+#[derive(Debug, Clone)]
+struct Clause {
+    params: Vec<Param>,
+    ret: Type,
+    span: Span,
+    /// Whether its type variables are instantiated per call. Clauses still
+    /// being checked are not: recursive calls share their variables.
+    generic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Function(String),
+    Method { ty: String, name: String },
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    /// `Unknown` for unannotated fields, which hold whatever they are given.
+    ty: Type,
+    /// Fields without a default value must be passed to the constructor.
+    required: bool,
+    span: Span,
+}
+
+struct Checker<'m> {
+    module: &'m Module,
+    functions: HashMap<String, Vec<&'m Function>>,
+    types: HashMap<String, &'m TypeDefinition>,
+    /// Every variant of a sum type, with the name of that type.
+    variants: HashMap<String, (String, &'m VariantDefinition)>,
+    /// Checked clauses of each function; `None` while its body is being checked.
+    signatures: HashMap<Key, Option<Vec<Clause>>>,
+    /// Provisional clauses of the functions being checked.
+    pending: HashMap<Key, Vec<Clause>>,
+    /// What each name refers to.
+    resolution: &'m Resolution,
+    /// The type of every local checked so far.
+    locals: HashMap<DefId, Binding>,
+    /// What each [`Type::Var`] has been inferred to be.
+    substitution: Vec<Option<Type>>,
+    /// What each unbound [`Type::Var`] must support, and the use requiring it.
+    constraints: Vec<Vec<(Constraint, Span)>>,
+    errors: Vec<TypeError>,
+}
+impl<'m> Checker<'m> {
+    fn new(module: &'m Module, resolution: &'m Resolution) -> Self {
+        let functions = module
+            .definitions()
+            .into_iter()
+            .map(|definition| (definition.name, definition.clauses))
+            .collect();
+        let types = module
+            .types
+            .iter()
+            .map(|ty| (ty.name.to_string(), ty))
+            .collect();
+        let variants = module
+            .types
+            .iter()
+            .flat_map(|ty| {
+                ty.variants
+                    .iter()
+                    .map(|variant| (variant.name.to_string(), (ty.name.to_string(), variant)))
+            })
+            .collect();
+
+        Self {
+            module,
+            functions,
+            types,
+            variants,
+            signatures: HashMap::new(),
+            pending: HashMap::new(),
+            resolution,
+            locals: HashMap::new(),
+            substitution: vec![],
+            constraints: vec![],
+            errors: vec![],
+        }
+    }
+
+    fn check_module(&mut self) {
+        for definition in self.module.definitions() {
+            self.check_coverage(&definition.name, &definition.clauses);
+            self.signature(&Key::Function(definition.name));
+        }
+        for ty in &self.module.types {
+            self.check_fields(ty);
+            for name in ty
+                .methods
+                .iter()
+                .map(|method| method.func_name.to_string())
+                .unique()
+            {
+                let key = Key::Method {
+                    ty: ty.name.to_string(),
+                    name,
+                };
+                let (clauses, _) = self.clauses_of(&key);
+                self.check_coverage(&format!("{}.{}", ty.name, clauses[0].func_name), &clauses);
+                let signature = self.signature(&key);
+                if clauses[0].func_name.to_string() == "to_string" {
+                    self.check_to_string(ty, &signature);
+                }
+            }
+        }
+    }
+
+    /// Interpolation and printing show instances through `to_string`, so it
+    /// must take no arguments and produce a `str`.
+    fn check_to_string(&mut self, ty: &TypeDefinition, clauses: &[Clause]) {
+        for clause in clauses {
+            let ret = self.resolve(&clause.ret);
+            if clause.params.is_empty() && matches!(ret, Type::Str | Type::Var(_) | Type::Unknown) {
+                continue;
+            }
+            self.errors.push(TypeError::ToString {
+                ty: ty.name.to_string(),
+                found: Type::Function {
+                    params: clause
+                        .params
+                        .iter()
+                        .map(|param| self.resolve(&param.ty))
+                        .collect(),
+                    ret: Box::new(ret),
+                },
+                span: clause.span.clone(),
+            });
+        }
+    }
+
+    /// Checks that the clauses of a function that matches on variants handle
+    /// every call. Clauses taking different numbers of arguments are separate.
+    fn check_coverage(&mut self, name: &str, clauses: &[&Function]) {
+        let by_arity = clauses
+            .iter()
+            .into_group_map_by(|clause| clause.params.len());
+        for (width, clauses) in by_arity.into_iter().sorted_by_key(|(width, _)| *width) {
+            let variants = clauses.iter().any(|clause| {
+                clause
+                    .params
+                    .iter()
+                    .any(|param| matches!(param, FunctionParameter::Variant { .. }))
+            });
+            if !variants {
+                continue;
+            }
+            let rows = clauses
+                .iter()
+                .map(|clause| clause.params.iter().collect_vec())
+                .collect_vec();
+            if let Some(missing) = self.coverage().uncovered(&rows, width) {
+                self.errors.push(TypeError::NonExhaustive {
+                    missing: format!("{}({})", name, missing.iter().join(", ")),
+                    case: "clause",
+                    span: clauses[0].func_name.span(),
+                });
+            }
+        }
+    }
+
+    fn coverage(&self) -> Coverage<'_, 'm> {
+        Coverage {
+            types: &self.types,
+            variants: &self.variants,
+        }
+    }
+
+    /// The checked clauses of every function and method, generalized.
+    fn signatures_in_order(&self) -> Vec<Signature> {
+        let functions = self
+            .module
+            .definitions()
+            .into_iter()
+            .map(|definition| (Key::Function(definition.name.clone()), definition.name));
+        let methods = self.module.types.iter().flat_map(|ty| {
+            ty.methods
+                .iter()
+                .map(|method| method.func_name.to_string())
+                .unique()
+                .map(|name| {
+                    let key = Key::Method {
+                        ty: ty.name.to_string(),
+                        name: name.clone(),
+                    };
+                    (key, format!("{}.{}", ty.name, name))
+                })
+        });
+
+        let mut signatures = vec![];
+        for (key, name) in functions.chain(methods).collect_vec() {
+            let Some(Some(clauses)) = self.signatures.get(&key) else {
+                continue;
+            };
+            for (function, clause) in self.clauses_of(&key).0.into_iter().zip(clauses) {
+                signatures.push(self.describe(&name, function, clause));
+            }
+        }
+        signatures
+    }
+
+    /// The signature of `clause`, after the type parameters `function`
+    /// declares naming its free type variables `A`, `B`, ...
+    fn describe(&self, name: &str, function: &Function, clause: &Clause) -> Signature {
+        let declared = function.generics.iter().map(Ident::to_string).collect_vec();
+        let mut generics = HashMap::new();
+        let params = function
+            .params
+            .iter()
+            .zip(&clause.params)
+            .map(|(param, checked)| {
+                let ty = self.generic(&checked.ty, &mut generics, &declared);
+                match param {
+                    FunctionParameter::NamedAndTyped { name, .. }
+                    | FunctionParameter::NamedDynamic { name } => format!("{} {}", name, ty),
+                    FunctionParameter::Anonymous { .. } => ty.to_string(),
+                    FunctionParameter::Literal { literal, .. } => literal_source(literal),
+                    FunctionParameter::Wildcard { .. } => "_".to_owned(),
+                    FunctionParameter::Cons { head, tail, .. } => match ty {
+                        Type::Unknown => format!("{}:{}", head, tail),
+                        ty => format!("{}:{} {}", head, tail, ty),
+                    },
+                    FunctionParameter::Empty { .. } => match ty {
+                        Type::Unknown => "[]".to_owned(),
+                        ty => format!("[] {}", ty),
+                    },
+                    FunctionParameter::Variant { .. } => format::parameter(param),
+                }
+            })
+            .collect();
+        let ret = self.generic(&clause.ret, &mut generics, &declared);
+
+        let inferred = generics
+            .into_iter()
+            .sorted_by_key(|(_, (index, _))| *index)
+            .map(|(var, (_, name))| {
+                let constraints = self.constraints[var]
+                    .iter()
+                    .map(|(constraint, _)| *constraint)
+                    .collect();
+                (name, constraints)
+            });
+        let generics = declared
+            .into_iter()
+            .map(|name| (name, vec![]))
+            .chain(inferred)
+            .collect();
+        Signature {
+            name: name.to_owned(),
+            generics,
+            params,
+            ret,
+        }
+    }
+
+    /// `ty` with each free type variable replaced by a generic parameter,
+    /// named in order of appearance around the `declared` ones.
+    fn generic(
+        &self,
+        ty: &Type,
+        names: &mut HashMap<usize, (usize, String)>,
+        declared: &[String],
+    ) -> Type {
+        match self.resolve(ty) {
+            Type::Var(var) => {
+                if let Some((_, name)) = names.get(&var) {
+                    return Type::Param(name.clone());
+                }
+                let name = (0..)
+                    .map(generic_name)
+                    .find(|name| {
+                        !declared.contains(name) && names.values().all(|(_, used)| used != name)
+                    })
+                    .unwrap_or_default();
+                names.insert(var, (names.len(), name.clone()));
+                Type::Param(name)
+            }
+            Type::Named(ty, args) => Type::Named(
+                ty,
+                args.iter()
+                    .map(|arg| self.generic(arg, names, declared))
+                    .collect(),
+            ),
+            Type::Array(element) => Type::Array(Box::new(self.generic(&element, names, declared))),
+            Type::Generator(element) => {
+                Type::Generator(Box::new(self.generic(&element, names, declared)))
+            }
+            Type::Function { params, ret } => Type::Function {
+                params: params
+                    .iter()
+                    .map(|param| self.generic(param, names, declared))
+                    .collect(),
+                ret: Box::new(self.generic(&ret, names, declared)),
+            },
+            ty => ty,
+        }
+    }
+
+    /// Checks field defaults against their annotations.
+    fn check_fields(&mut self, ty: &TypeDefinition) {
+        for field in &ty.fields {
+            let Expression::Assignment {
+                typ,
+                value: Some(value),
+                ..
+            } = &field.definition
+            else {
+                continue;
+            };
+
+            let value_ty = self.check_expr(value);
+            if let Some(typ) = typ {
+                let origin = (typ.span(), "expected due to this annotation");
+                self.require(
+                    &value_ty,
+                    &self.annotation(typ),
+                    &value.span(),
+                    Some(origin),
+                );
+            }
+        }
+    }
+
+    /// The checked clauses of a function or method, checking them on first use.
+    fn signature(&mut self, key: &Key) -> Vec<Clause> {
+        match self.signatures.get(key) {
+            Some(Some(clauses)) => return clauses.clone(),
+            Some(None) => return self.pending.get(key).cloned().unwrap_or_default(),
+            None => {}
+        }
+
+        trace!("[Start] typeck:signature({:?})", key);
+        self.signatures.insert(key.clone(), None);
+        let (functions, receiver) = self.clauses_of(key);
+        let provisional = functions
+            .iter()
+            .map(|function| self.provisional(function))
+            .collect_vec();
+        self.pending.insert(key.clone(), provisional.clone());
+
+        let clauses = functions
+            .into_iter()
+            .zip(provisional)
+            .map(|(function, clause)| self.check_clause(function, receiver.as_deref(), clause))
+            .collect_vec();
+        self.pending.remove(key);
+        self.check_returns(&clauses);
+        self.signatures.insert(key.clone(), Some(clauses.clone()));
+        trace!("[EndOf] typeck:signature({:?})", key);
+        clauses
+    }
+
+    /// Any clause taking as many arguments as a call may be the one that
+    /// runs, so they must agree on what they return. Clauses that never
+    /// return have no say.
+    fn check_returns(&mut self, clauses: &[Clause]) {
+        let by_arity = clauses
+            .iter()
+            .filter(|clause| clause.ret != Type::Never)
+            .into_group_map_by(|clause| clause.params.len());
+        for (_, clauses) in by_arity.into_iter().sorted_by_key(|(width, _)| *width) {
+            let first = clauses[0];
+            for clause in &clauses[1..] {
+                if !self.fits(&clause.ret, &first.ret, &clause.span) {
+                    self.errors.push(TypeError::Mismatch {
+                        expected: self.resolve(&first.ret),
+                        found: self.resolve(&clause.ret),
+                        span: clause.span.clone(),
+                        origin: Some((first.span.clone(), "first clause returns this")),
+                    });
+                }
+            }
+        }
+    }
+
+    fn clauses_of(&self, key: &Key) -> (Vec<&'m Function>, Option<String>) {
+        match key {
+            Key::Function(name) => (self.functions.get(name).cloned().unwrap_or_default(), None),
+            Key::Method { ty, name } => {
+                let methods = self
+                    .types
+                    .get(ty)
+                    .map(|definition| {
+                        definition
+                            .methods
+                            .iter()
+                            .filter(|method| method.func_name.to_string() == *name)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (methods, Some(ty.clone()))
+            }
+        }
+    }
+
+    /// The signature of `function` from its annotations, with type variables
+    /// for everything else.
+    fn provisional(&mut self, function: &Function) -> Clause {
+        let params = function
+            .params
+            .iter()
+            .map(|param| match param {
+                FunctionParameter::NamedDynamic { .. } => Param {
+                    ty: self.fresh(),
+                    inferred: true,
+                    ..self.declared_param(param)
+                },
+                param => self.declared_param(param),
+            })
+            .collect();
+        let ret = match &function.return_type {
+            Some(declared) => self.annotation(declared),
+            None => self.fresh(),
+        };
+        Clause {
+            params,
+            ret,
+            span: function.func_name.span(),
+            generic: false,
+        }
+    }
+
+    /// Checks the body of `function` in a fresh frame against its
+    /// `provisional` signature, binding `self` to `receiver` for methods.
+    fn check_clause(
+        &mut self,
+        function: &Function,
+        receiver: Option<&str>,
+        provisional: Clause,
+    ) -> Clause {
+        trace!("[Start] typeck:clause({})", function.func_name);
+        if let Some(ty) = receiver {
+            let own = self.own_type(ty);
+            self.define(function.func_name.span(), own);
+        }
+
+        for (param, checked) in function.params.iter().zip(&provisional.params) {
+            self.bind_parameter(param, &checked.ty);
+        }
+        let body = self.check_body(&function.body, true);
+        let span = function
+            .body
+            .iter()
+            .rev()
+            .find_map(statement_span)
+            .unwrap_or_else(|| function.func_name.span());
+        let origin = function
+            .return_type
+            .as_ref()
+            .map(|declared| (declared.span(), "return type declared here"));
+        self.require(&body, &provisional.ret, &span, origin);
+        if let (Type::Unknown, Type::Var(var)) =
+            (self.resolve(&body), self.resolve(&provisional.ret))
+        {
+            self.substitution[var] = Some(Type::Unknown);
+        }
+
+        // Whatever is still a variable now is generic.
+        let params = provisional
+            .params
+            .into_iter()
+            .map(|param| Param {
+                ty: self.resolve(&param.ty),
+                ..param
+            })
+            .collect_vec();
+        let mut ret = self.resolve(&provisional.ret);
+        // A result no parameter or requirement ties down is never produced:
+        // every path through the body ends in a call that does not return.
+        if let Type::Var(var) = ret {
+            let free = self.constraints[var].is_empty()
+                && !params.iter().any(|param| self.occurs(var, &param.ty));
+            if free {
+                ret = Type::Never;
+            }
+        }
+        trace!("[EndOf] typeck:clause({})", function.func_name);
+        Clause {
+            params,
+            ret,
+            span: function.func_name.span(),
+            generic: true,
+        }
+    }
+
+    /// Brings the names bound by `param`, which accepts `ty`, into scope.
+    fn bind_parameter(&mut self, param: &FunctionParameter, ty: &Type) {
+        match param {
+            FunctionParameter::NamedAndTyped { name, .. }
+            | FunctionParameter::NamedDynamic { name } => {
+                self.define(name.span(), ty.clone());
+            }
+            FunctionParameter::Anonymous { ty: annotation } => {
+                self.define(annotation.span(), ty.clone());
+            }
+            FunctionParameter::Cons { head, tail, .. } => {
+                let element = ty.element().unwrap_or(Type::Unknown);
+                self.define(head.span(), element);
+                self.define(tail.span(), ty.clone());
+            }
+            FunctionParameter::Variant { name, fields, .. } => {
+                let Some((owner, variant)) = self.variants.get(&name.to_string()).cloned() else {
+                    return;
+                };
+                let args = self.type_args(&owner, ty);
+                for (field, declared) in fields.iter().zip(&variant.fields) {
+                    let expected = self.annotation(declared).substitute(&args);
+                    let ty = match field.ty() {
+                        Some(annotation) => {
+                            let annotation = self.annotation(annotation);
+                            let origin = (declared.span(), "field declared here");
+                            self.require(&annotation, &expected, &field.span(), Some(origin));
+                            annotation
+                        }
+                        None => expected,
+                    };
+                    self.bind_parameter(field, &ty);
+                }
+            }
+            FunctionParameter::Literal { .. }
+            | FunctionParameter::Wildcard { .. }
+            | FunctionParameter::Empty { .. } => {}
+        }
+    }
+
+    /// The type of a body: that of its last statement. The values of the
+    /// others are discarded, as is that of the last one unless `used`.
+    fn check_body(&mut self, body: &[Statement], used: bool) -> Type {
+        let mut ty = Type::Unit;
+        for (index, stmt) in body.iter().enumerate() {
+            ty = self.check_statement(stmt, used && index + 1 == body.len());
+        }
+        ty
+    }
+
+    /// The type of `body`, and the span of the value it produces.
+    fn check_block(&mut self, body: &[Statement], used: bool) -> (Type, Option<Span>) {
+        let ty = self.check_body(body, used);
+        (ty, body.iter().rev().find_map(statement_span))
+    }
+
+    /// Checks `stmt`; branches only need to agree on a type if its value is
+    /// `used`.
+    fn check_statement(&mut self, stmt: &Statement, used: bool) -> Type {
+        match stmt {
+            Statement::Nop => Type::Unit,
+            Statement::Expression(expr) => match &**expr {
+                Expression::Do {
+                    branches,
+                    default_branch,
+                    ..
+                } => self.check_do(branches, default_branch, used),
+                Expression::Match {
+                    scrutinee,
+                    branches,
+                    span,
+                } => self.check_match(scrutinee, branches, span, used),
+                expr => self.check_expr(expr),
+            },
+            Statement::Assignment { ident, typ, value } => {
+                let value_ty = self.check_expr(value);
+                let ty = match typ {
+                    Some(typ) => {
+                        let expected = self.annotation(typ);
+                        let origin = (typ.span(), "expected due to this annotation");
+                        self.require(&value_ty, &expected, &value.span(), Some(origin));
+                        expected
+                    }
+                    None => self.resolve(&value_ty),
+                };
+                self.define(ident.span(), ty.clone());
+                ty
+            }
+            Statement::Declaration { ident, typ } => {
+                self.define(ident.span(), self.annotation(typ));
+                Type::Unit
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_ifs,
+                else_branch,
+                span,
+                ..
+            } => {
+                self.check_condition(condition, "if");
+                let mut branches = vec![self.check_block(then_branch, used)];
+                for else_if in else_ifs {
+                    self.check_condition(&else_if.condition, "else if");
+                    branches.push(self.check_block(&else_if.body, used));
+                }
+                if let Some(body) = else_branch {
+                    branches.push(self.check_block(body, used));
+                }
+                let branches = branches
+                    .into_iter()
+                    .map(|(ty, last)| (ty, last.unwrap_or_else(|| span.clone())))
+                    .collect();
+                let ty = self.join(branches, used);
+
+                // Without an `else`, nothing runs when every condition fails.
+                match else_branch {
+                    Some(_) => ty,
+                    None if used && !self.fits(&Type::Unit, &ty, span) => {
+                        self.errors.push(TypeError::MissingElse {
+                            expected: self.resolve(&ty),
+                            span: span.clone(),
+                        });
+                        Type::Unknown
+                    }
+                    None => Type::Unit,
+                }
+            }
+            Statement::Error { .. } => Type::Unknown,
+        }
+    }
+
+    /// Checks the condition of `construct`, which must be a `bool`.
+    fn check_condition(&mut self, condition: &Expression, construct: &'static str) {
+        let ty = self.check_expr(condition);
+        match self.resolve(&ty) {
+            Type::Var(var) => self.bind(var, Type::Bool, &condition.span()),
+            ty if ty.conforms(&Type::Bool) => {}
+            found => self.errors.push(TypeError::NonBoolCondition {
+                construct,
+                found,
+                span: condition.span(),
+            }),
+        }
+    }
+
+    fn check_expr(&mut self, expr: &Expression) -> Type {
+        match expr {
+            Expression::Atom(Atom::Literal { literal, .. }) => literal_type(literal),
+            Expression::Atom(Atom::Ident(ident)) => self.lookup(ident),
+            Expression::BinaryOperation {
+                lhs,
+                operator,
+                rhs,
+                span,
+            } => self.check_binary(lhs, operator, rhs, span),
+            Expression::PrefixOperation {
+                operator,
+                rhs,
+                span,
+            } => self.check_prefix(operator, rhs, span),
+            Expression::PostfixOperation {
+                lhs,
+                operator,
+                span,
+            } => {
+                let ty = self.check_expr(lhs);
+                if matches!(operator, Operator::Range) {
+                    self.require(&ty, &Type::Int, &lhs.span(), None);
+                    return Type::Generator(Box::new(Type::Int));
+                }
+                self.step(&ty, operator, span)
+            }
+            Expression::Call { lhs, params, span } => {
+                let callee = self.check_expr(lhs);
+                let args = self.check_arguments(params.as_deref());
+                self.apply(&callee, &args, span)
+            }
+            Expression::Interpolation { parts, .. } => {
+                for part in parts {
+                    if let StringPart::Expression(expr) = part {
+                        self.check_expr(expr);
+                    }
+                }
+                Type::Str
+            }
+            Expression::Pipe {
+                receiver,
+                name,
+                params,
+                span,
+            } => {
+                let receiver = receiver
+                    .as_deref()
+                    .map(|receiver| (self.check_expr(receiver), receiver.span()));
+                let mut args = self.check_arguments(params.as_deref());
+
+                if let Some((Type::Named(ty, receiver_args), _)) = receiver
+                    .as_ref()
+                    .map(|(receiver, span)| (self.resolve(receiver), span))
+                {
+                    let name = name.to_string();
+                    let method = Key::Method {
+                        ty: ty.clone(),
+                        name: name.clone(),
+                    };
+                    if !self.clauses_of(&method).0.is_empty() {
+                        let callee = Type::Callable(Callee::Method {
+                            ty,
+                            name,
+                            args: receiver_args,
+                        });
+                        return self.apply(&callee, &args, span);
+                    }
+                }
+                if let (Some((receiver, _)), Some(DefKind::Method)) =
+                    (&receiver, self.kind_of(name))
+                {
+                    return self.pipe_method(receiver, name, &args, span);
+                }
+                args.splice(0..0, receiver);
+                let callee = self.lookup(name);
+                self.apply(&callee, &args, span)
+            }
+            Expression::Assignment {
+                declare,
+                name,
+                typ,
+                value,
+                span,
+            } => {
+                let value_ty = match (value, typ) {
+                    (Some(value), _) => self.check_expr(value),
+                    (None, Some(typ)) => self.annotation(typ),
+                    (None, None) => Type::Unit,
+                };
+                let value_span = value.as_ref().map_or_else(|| span.clone(), |v| v.span());
+                let ty = match typ {
+                    Some(typ) => {
+                        let expected = self.annotation(typ);
+                        let origin = (typ.span(), "expected due to this annotation");
+                        self.require(&value_ty, &expected, &value_span, Some(origin));
+                        expected
+                    }
+                    None => self.resolve(&value_ty),
+                };
+
+                if *declare {
+                    self.define(name.span(), ty.clone());
+                } else {
+                    self.assign_to_binding(name, &value_ty, &value_span);
+                }
+                ty
+            }
+            Expression::Do {
+                branches,
+                default_branch,
+                ..
+            } => self.check_do(branches, default_branch, true),
+            Expression::Match {
+                scrutinee,
+                branches,
+                span,
+            } => self.check_match(scrutinee, branches, span, true),
+        }
+    }
+
+    /// Checks a `do` guard chain, whose branches must agree on a type if its
+    /// value is `used`.
+    fn check_do(&mut self, branches: &[DoBranch], default_branch: &DoBranch, used: bool) -> Type {
+        let mut types = vec![];
+        for branch in branches {
+            self.check_condition(&branch.condition, "do");
+            types.push((self.check_expr(&branch.behavior), branch.behavior.span()));
+        }
+        let default = &default_branch.behavior;
+        types.push((self.check_expr(default), default.span()));
+        self.join(types, used)
+    }
+
+    /// Checks each branch with the names its pattern binds in scope, and that
+    /// some branch matches every value.
+    fn check_match(
+        &mut self,
+        scrutinee: &Expression,
+        branches: &[MatchBranch],
+        span: &Span,
+        used: bool,
+    ) -> Type {
+        let ty = self.check_expr(scrutinee);
+        let scrutinee_span = scrutinee.span();
+        let mut types = vec![];
+        for MatchBranch { pattern, behavior } in branches {
+            let declared = self.declared_param(pattern);
+            // Branches on a value of unknown type may each expect another.
+            let bound = match self.resolve(&ty) {
+                scrutinee if scrutinee.is_unknown() => match declared.ty.is_unknown() {
+                    true => scrutinee,
+                    false => declared.ty.clone(),
+                },
+                scrutinee => {
+                    let origin = (scrutinee_span.clone(), "matched value has this type");
+                    self.require(&declared.ty, &scrutinee, &declared.span, Some(origin));
+                    scrutinee
+                }
+            };
+
+            self.bind_parameter(pattern, &bound);
+            types.push((self.check_expr(behavior), behavior.span()));
+        }
+        let rows = branches
+            .iter()
+            .map(|branch| vec![&branch.pattern])
+            .collect_vec();
+        if let Some(missing) = self.coverage().uncovered(&rows, 1) {
+            self.errors.push(TypeError::NonExhaustive {
+                missing: missing.iter().join(", "),
+                case: "branch",
+                span: span.clone(),
+            });
+        }
+        self.join(types, used)
+    }
+
+    fn check_arguments(&mut self, params: Option<&Expression>) -> Vec<(Type, Span)> {
+        params
+            .map(Expression::arguments)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|arg| (self.check_expr(arg), arg.span()))
+            .collect()
+    }
+
+    fn check_binary(
+        &mut self,
+        lhs: &Expression,
+        operator: &Operator,
+        rhs: &Expression,
+        span: &Span,
+    ) -> Type {
+        match operator {
+            Operator::FieldAccess => {
+                let receiver = self.check_expr(lhs);
+                self.field(&receiver, rhs, span)
+            }
+            Operator::Assign => {
+                let value = self.check_expr(rhs);
+                self.assign_to(lhs, &value, &rhs.span());
+                value
+            }
+            Operator::Comma => {
+                self.check_expr(lhs);
+                self.check_expr(rhs)
+            }
+            Operator::And | Operator::Or => {
+                for operand in [lhs, rhs] {
+                    let ty = self.check_expr(operand);
+                    self.require(&ty, &Type::Bool, &operand.span(), None);
+                }
+                Type::Bool
+            }
+            Operator::Eq | Operator::Neq => {
+                let lhs = self.check_expr(lhs);
+                let rhs = self.check_expr(rhs);
+                let (lhs, rhs) = (self.resolve(&lhs), self.resolve(&rhs));
+                // `int`s and `float`s compare by value; anything else only
+                // compares with its own type.
+                let numbers = [&lhs, &rhs]
+                    .iter()
+                    .all(|ty| matches!(ty, Type::Int | Type::Float));
+                if !numbers && !self.fits(&rhs, &lhs, span) {
+                    self.errors.push(TypeError::InvalidOperands {
+                        operator: operator.clone(),
+                        lhs,
+                        rhs,
+                        span: span.clone(),
+                    });
+                }
+                Type::Bool
+            }
+            Operator::Range | Operator::RangeInclusive => {
+                for operand in [lhs, rhs] {
+                    let ty = self.check_expr(operand);
+                    self.require(&ty, &Type::Int, &operand.span(), None);
+                }
+                Type::Generator(Box::new(Type::Int))
+            }
+            _ => {
+                let lhs = self.check_expr(lhs);
+                let rhs = self.check_expr(rhs);
+                if let Some(ty) = self.infer_operands(&lhs, operator, &rhs, span) {
+                    return ty;
+                }
+                let (lhs, rhs) = (self.resolve(&lhs), self.resolve(&rhs));
+                binary(&lhs, operator, &rhs).unwrap_or_else(|| {
+                    self.errors.push(TypeError::InvalidOperands {
+                        operator: operator.clone(),
+                        lhs,
+                        rhs,
+                        span: span.clone(),
+                    });
+                    Type::Unknown
+                })
+            }
+        }
+    }
+
+    /// Infers what type variable operands of an arithmetic, comparison or
+    /// bitwise operator must be. `None` if there are none, or if one was bound
+    /// to the other operand's type and the operation can be checked as usual.
+    fn infer_operands(
+        &mut self,
+        lhs: &Type,
+        operator: &Operator,
+        rhs: &Type,
+        span: &Span,
+    ) -> Option<Type> {
+        let constraint = Constraint::of(operator)?;
+        let comparison = constraint == Constraint::Ord;
+        match (self.resolve(lhs), self.resolve(rhs)) {
+            (Type::Var(lhs), Type::Var(rhs)) => {
+                self.bind(lhs, Type::Var(rhs), span);
+                self.constrain(rhs, constraint, span);
+                Some(match comparison {
+                    true => Type::Bool,
+                    false => Type::Var(rhs),
+                })
+            }
+            (Type::Var(var), other) | (other, Type::Var(var)) => match other {
+                Type::Unknown => {
+                    self.constrain(var, constraint, span);
+                    Some(match comparison {
+                        true => Type::Bool,
+                        false => Type::Unknown,
+                    })
+                }
+                // Numbers mix, so either kind works with a number; the result
+                // is a `float` if either is.
+                Type::Int | Type::Float if constraint != Constraint::Bits => {
+                    self.constrain(var, Constraint::Num, span);
+                    Some(match (comparison, other) {
+                        (true, _) => Type::Bool,
+                        (false, Type::Float) => Type::Float,
+                        (false, _) => Type::Var(var),
+                    })
+                }
+                // Anything else only combines with its own type.
+                other => {
+                    self.bind(var, other, span);
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+
+    fn check_prefix(&mut self, operator: &Operator, rhs: &Expression, span: &Span) -> Type {
+        let ty = self.check_expr(rhs);
+        let required = match operator {
+            Operator::Inc | Operator::Dec => return self.step(&ty, operator, span),
+            Operator::Range | Operator::RangeInclusive => {
+                self.require(&ty, &Type::Int, &rhs.span(), None);
+                return Type::Generator(Box::new(Type::Int));
+            }
+            Operator::Not => Some(Type::Bool),
+            Operator::BitNot => Some(Type::Int),
+            _ => None,
+        };
+
+        match (operator, self.resolve(&ty)) {
+            (Operator::Add | Operator::Subtract, Type::Var(var)) => {
+                self.constrain(var, Constraint::Num, span);
+                Type::Var(var)
+            }
+            (_, Type::Var(var)) if required.is_some() => {
+                let required = required.unwrap_or(Type::Unknown);
+                self.bind(var, required.clone(), span);
+                required
+            }
+            (_, ty) if ty.is_unknown() => required.unwrap_or(Type::Unknown),
+            (Operator::Add | Operator::Subtract, ty @ (Type::Int | Type::Float)) => ty,
+            (Operator::Not, Type::Bool) => Type::Bool,
+            (Operator::BitNot, Type::Int) => Type::Int,
+            (_, operand) => {
+                self.errors.push(TypeError::InvalidOperand {
+                    operator: operator.clone(),
+                    operand,
+                    span: span.clone(),
+                });
+                Type::Unknown
+            }
+        }
+    }
+
+    /// `++`/`--` applied to a value of type `ty`.
+    fn step(&mut self, ty: &Type, operator: &Operator, span: &Span) -> Type {
+        match self.resolve(ty) {
+            ty @ (Type::Int | Type::Float) => ty,
+            Type::Var(var) => {
+                self.constrain(var, Constraint::Num, span);
+                Type::Var(var)
+            }
+            ty if ty.is_unknown() => ty,
+            operand => {
+                self.errors.push(TypeError::InvalidOperand {
+                    operator: operator.clone(),
+                    operand,
+                    span: span.clone(),
+                });
+                Type::Unknown
+            }
+        }
+    }
+
+    /// `receiver.name`: a field, or a method bound to `receiver`.
+    fn field(&mut self, receiver: &Type, rhs: &Expression, span: &Span) -> Type {
+        let Expression::Atom(Atom::Ident(field)) = rhs else {
+            return Type::Unknown;
+        };
+        let name = field.to_string();
+
+        let receiver = self.resolve(receiver);
+        let (ty, receiver_args) = match &receiver {
+            Type::Named(ty, args) if self.types.contains_key(ty) => (ty.clone(), args.clone()),
+            receiver if receiver.is_unknown() => return Type::Unknown,
+            Type::Named(..) => return Type::Unknown,
+            receiver => {
+                self.errors.push(TypeError::UnknownField {
+                    ty: receiver.clone(),
+                    field: name,
+                    span: span.clone(),
+                });
+                return Type::Unknown;
+            }
+        };
+
+        if let Some(field) = self.fields(&ty).into_iter().find(|f| f.name == name) {
+            return field.ty.substitute(&self.type_args(&ty, &receiver));
+        }
+        let method = Key::Method {
+            ty: ty.clone(),
+            name: name.clone(),
+        };
+        if !self.clauses_of(&method).0.is_empty() {
+            return Type::Callable(Callee::Method {
+                ty,
+                name,
+                args: receiver_args,
+            });
+        }
+        self.errors.push(TypeError::UnknownField {
+            ty: receiver,
+            field: name,
+            span: span.clone(),
+        });
+        Type::Unknown
+    }
+
+    fn fields(&self, ty: &str) -> Vec<Field> {
+        let Some(definition) = self.types.get(ty) else {
+            return vec![];
+        };
+        definition
+            .fields
+            .iter()
+            .filter_map(|field| match &field.definition {
+                Expression::Assignment {
+                    name, typ, value, ..
+                } => Some(Field {
+                    name: name.to_string(),
+                    ty: typ
+                        .as_ref()
+                        .map_or(Type::Unknown, |typ| self.annotation(typ)),
+                    required: value.is_none(),
+                    span: name.span(),
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks a value stored into the place denoted by `target`.
+    fn assign_to(&mut self, target: &Expression, value: &Type, value_span: &Span) {
+        match target {
+            Expression::Atom(Atom::Ident(ident)) => {
+                self.assign_to_binding(ident, value, value_span)
+            }
+            Expression::BinaryOperation {
+                lhs,
+                operator: Operator::FieldAccess,
+                rhs,
+                span,
+            } => {
+                let receiver = self.check_expr(lhs);
+                let field = self.field(&receiver, rhs, span);
+                let Expression::Atom(Atom::Ident(name)) = &**rhs else {
+                    return;
+                };
+                let declared = match self.resolve(&receiver) {
+                    Type::Named(ty, _) => self
+                        .fields(&ty)
+                        .into_iter()
+                        .find(|field| field.name == name.to_string()),
+                    _ => None,
+                };
+                if let Some(declared) = declared {
+                    let origin = (declared.span, "field declared here");
+                    self.require(value, &field, value_span, Some(origin));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn assign_to_binding(&mut self, ident: &Ident, value: &Type, value_span: &Span) {
+        if let Some(binding) = self.binding(ident) {
+            let origin = (binding.span, "variable declared here");
+            self.require(value, &binding.ty, value_span, Some(origin));
+        }
+    }
+
+    /// Checks a call of `callee` with arguments of the given types.
+    fn apply(&mut self, callee: &Type, args: &[(Type, Span)], span: &Span) -> Type {
+        match self.resolve(callee) {
+            Type::Callable(Callee::Function(name)) => {
+                let clauses = self.signature(&Key::Function(name.clone()));
+                self.call_clauses(&name, &clauses, &HashMap::new(), args, span)
+            }
+            Type::Callable(Callee::Method {
+                ty,
+                name,
+                args: receiver_args,
+            }) => {
+                let bound = self.type_args(&ty, &Type::Named(ty.clone(), receiver_args));
+                let clauses = self.signature(&Key::Method {
+                    ty,
+                    name: name.clone(),
+                });
+                self.call_clauses(&name, &clauses, &bound, args, span)
+            }
+            Type::Callable(Callee::Constructor(name)) => self.construct(&name, args, span),
+            Type::Callable(Callee::Variant(name)) => self.construct_variant(&name, args, span),
+            Type::Callable(Callee::Builtin(builtin)) => self.builtin(builtin, args, span),
+            // Calling a parameter infers that it takes these arguments.
+            Type::Var(var) => {
+                let params = args.iter().map(|_| self.fresh()).collect_vec();
+                let ret = self.fresh();
+                let function = Type::Function {
+                    params,
+                    ret: Box::new(ret),
+                };
+                self.bind(var, function.clone(), span);
+                self.apply(&function, args, span)
+            }
+            Type::Function { params, ret } if params.len() == args.len() => {
+                for (param, (arg, arg_span)) in params.iter().zip(args) {
+                    self.require(arg, param, arg_span, None);
+                }
+                self.resolve(&ret)
+            }
+            Type::Function { params, ret } => {
+                let expected = params.len().to_string();
+                self.errors.push(TypeError::ArityMismatch {
+                    callee: Type::Function { params, ret }.to_string(),
+                    expected,
+                    actual: args.len(),
+                    span: span.clone(),
+                    definition: None,
+                });
+                Type::Unknown
+            }
+            ty if ty.is_unknown() => Type::Unknown,
+            ty => {
+                self.errors.push(TypeError::NotCallable {
+                    ty,
+                    span: span.clone(),
+                });
+                Type::Unknown
+            }
+        }
+    }
+
+    /// A call runs the first clause whose parameters accept its arguments;
+    /// its type is the return type all such clauses agree on. `bound` holds
+    /// the type arguments of a method's receiver.
+    fn call_clauses(
+        &mut self,
+        name: &str,
+        clauses: &[Clause],
+        bound: &HashMap<String, Type>,
+        args: &[(Type, Span)],
+        span: &Span,
+    ) -> Type {
+        let candidates = clauses
+            .iter()
+            .filter(|clause| clause.params.len() == args.len())
+            .map(|clause| self.instantiate(clause, bound))
+            .collect_vec();
+        if candidates.is_empty() {
+            self.errors.push(TypeError::ArityMismatch {
+                callee: name.to_owned(),
+                expected: clauses
+                    .iter()
+                    .map(|clause| clause.params.len())
+                    .unique()
+                    .sorted()
+                    .join(" or "),
+                actual: args.len(),
+                span: span.clone(),
+                definition: clauses.first().map(|clause| clause.span.clone()),
+            });
+            return Type::Unknown;
+        }
+
+        let arg_types = args.iter().map(|(ty, _)| self.resolve(ty)).collect_vec();
+        let matching = candidates
+            .iter()
+            .filter(|clause| {
+                clause
+                    .params
+                    .iter()
+                    .zip(&arg_types)
+                    .all(|(param, arg)| self.accepts(param, arg))
+            })
+            .collect_vec();
+
+        match (&candidates[..], &matching[..]) {
+            // A lone candidate reports exactly which argument it rejects.
+            ([clause], []) => {
+                let rejected = clause
+                    .params
+                    .iter()
+                    .zip(arg_types.into_iter().zip(args))
+                    .find(|(param, (arg, _))| !self.accepts(param, arg));
+                match rejected {
+                    // Binding reports the constraint it violates.
+                    Some((param, (found, (_, span)))) if self.resolve(&param.ty).is_var() => {
+                        self.fits(&found, &param.ty, span);
+                    }
+                    Some((param, (found, (_, span)))) => {
+                        self.errors.push(reject(name, param, found, span));
+                    }
+                    None => {}
+                }
+                Type::Unknown
+            }
+            // Only one clause can run, so its parameter types are the
+            // argument types.
+            ([clause], _) => {
+                // Earlier arguments may have pinned down what later ones must be.
+                for (param, (arg, arg_span)) in clause.params.iter().zip(args) {
+                    if !self.fits(arg, &param.ty, arg_span) {
+                        let param = Param {
+                            ty: self.resolve(&param.ty),
+                            ..param.clone()
+                        };
+                        let found = self.resolve(arg);
+                        self.errors.push(reject(name, &param, found, arg_span));
+                    }
+                    if let (true, Type::Var(var)) = (param.sequence, self.resolve(arg)) {
+                        self.constrain(var, Constraint::Seq, arg_span);
+                    }
+                }
+                self.resolve(&clause.ret)
+            }
+            // Whichever clause the values select runs, so every clause they
+            // may select must take the arguments.
+            (candidates, _) => {
+                let reachable = candidates
+                    .iter()
+                    .filter(|clause| {
+                        clause
+                            .params
+                            .iter()
+                            .zip(&arg_types)
+                            .all(|(param, arg)| self.reaches(param, arg))
+                    })
+                    .collect_vec();
+                if reachable.is_empty() {
+                    self.errors.push(TypeError::NoMatchingClause {
+                        callee: name.to_owned(),
+                        args: arg_types.iter().join(", "),
+                        span: span.clone(),
+                    });
+                    return Type::Unknown;
+                }
+                for clause in &reachable {
+                    let rejected = clause
+                        .params
+                        .iter()
+                        .zip(args)
+                        .find(|(param, (arg, arg_span))| !self.fits(arg, &param.ty, arg_span));
+                    if let Some((param, (arg, arg_span))) = rejected {
+                        let param = Param {
+                            ty: self.resolve(&param.ty),
+                            ..param.clone()
+                        };
+                        let found = self.resolve(arg);
+                        self.errors.push(reject(name, &param, found, arg_span));
+                    }
+                }
+
+                // Clauses still being checked have no say until they are.
+                let known = reachable
+                    .iter()
+                    .map(|clause| (clause.generic, self.resolve(&clause.ret)))
+                    .filter(|(generic, ret)| *generic || !ret.is_var())
+                    .map(|(_, ret)| ret)
+                    .collect_vec();
+                match known.is_empty() {
+                    true => Type::Unknown,
+                    false => Type::join(known),
+                }
+            }
+        }
+    }
+
+    /// Checks a constructor call of `ty`, whose arguments fill its fields in
+    /// declaration order.
+    fn construct(&mut self, ty: &str, args: &[(Type, Span)], span: &Span) -> Type {
+        let (instance, type_args) = self.instance(ty);
+        let fields = self.fields(ty);
+        let required = fields
+            .iter()
+            .rposition(|field| field.required)
+            .map_or(0, |last| last + 1);
+        if args.len() < required || args.len() > fields.len() {
+            self.errors.push(TypeError::ArityMismatch {
+                callee: ty.to_owned(),
+                expected: match required == fields.len() {
+                    true => fields.len().to_string(),
+                    false => format!("{} to {}", required, fields.len()),
+                },
+                actual: args.len(),
+                span: span.clone(),
+                definition: self.types.get(ty).map(|definition| definition.name.span()),
+            });
+            return instance;
+        }
+
+        for (field, (arg, arg_span)) in fields.into_iter().zip(args) {
+            let origin = (field.span, "field declared here");
+            self.require(
+                arg,
+                &field.ty.substitute(&type_args),
+                arg_span,
+                Some(origin),
+            );
+        }
+        instance
+    }
+
+    /// Checks a call of the variant `name`, with one argument per field.
+    fn construct_variant(&mut self, name: &str, args: &[(Type, Span)], span: &Span) -> Type {
+        let Some((ty, variant)) = self.variants.get(name).cloned() else {
+            return Type::Unknown;
+        };
+        let (instance, type_args) = self.instance(&ty);
+        if args.len() != variant.fields.len() {
+            self.errors.push(TypeError::ArityMismatch {
+                callee: name.to_owned(),
+                expected: variant.fields.len().to_string(),
+                actual: args.len(),
+                span: span.clone(),
+                definition: Some(variant.name.span()),
+            });
+            return instance;
+        }
+
+        for (field, (arg, arg_span)) in variant.fields.iter().zip(args) {
+            let origin = (field.span(), "field declared here");
+            let expected = self.annotation(field).substitute(&type_args);
+            self.require(arg, &expected, arg_span, Some(origin));
+        }
+        instance
+    }
+
+    /// A value of the type `ty` with a fresh variable for each of its type
+    /// parameters, and the parameters mapped to them.
+    fn instance(&mut self, ty: &str) -> (Type, HashMap<String, Type>) {
+        let params = self.type_params(ty);
+        let args = params.iter().map(|_| self.fresh()).collect_vec();
+        let type_args = params.into_iter().zip(args.clone()).collect();
+        (Type::Named(ty.to_owned(), args), type_args)
+    }
+
+    /// The type `ty` as seen from its own fields and methods, where its type
+    /// parameters stand for themselves.
+    fn own_type(&self, ty: &str) -> Type {
+        let params = self.type_params(ty);
+        Type::Named(ty.to_owned(), params.into_iter().map(Type::Param).collect())
+    }
+
+    /// The type parameters of `ty` mapped to the type arguments of
+    /// `instance`, a value of it; to `Unknown` where it has none.
+    fn type_args(&self, ty: &str, instance: &Type) -> HashMap<String, Type> {
+        let args = match self.resolve(instance) {
+            Type::Named(name, args) if name == ty => args,
+            _ => vec![],
+        };
+        self.type_params(ty)
+            .into_iter()
+            .enumerate()
+            .map(|(index, param)| (param, args.get(index).cloned().unwrap_or(Type::Unknown)))
+            .collect()
+    }
+
+    /// The type written as `ty`. Type arguments left out of a generic type
+    /// are `Unknown`, so they are not checked.
+    fn annotation(&self, ty: &TypeExpr) -> Type {
+        self.complete(Type::from_expr(ty))
+    }
+
+    fn complete(&self, ty: Type) -> Type {
+        match ty {
+            Type::Named(name, args) if args.is_empty() => {
+                let args = self
+                    .type_params(&name)
+                    .iter()
+                    .map(|_| Type::Unknown)
+                    .collect();
+                Type::Named(name, args)
+            }
+            Type::Named(name, args) => Type::Named(
+                name,
+                args.into_iter().map(|arg| self.complete(arg)).collect(),
+            ),
+            Type::Array(element) => Type::Array(Box::new(self.complete(*element))),
+            Type::Generator(element) => Type::Generator(Box::new(self.complete(*element))),
+            ty => ty,
+        }
+    }
+
+    fn type_params(&self, ty: &str) -> Vec<String> {
+        self.types
+            .get(ty)
+            .map(|definition| definition.generics.iter().map(Ident::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    fn builtin(&mut self, builtin: Builtin, args: &[(Type, Span)], span: &Span) -> Type {
+        let arity = match builtin {
+            Builtin::Print | Builtin::Println => return Type::Unit,
+            Builtin::Assert | Builtin::Collect => 1,
+            Builtin::AssertEq => 2,
+        };
+        if args.len() != arity {
+            self.errors.push(TypeError::ArityMismatch {
+                callee: builtin.name().to_owned(),
+                expected: arity.to_string(),
+                actual: args.len(),
+                span: span.clone(),
+                definition: None,
+            });
+            return Type::Unknown;
+        }
+
+        match builtin {
+            Builtin::Assert => {
+                let (arg, arg_span) = &args[0];
+                self.require(arg, &Type::Bool, arg_span, None);
+                Type::Unit
+            }
+            Builtin::Collect => {
+                let (arg, arg_span) = &args[0];
+                if let Type::Var(var) = self.resolve(arg) {
+                    self.constrain(var, Constraint::Seq, arg_span);
+                }
+                match self.resolve(arg).element() {
+                    Some(element) => Type::Array(Box::new(element)),
+                    None => {
+                        self.errors.push(TypeError::NotSequence {
+                            callee: builtin.name().to_owned(),
+                            found: self.resolve(arg),
+                            span: arg_span.clone(),
+                        });
+                        Type::Unknown
+                    }
+                }
+            }
+            _ => Type::Unit,
+        }
+    }
+
+    /// The type of what `ident` was resolved to. Names that did not resolve
+    /// have already been reported, and are `Unknown`.
+    fn lookup(&mut self, ident: &Ident) -> Type {
+        let Some(id) = self.resolution.use_of(ident) else {
+            return Type::Unknown;
+        };
+        let Definition { name, kind, .. } = self.resolution.definition(id).clone();
+        match kind {
+            DefKind::Param | DefKind::Local | DefKind::SelfValue => self
+                .locals
+                .get(&id)
+                .map_or(Type::Unknown, |binding| binding.ty.clone()),
+            DefKind::Function => Type::Callable(Callee::Function(name)),
+            DefKind::Type => Type::Callable(Callee::Constructor(name)),
+            DefKind::Variant => match self.variants.get(&name).cloned() {
+                Some((ty, variant)) if variant.fields.is_empty() => self.instance(&ty).0,
+                _ => Type::Callable(Callee::Variant(name)),
+            },
+            DefKind::Builtin => Builtin::lookup(&name).map_or(Type::Unknown, |builtin| {
+                Type::Callable(Callee::Builtin(builtin))
+            }),
+            // Only a pipe calls a method by name, on a receiver it checks
+            // first.
+            DefKind::Method => Type::Unknown,
+        }
+    }
+
+    /// What kind of definition `ident` refers to.
+    fn kind_of(&self, ident: &Ident) -> Option<DefKind> {
+        let id = self.resolution.use_of(ident)?;
+        Some(self.resolution.definition(id).kind)
+    }
+
+    /// Checks a pipe to the method `name` on a receiver whose type does not
+    /// define it. If the receiver's type is still unknown and one type has
+    /// the method, the receiver is a value of that type.
+    fn pipe_method(
+        &mut self,
+        receiver: &Type,
+        name: &Ident,
+        args: &[(Type, Span)],
+        span: &Span,
+    ) -> Type {
+        let method = name.to_string();
+        let owners = self
+            .types
+            .values()
+            .filter(|ty| ty.methods.iter().any(|m| m.func_name.to_string() == method))
+            .map(|ty| ty.name.to_string())
+            .collect_vec();
+        match (self.resolve(receiver), &owners[..]) {
+            (Type::Var(_), [owner]) => {
+                let (instance, _) = self.instance(owner);
+                self.require(receiver, &instance, span, None);
+                let Type::Named(ty, args_of_ty) = self.resolve(&instance) else {
+                    return Type::Unknown;
+                };
+                let callee = Type::Callable(Callee::Method {
+                    ty,
+                    name: method,
+                    args: args_of_ty,
+                });
+                self.apply(&callee, args, span)
+            }
+            (found, _) if found.is_unknown() => Type::Unknown,
+            (found, _) => {
+                self.errors.push(TypeError::UnknownField {
+                    ty: found,
+                    field: method,
+                    span: name.span(),
+                });
+                Type::Unknown
+            }
+        }
+    }
+
+    /// The local `ident` refers to.
+    fn binding(&self, ident: &Ident) -> Option<Binding> {
+        let id = self.resolution.use_of(ident)?;
+        self.locals.get(&id).cloned()
+    }
+
+    /// Gives the local introduced at `span` the type `ty`.
+    fn define(&mut self, span: Span, ty: Type) {
+        if let Some(id) = self.resolution.binding(&span) {
+            self.locals.insert(id, Binding { ty, span });
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.substitution.push(None);
+        self.constraints.push(vec![]);
+        Type::Var(self.substitution.len() - 1)
+    }
+
+    /// A copy of `clause` for one call. Declared type parameters are
+    /// replaced by what `bound` maps them to, or fresh type variables; the
+    /// inferred variables of a generic clause are fresh too, so that every
+    /// call infers them anew.
+    fn instantiate(&mut self, clause: &Clause, bound: &HashMap<String, Type>) -> Clause {
+        let mut args = bound.clone();
+        let declared = clause
+            .params
+            .iter()
+            .map(|param| &param.ty)
+            .chain([&clause.ret])
+            .flat_map(|ty| self.resolve(ty).params())
+            .collect_vec();
+        for name in declared {
+            args.entry(name).or_insert_with(|| self.fresh());
+        }
+
+        let mut fresh = HashMap::new();
+        let mut copy = |this: &mut Self, ty: &Type| {
+            let ty = match clause.generic {
+                true => this.freshen(ty, &mut fresh),
+                false => this.resolve(ty),
+            };
+            ty.substitute(&args)
+        };
+        let params = clause
+            .params
+            .iter()
+            .map(|param| Param {
+                ty: copy(self, &param.ty),
+                ..param.clone()
+            })
+            .collect();
+        let ret = copy(self, &clause.ret);
+        Clause {
+            params,
+            ret,
+            span: clause.span.clone(),
+            generic: clause.generic,
+        }
+    }
+
+    fn freshen(&mut self, ty: &Type, fresh: &mut HashMap<usize, Type>) -> Type {
+        match self.resolve(ty) {
+            Type::Var(var) => {
+                if let Some(ty) = fresh.get(&var) {
+                    return ty.clone();
+                }
+                let ty = self.fresh();
+                if let Type::Var(new) = ty {
+                    self.constraints[new] = self.constraints[var].clone();
+                }
+                fresh.insert(var, ty.clone());
+                ty
+            }
+            Type::Named(ty, args) => Type::Named(
+                ty,
+                args.iter().map(|arg| self.freshen(arg, fresh)).collect(),
+            ),
+            Type::Array(element) => Type::Array(Box::new(self.freshen(&element, fresh))),
+            Type::Generator(element) => Type::Generator(Box::new(self.freshen(&element, fresh))),
+            Type::Function { params, ret } => Type::Function {
+                params: params
+                    .iter()
+                    .map(|param| self.freshen(param, fresh))
+                    .collect(),
+                ret: Box::new(self.freshen(&ret, fresh)),
+            },
+            ty => ty,
+        }
+    }
+
+    /// Requires the unbound `var` to support `constraint` because of the use
+    /// at `span`.
+    fn constrain(&mut self, var: usize, constraint: Constraint, span: &Span) {
+        let constraints = &mut self.constraints[var];
+        if !constraints
+            .iter()
+            .any(|(existing, _)| *existing == constraint)
+        {
+            constraints.push((constraint, span.clone()));
+        }
+    }
+
+    /// Infers that the unbound `var` stands for `ty`, checking its
+    /// constraints.
+    fn bind(&mut self, var: usize, ty: Type, span: &Span) {
+        let ty = self.resolve(&ty);
+        if self.occurs(var, &ty) {
+            return;
+        }
+        let constraints = mem::take(&mut self.constraints[var]);
+        match &ty {
+            Type::Var(other) => {
+                for (constraint, required) in constraints {
+                    self.constrain(*other, constraint, &required);
+                }
+            }
+            ty => {
+                for (constraint, required) in constraints {
+                    if !constraint.admits(ty) {
+                        self.errors.push(TypeError::Unsatisfied {
+                            constraint,
+                            found: ty.clone(),
+                            span: span.clone(),
+                            required,
+                        });
+                    }
+                }
+            }
+        }
+        self.substitution[var] = Some(ty);
+    }
+
+    /// Whether `ty` mentions `var`, which then cannot stand for it.
+    fn occurs(&self, var: usize, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(other) => other == var,
+            Type::Array(element) | Type::Generator(element) => self.occurs(var, &element),
+            Type::Named(_, args) => args.iter().any(|arg| self.occurs(var, arg)),
+            Type::Function { params, ret } => {
+                params.iter().chain([&*ret]).any(|ty| self.occurs(var, ty))
+            }
+            _ => false,
+        }
+    }
+
+    /// What `param` accepts according to its annotation or pattern alone.
+    fn declared_param(&mut self, param: &FunctionParameter) -> Param {
+        let variant_of = match param {
+            FunctionParameter::Variant { name, .. } if !checks_types(param) => self
+                .variants
+                .get(&name.to_string())
+                .map(|(ty, _)| ty.clone()),
+            _ => None,
+        };
+        let ty = match param {
+            FunctionParameter::Literal { literal, .. } => literal_type(literal),
+            FunctionParameter::Variant { name, .. } => match self.variants.get(&name.to_string()) {
+                Some((ty, _)) => self.instance(&ty.clone()).0,
+                None => Type::Unknown,
+            },
+            param => param.ty().map_or(Type::Unknown, |ty| self.annotation(ty)),
+        };
+        Param {
+            ty,
+            sequence: matches!(
+                param,
+                FunctionParameter::Cons { .. } | FunctionParameter::Empty { .. }
+            ),
+            span: param.span(),
+            inferred: false,
+            variant_of,
+        }
+    }
+
+    /// Whether a value of type `arg` may select `param` at runtime, where
+    /// only annotations, literals and variants are checked.
+    fn reaches(&self, param: &Param, arg: &Type) -> bool {
+        if param.inferred {
+            return true;
+        }
+        match (&param.variant_of, self.resolve(arg)) {
+            (Some(owner), Type::Named(name, _)) => *owner == name,
+            (Some(_), arg) => arg.is_unknown(),
+            (None, arg) => self.accepts(param, &arg),
+        }
+    }
+
+    /// Whether `param` accepts an argument of type `arg` as far as is known.
+    fn accepts(&self, param: &Param, arg: &Type) -> bool {
+        let arg = self.resolve(arg);
+        let constraints = match self.resolve(&param.ty) {
+            Type::Var(var) => self.constraints[var].as_slice(),
+            _ => &[],
+        };
+        (!param.sequence || arg.element().is_some())
+            && arg.conforms(&self.resolve(&param.ty))
+            && (arg.is_var() || constraints.iter().all(|(c, _)| c.admits(&arg)))
+    }
+
+    /// `ty` with every inferred type variable replaced by what it stands for.
+    fn resolve(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match &self.substitution[*var] {
+                Some(ty) => self.resolve(ty),
+                None => ty.clone(),
+            },
+            Type::Named(ty, args) => Type::Named(
+                ty.clone(),
+                args.iter().map(|arg| self.resolve(arg)).collect(),
+            ),
+            Type::Array(element) => Type::Array(Box::new(self.resolve(element))),
+            Type::Generator(element) => Type::Generator(Box::new(self.resolve(element))),
+            Type::Function { params, ret } => Type::Function {
+                params: params.iter().map(|param| self.resolve(param)).collect(),
+                ret: Box::new(self.resolve(ret)),
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    /// The type of a construct that evaluates one of `branches`, each with
+    /// the span of the value it produces. Unless the value is discarded, every
+    /// branch must have the type of the first.
+    fn join(&mut self, branches: Vec<(Type, Span)>, used: bool) -> Type {
+        if !used {
+            return Type::Unit;
+        }
+        if branches.is_empty() {
+            return Type::Unit;
+        }
+        // Branches that never produce a value have no say.
+        let mut branches = branches
+            .into_iter()
+            .filter(|(ty, _)| self.resolve(ty) != Type::Never)
+            .collect_vec()
+            .into_iter();
+        let Some((joined, first)) = branches.next() else {
+            return Type::Never;
+        };
+        for (ty, span) in branches {
+            if !self.fits(&ty, &joined, &span) {
+                self.errors.push(TypeError::Mismatch {
+                    expected: self.resolve(&joined),
+                    found: self.resolve(&ty),
+                    span,
+                    origin: Some((first.clone(), "first branch has this type")),
+                });
+                return Type::Unknown;
+            }
+        }
+        self.resolve(&joined)
+    }
+
+    /// Checks that a value of type `actual` may be used where `expected` is
+    /// required, inferring type variables on either side.
+    fn require(
+        &mut self,
+        actual: &Type,
+        expected: &Type,
+        span: &Span,
+        origin: Option<(Span, &'static str)>,
+    ) {
+        if !self.fits(actual, expected, span) {
+            self.errors.push(TypeError::Mismatch {
+                expected: self.resolve(expected),
+                found: self.resolve(actual),
+                span: span.clone(),
+                origin,
+            });
+        }
+    }
+
+    /// Unifies `actual` with `expected` where they hold type variables.
+    /// Returns whether the value fits; violated constraints are reported
+    /// while binding.
+    fn fits(&mut self, actual: &Type, expected: &Type, span: &Span) -> bool {
+        match (self.resolve(actual), self.resolve(expected)) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Never, _) => true,
+            // Anything iterable can be consumed lazily, so a generator
+            // parameter does not pin down which sequence it is given.
+            (Type::Var(var), Type::Generator(_)) => {
+                self.constrain(var, Constraint::Seq, span);
+                true
+            }
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                self.bind(var, ty, span);
+                true
+            }
+            (Type::Array(actual), Type::Array(expected)) => self.fits(&actual, &expected, span),
+            // A function is passed where one is called: the values it will be
+            // called with must be accepted, and its result must fit.
+            (
+                Type::Function { params, ret },
+                Type::Function {
+                    params: expected,
+                    ret: expected_ret,
+                },
+            ) => {
+                params.len() == expected.len()
+                    && expected
+                        .iter()
+                        .zip(&params)
+                        .all(|(expected, param)| self.fits(expected, param, span))
+                    && self.fits(&ret, &expected_ret, span)
+            }
+            // A named function is checked by calling it, which reports the
+            // arguments it rejects.
+            (callee @ Type::Callable(_), Type::Function { params, ret })
+            | (Type::Function { params, ret }, callee @ Type::Callable(_)) => {
+                let args = params
+                    .into_iter()
+                    .map(|param| (param, span.clone()))
+                    .collect_vec();
+                let result = self.apply(&callee, &args, span);
+                self.fits(&result, &ret, span)
+            }
+            (Type::Named(actual, args), Type::Named(expected, params)) => {
+                actual == expected
+                    && args.len() == params.len()
+                    && args
+                        .iter()
+                        .zip(&params)
+                        .all(|(arg, param)| self.fits(arg, param, span))
+            }
+            (actual, Type::Generator(expected)) => match actual.element() {
+                Some(element) => self.fits(&element, &expected, span),
+                None => false,
+            },
+            (actual, expected) => actual == expected,
+        }
+    }
+}
+
+/// Whether the interpreter checks a type when matching `param`: it is
+/// annotated, a literal, or holds either.
+fn checks_types(param: &FunctionParameter) -> bool {
+    match param {
+        FunctionParameter::Literal { .. } => true,
+        FunctionParameter::Variant { fields, .. } => fields.iter().any(checks_types),
+        param => param.ty().is_some(),
+    }
+}
+
+/// The error for an argument of type `found` that `param` rejects.
+fn reject(callee: &str, param: &Param, found: Type, span: &Span) -> TypeError {
+    if param.sequence && found.element().is_none() {
+        return TypeError::NotSequence {
+            callee: callee.to_owned(),
+            found,
+            span: span.clone(),
+        };
+    }
+    let reason = match param.inferred {
+        true => "parameter type inferred from its uses",
+        false => "parameter declared here",
+    };
+    TypeError::Mismatch {
+        expected: param.ty.clone(),
+        found,
+        span: span.clone(),
+        origin: Some((param.span.clone(), reason)),
+    }
+}
+
+/// `A` to `Z`, then `A1`, `B1`, ...
+fn generic_name(index: usize) -> String {
+    let letter = char::from(b'A' + (index % 26) as u8);
+    match index / 26 {
+        0 => letter.to_string(),
+        round => format!("{}{}", letter, round),
+    }
+}
+
+fn literal_type(literal: &Literal) -> Type {
+    match literal {
+        Literal::Integer { .. } => Type::Int,
+        Literal::Float { .. } => Type::Float,
+        Literal::String(_) => Type::Str,
+        Literal::Char(_) => Type::Char,
+        Literal::Bool(_) => Type::Bool,
+    }
+}
+
+/// The type of an arithmetic, comparison or bitwise operation, mirroring the
+/// interpreter. `None` if the operands are not supported.
+fn binary(lhs: &Type, operator: &Operator, rhs: &Type) -> Option<Type> {
+    let arithmetic = matches!(
+        operator,
+        Operator::Add
+            | Operator::Subtract
+            | Operator::Multiply
+            | Operator::Divide
+            | Operator::Mod
+            | Operator::Pow
+    );
+    let comparison = matches!(
+        operator,
+        Operator::Greater | Operator::Lesser | Operator::GreaterEq | Operator::LesserEq
+    );
+    let bitwise = matches!(
+        operator,
+        Operator::BitAnd | Operator::BitOr | Operator::BitXor
+    );
+
+    if lhs.is_unknown() || rhs.is_unknown() {
+        let known = if lhs.is_unknown() { rhs } else { lhs };
+        return Some(match known {
+            _ if comparison => Type::Bool,
+            Type::Float if arithmetic => Type::Float,
+            Type::Str if matches!(operator, Operator::Add) => Type::Str,
+            Type::Int | Type::Bool if bitwise => known.clone(),
+            _ => Type::Unknown,
+        });
+    }
+
+    match (lhs, rhs) {
+        (Type::Int, Type::Int) if arithmetic || bitwise => Some(Type::Int),
+        (Type::Int | Type::Float, Type::Int | Type::Float) if arithmetic => Some(Type::Float),
+        (Type::Int | Type::Float, Type::Int | Type::Float) if comparison => Some(Type::Bool),
+        (Type::Str, Type::Str) if matches!(operator, Operator::Add) => Some(Type::Str),
+        (Type::Str, Type::Str) | (Type::Char, Type::Char) if comparison => Some(Type::Bool),
+        (Type::Bool, Type::Bool) if bitwise => Some(Type::Bool),
+        _ => None,
+    }
+}
+
+/// The span of the value a statement produces.
+fn statement_span(stmt: &Statement) -> Option<Span> {
+    match stmt {
+        Statement::Nop => None,
+        Statement::Expression(expr) => Some(expr.span()),
+        Statement::Assignment { value, .. } => Some(value.span()),
+        Statement::Declaration { ident, .. } => Some(ident.span()),
+        Statement::If { span, .. } | Statement::Error { span } => Some(span.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_str, resolve::resolve};
+
+    use super::check;
+
+    /// The message of every type error in `source`.
+    fn errors(source: &str) -> Vec<String> {
+        let module = parse_str(source).expect("source parses");
+        check(&module, &resolve(&module).resolution)
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// The signatures `--show-types` prints for `source`.
+    fn signatures(source: &str) -> Vec<String> {
+        let module = parse_str(source).expect("source parses");
+        let output = check(&module, &resolve(&module).resolution);
+        assert!(output.errors.is_empty(), "{:?}", output.errors);
+        output.signatures.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn do_conditions_must_be_bool() {
+        let source = "fn f (x int) => do {\n    x => 1\n    _ => 2\n}\n";
+        assert_eq!(
+            errors(source),
+            ["`do` condition must be `bool`, found `int`"]
+        );
+    }
+
+    #[test]
+    fn if_conditions_must_be_bool() {
+        let source = "fn f (x int) => {\n    if (x) {\n        1\n    } else if (\"no\") {\n        2\n    }\n    3\n}\n";
+        assert_eq!(
+            errors(source),
+            [
+                "`if` condition must be `bool`, found `int`",
+                "`else if` condition must be `bool`, found `str`"
+            ]
+        );
+    }
+
+    #[test]
+    fn used_if_needs_agreeing_branches_and_an_else() {
+        let source = "fn f (x bool) int => {\n    if (x) {\n        1\n    } else {\n        \"one\"\n    }\n}\n\nfn g (x bool) int => {\n    if (x) {\n        1\n    }\n}\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Mismatched types: expected `int`, found `str`",
+                "`if` without `else` has no `int` when its condition is false"
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_if_branches_may_differ() {
+        let source = "fn f (x bool) => {\n    if (x) {\n        1\n    } else {\n        \"one\"\n    }\n    2\n}\n";
+        assert!(errors(source).is_empty());
+    }
+
+    #[test]
+    fn range_bounds_must_be_int() {
+        let source = "fn f => 1.5..2\n\nfn g => ..=\"z\"\n\nfn h int* => 1..3\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Mismatched types: expected `int`, found `float`",
+                "Mismatched types: expected `int`, found `str`"
+            ]
+        );
+    }
+
+    #[test]
+    fn pipes_check_the_receiver_as_the_first_argument() {
+        let source = "fn add (x int, y int) => x + y\n\nfn f => 3:add\n\nfn g => \"a\":add(1)\n\nfn h => 1:add(2):add(3)\n";
+        assert_eq!(
+            errors(source),
+            [
+                "`add` expects 2 argument(s), got 1",
+                "Mismatched types: expected `int`, found `str`"
+            ]
+        );
+    }
+
+    #[test]
+    fn to_string_must_produce_str() {
+        let source = "type A {\n    fn to_string => 1\n}\n\ntype B {\n    fn to_string (x int) str => \"b\"\n}\n\ntype C {\n    fn to_string => \"c\"\n}\n";
+        assert_eq!(
+            errors(source),
+            [
+                "`A.to_string` must have type `fn() str`, found `fn() int`",
+                "`B.to_string` must have type `fn() str`, found `fn(int) str`"
+            ]
+        );
+    }
+
+    #[test]
+    fn annotations_must_match() {
+        let source = "fn f int => \"one\"\n\nfn g => {\n    let x str = 1\n    x\n}\n\nfn h (x int) => x\n\nfn main => h(true)\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Mismatched types: expected `int`, found `str`",
+                "Mismatched types: expected `str`, found `int`",
+                "Mismatched types: expected `int`, found `bool`"
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_must_pass_every_argument() {
+        let source = "fn two (a int, b int) => a + b\n\nfn f => two(1)\n\nfn g => two(1, 2, 3)\n\nfn h (k) => k(1) + k(1, 2)\n";
+        assert_eq!(
+            errors(source),
+            [
+                "`two` expects 2 argument(s), got 1",
+                "`two` expects 2 argument(s), got 3",
+                "`fn(int) _` expects 1 argument(s), got 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn operators_check_their_operands() {
+        let source = "fn f => 1 + true\n\nfn g => \"a\" - \"b\"\n\nfn h => !1\n\nfn i => 1.5 & 2\n\nfn j => \"a\" + \"b\", 1 + 2.5, 3 < 4.5\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Cannot apply `+` to `int` and `bool`",
+                "Cannot apply `-` to `str` and `str`",
+                "Cannot apply `!` to `int`",
+                "Cannot apply `&` to `float` and `int`"
+            ]
+        );
+    }
+
+    #[test]
+    fn equality_needs_one_type() {
+        let source = "fn f => true == 1\n\nfn g => \"a\" != 'a'\n\nfn h => 1 == 1.0, 'a' == 'b', (1..3):collect == (2..4):collect\n\nfn same (a, b) => a == b\n\nfn i => same(1, \"one\")\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Cannot apply `==` to `bool` and `int`",
+                "Cannot apply `!=` to `str` and `char`",
+                "Mismatched types: expected `int`, found `str`"
+            ]
+        );
+    }
+
+    #[test]
+    fn signatures_show_the_checked_types() {
+        let source = "type Point {\n    x int\n    fn sum (d int) => self.x + d\n}\n\nfn square (x int) int => x * x\n\nfn half (x float) => x / 2\n\nfn main => println(square(2))\n";
+        assert_eq!(
+            signatures(source),
+            [
+                "fn square (x int) int",
+                "fn half (x float) float",
+                "fn main ()",
+                "fn Point.sum (d int) int"
+            ]
+        );
+    }
+
+    #[test]
+    fn unannotated_parameters_are_generic() {
+        let source = "fn double (x) => x * 2\n\nfn concat (a, b) => a + b\n\nfn id (x) => x\n\nfn less (a, b) => a < b\n\nfn main => {\n    let s str = id(\"s\")\n    double(1.5) + double(2) + id(3)\n}\n";
+        assert_eq!(
+            signatures(source),
+            [
+                "fn double<A> (x A) A where A: Num",
+                "fn concat<A> (a A, b A) A where A: Add",
+                "fn id<A> (x A) A",
+                "fn less<A> (a A, b A) bool where A: Ord",
+                "fn main float"
+            ]
+        );
+    }
+
+    #[test]
+    fn called_parameters_are_functions() {
+        let source = "fn apply (f, x) => f(x)\n\nfn twice (f, x) => f(f(x))\n\nfn inc (x int) => x + 1\n\nfn main => apply(inc, 1) + twice(inc, 2)\n";
+        assert_eq!(
+            signatures(source),
+            [
+                "fn apply<A, B> (f fn(A) B, x A) B",
+                "fn twice<A> (f fn(A) A, x A) A",
+                "fn inc (x int) int",
+                "fn main int"
+            ]
+        );
+    }
+
+    #[test]
+    fn calls_that_never_return_fit_anywhere() {
+        let source = "fn spin => spin()\n\nfn forever (x) => forever(x)\n\nfn pick (0) => spin()\nfn pick (n int) => n\n\nfn main => {\n    let n int = spin()\n    let s str = forever(1)\n    let m int = pick(2) + n\n}\n";
+        assert!(errors(source).is_empty(), "{:?}", errors(source));
+        assert_eq!(
+            signatures(source)[..4],
+            [
+                "fn spin !",
+                "fn forever<A> (x A) !",
+                "fn pick (0) !",
+                "fn pick (n int) int"
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_calls_check_their_constraints() {
+        let source = "fn double (x) => x * 2\n\nfn less (a, b) => a < b\n\nfn twice (f, x) => f(f(x))\n\nfn inc (x int) => x + 1\n\nfn main => {\n    double(\"a\")\n    less(true, false)\n    let n str = twice(inc, 1)\n}\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Type `str` does not satisfy `Num`",
+                "Type `bool` does not satisfy `Ord`",
+                "Mismatched types: expected `str`, found `int`"
+            ]
+        );
+    }
+
+    #[test]
+    fn variants_must_all_be_handled() {
+        let source = "type Shape = Circle(float) | Rect(float, float) | Empty\n\ntype Tree =\n    | Leaf\n    | Node(Tree, int, Tree)\n\nfn area (Circle(r)) => 3.0 * r * r\nfn area (Rect(w, h)) => w * h\n\nfn depth (Leaf) => 0\nfn depth (Node(Leaf, _, right)) => 1 + depth(right)\n\nfn name (shape Shape) => do shape {\n    Circle(_) => \"circle\"\n    Empty => \"empty\"\n}\n";
+        assert_eq!(
+            errors(source),
+            [
+                "Non-exhaustive patterns: `area(Empty)` not covered",
+                "Non-exhaustive patterns: `depth(Node(Node(_, _, _), _, _))` not covered",
+                "Non-exhaustive patterns: `Rect(_, _)` not covered"
+            ]
+        );
+    }
+
+    #[test]
+    fn handled_variants_are_exhaustive() {
+        let source = "type Shape = Circle(float) | Rect(float, float) | Empty\n\nfn area (Circle(r)) => 3.0 * r * r\nfn area (Rect(w, h)) => w * h\nfn area (Empty) => 0.0\n\nfn name (shape Shape) => do shape {\n    Circle(_) => \"circle\"\n    _ => \"other\"\n}\n";
+        assert_eq!(errors(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn every_clause_a_value_selects_takes_the_argument() {
+        let source = "type O<T> = Some(T) | None\n\nfn get (Some(x)) => x + 1\nfn get (None) => 0\n\nfn main => get(Some(\"x\"))\n";
+        assert_eq!(
+            errors(source),
+            ["Mismatched types: expected `O<int>`, found `O<str>`"]
+        );
+    }
+
+    #[test]
+    fn type_arguments_must_agree() {
+        let source = "type O<T> = Some(T) | None\n\nfn ints (o O<int>) => o\n\nfn any (o O) => o\n\nfn main => {\n    any(Some(\"x\"))\n    ints(Some(\"x\"))\n}\n";
+        assert_eq!(
+            errors(source),
+            ["Mismatched types: expected `O<int>`, found `O<str>`"]
+        );
+    }
+
+    #[test]
+    fn piped_methods_need_a_receiver_of_their_type() {
+        let source =
+            "type P {\n    x int\n\n    fn get => self.x\n}\n\nfn main => println(1:get)\n";
+        assert_eq!(errors(source), ["Type `int` has no field or method `get`"]);
+
+        let source = "type P {\n    x int\n\n    fn get => self.x\n}\n\nfn read (p) => p:get\n";
+        assert_eq!(signatures(source)[0], "fn read (p P) int");
+    }
+}
@@ -0,0 +1,191 @@
+//! Exhaustiveness of function clauses and `do` branches.
+//!
+//! The parameters of the clauses form a matrix with one row per clause.
+//! A row of patterns that no clause matches is searched for column by
+//! column: when the patterns in a column name every variant of a type, each
+//! variant is tried in turn against the rows that can match it; otherwise
+//! only the rows that match anything in that column are left.
+
+use std::{collections::HashMap, fmt::Display, iter};
+
+use itertools::Itertools;
+
+use crate::parser::ast::{
+    expr::literal::Literal,
+    function_parameter::FunctionParameter,
+    type_definition::{TypeDefinition, VariantDefinition},
+};
+
+/// A pattern reduced to what exhaustiveness depends on.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Matches anything of the expected type.
+    Any,
+    /// Matches values built with `Constructor` whose parts match the fields.
+    Constructor(Constructor, Vec<Pattern>),
+    /// Matches a single value of a type with too many to list.
+    Literal,
+}
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any | Self::Literal => write!(f, "_"),
+            Self::Constructor(Constructor::Variant(name), fields) if fields.is_empty() => {
+                write!(f, "{}", name)
+            }
+            Self::Constructor(Constructor::Variant(name), fields) => {
+                write!(f, "{}({})", name, fields.iter().join(", "))
+            }
+            Self::Constructor(Constructor::Bool(value), _) => write!(f, "{}", value),
+            Self::Constructor(Constructor::Empty, _) => write!(f, "[]"),
+            Self::Constructor(Constructor::Cons, fields) => {
+                write!(f, "{}", fields.iter().join(":"))
+            }
+        }
+    }
+}
+
+/// One of a closed set of ways to build a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constructor {
+    Variant(String),
+    Bool(bool),
+    /// `[]`
+    Empty,
+    /// `head:tail`
+    Cons,
+}
+
+/// The sum types of a module, to enumerate the variants of each.
+pub struct Coverage<'a, 'm> {
+    pub types: &'a HashMap<String, &'m TypeDefinition>,
+    /// Every variant, with the name of its type.
+    pub variants: &'a HashMap<String, (String, &'m VariantDefinition)>,
+}
+impl Coverage<'_, '_> {
+    /// Arguments that none of `rows` match, one per column, if there are any.
+    pub fn uncovered(
+        &self,
+        rows: &[Vec<&FunctionParameter>],
+        width: usize,
+    ) -> Option<Vec<Pattern>> {
+        let matrix = rows
+            .iter()
+            .map(|row| row.iter().map(|param| pattern(param)).collect_vec())
+            .collect_vec();
+        self.missing(&matrix, width)
+    }
+
+    fn missing(&self, matrix: &[Vec<Pattern>], width: usize) -> Option<Vec<Pattern>> {
+        if width == 0 {
+            return matrix.is_empty().then(Vec::new);
+        }
+
+        let heads = matrix
+            .iter()
+            .filter_map(|row| match &row[0] {
+                Pattern::Constructor(constructor, _) => Some(constructor.clone()),
+                _ => None,
+            })
+            .collect_vec();
+        let siblings = heads
+            .first()
+            .map(|constructor| self.siblings(constructor))
+            .unwrap_or_default();
+        let unused = siblings
+            .iter()
+            .find(|(constructor, _)| !heads.contains(constructor));
+
+        // Every constructor is listed, so whatever is missing is further in.
+        if !siblings.is_empty() && unused.is_none() {
+            return siblings.into_iter().find_map(|(constructor, arity)| {
+                let specialized = matrix
+                    .iter()
+                    .filter_map(|row| specialize(row, &constructor, arity))
+                    .collect_vec();
+                let mut fields = self.missing(&specialized, arity + width - 1)?;
+                let rest = fields.split_off(arity);
+                Some(
+                    iter::once(Pattern::Constructor(constructor, fields))
+                        .chain(rest)
+                        .collect(),
+                )
+            });
+        }
+
+        let defaults = matrix
+            .iter()
+            .filter(|row| matches!(row[0], Pattern::Any))
+            .map(|row| row[1..].to_vec())
+            .collect_vec();
+        let rest = self.missing(&defaults, width - 1)?;
+        let head = match unused {
+            Some((constructor, arity)) => {
+                Pattern::Constructor(constructor.clone(), vec![Pattern::Any; *arity])
+            }
+            None => Pattern::Any,
+        };
+        Some(iter::once(head).chain(rest).collect())
+    }
+
+    /// Every constructor of the type `constructor` builds, with its number
+    /// of fields.
+    fn siblings(&self, constructor: &Constructor) -> Vec<(Constructor, usize)> {
+        match constructor {
+            Constructor::Variant(name) => self
+                .variants
+                .get(name)
+                .and_then(|(ty, _)| self.types.get(ty))
+                .map(|ty| {
+                    ty.variants
+                        .iter()
+                        .map(|variant| {
+                            let name = Constructor::Variant(variant.name.to_string());
+                            (name, variant.fields.len())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            Constructor::Bool(_) => {
+                vec![(Constructor::Bool(false), 0), (Constructor::Bool(true), 0)]
+            }
+            Constructor::Empty | Constructor::Cons => {
+                vec![(Constructor::Empty, 0), (Constructor::Cons, 2)]
+            }
+        }
+    }
+}
+
+/// `row` with its first pattern replaced by the fields it requires of a value
+/// built with `constructor`, or `None` if it cannot match such a value.
+fn specialize(row: &[Pattern], constructor: &Constructor, arity: usize) -> Option<Vec<Pattern>> {
+    let fields = match &row[0] {
+        Pattern::Any => vec![Pattern::Any; arity],
+        Pattern::Constructor(head, fields) if head == constructor => fields.clone(),
+        _ => return None,
+    };
+    Some(fields.into_iter().chain(row[1..].iter().cloned()).collect())
+}
+
+/// Type annotations are checked statically, so they do not narrow a pattern.
+fn pattern(param: &FunctionParameter) -> Pattern {
+    match param {
+        FunctionParameter::NamedAndTyped { .. }
+        | FunctionParameter::NamedDynamic { .. }
+        | FunctionParameter::Anonymous { .. }
+        | FunctionParameter::Wildcard { .. } => Pattern::Any,
+        FunctionParameter::Literal {
+            literal: Literal::Bool(value),
+            ..
+        } => Pattern::Constructor(Constructor::Bool(*value), vec![]),
+        FunctionParameter::Literal { .. } => Pattern::Literal,
+        FunctionParameter::Cons { .. } => {
+            Pattern::Constructor(Constructor::Cons, vec![Pattern::Any, Pattern::Any])
+        }
+        FunctionParameter::Empty { .. } => Pattern::Constructor(Constructor::Empty, vec![]),
+        FunctionParameter::Variant { name, fields, .. } => Pattern::Constructor(
+            Constructor::Variant(name.to_string()),
+            fields.iter().map(pattern).collect(),
+        ),
+    }
+}
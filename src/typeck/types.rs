@@ -0,0 +1,285 @@
+use std::{collections::HashMap, fmt::Display};
+
+use itertools::Itertools;
+
+use crate::{
+    interp::builtins::Builtin,
+    parser::ast::{expr::operator::Operator, type_expr::TypeExpr},
+};
+
+/// The static type of an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Unit,
+    /// The result of a call that never returns, such as to a function that
+    /// only calls itself. Fits wherever a value is expected.
+    Never,
+    Int,
+    Float,
+    Char,
+    Str,
+    Bool,
+    /// A user-defined `type`, either a record or a sum type, with the type
+    /// arguments of a generic one. No arguments stand for unknown ones.
+    Named(String, Vec<Type>),
+    /// A type parameter of the `type` or `fn` being checked. Only equal to
+    /// itself there; uses of the definition substitute it.
+    Param(String),
+    /// `T[]`
+    Array(Box<Type>),
+    /// `T*`
+    Generator(Box<Type>),
+    /// A function, constructor, builtin or bound method.
+    Callable(Callee),
+    /// Any function taking `params` and returning `ret`, as inferred for a
+    /// parameter that is called.
+    Function {
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+    /// A type still being inferred, see the [module docs](super).
+    Var(usize),
+    /// Could be anything at runtime; checks against it always pass.
+    Unknown,
+}
+
+/// What calling a [`Type::Callable`] runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Callee {
+    Function(String),
+    Constructor(String),
+    /// A variant of a sum type with fields.
+    Variant(String),
+    Builtin(Builtin),
+    Method {
+        ty: String,
+        name: String,
+        /// The type arguments of the receiver.
+        args: Vec<Type>,
+    },
+}
+
+/// What a type variable must support, from how an unannotated parameter is
+/// used. Checked once the variable is instantiated with a concrete type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// Arithmetic other than `+` and mixing with numbers: `int` or `float`.
+    Num,
+    /// `+`: a number or a `str`.
+    Add,
+    /// `<`, `<=`, `>` and `>=`.
+    Ord,
+    /// `&`, `|` and `^`: `int` or `bool`.
+    Bits,
+    /// Iteration.
+    Seq,
+}
+impl Constraint {
+    /// The constraint an operand of `operator` must satisfy, if any.
+    pub fn of(operator: &Operator) -> Option<Self> {
+        match operator {
+            Operator::Add => Some(Self::Add),
+            Operator::Subtract
+            | Operator::Multiply
+            | Operator::Divide
+            | Operator::Mod
+            | Operator::Pow => Some(Self::Num),
+            Operator::Greater | Operator::Lesser | Operator::GreaterEq | Operator::LesserEq => {
+                Some(Self::Ord)
+            }
+            Operator::BitAnd | Operator::BitOr | Operator::BitXor => Some(Self::Bits),
+            _ => None,
+        }
+    }
+
+    pub fn admits(self, ty: &Type) -> bool {
+        match (self, ty) {
+            (_, Type::Unknown) => true,
+            (Self::Num, Type::Int | Type::Float) => true,
+            (Self::Add, Type::Int | Type::Float | Type::Str) => true,
+            (Self::Ord, Type::Int | Type::Float | Type::Char | Type::Str) => true,
+            (Self::Bits, Type::Int | Type::Bool) => true,
+            (Self::Seq, ty) => ty.element().is_some(),
+            _ => false,
+        }
+    }
+
+    /// The types that satisfy this constraint, for diagnostics.
+    pub fn members(self) -> &'static str {
+        match self {
+            Self::Num => "`int` and `float`",
+            Self::Add => "`int`, `float` and `str`",
+            Self::Ord => "`int`, `float`, `char` and `str`",
+            Self::Bits => "`int` and `bool`",
+            Self::Seq => "`str`, arrays and generators",
+        }
+    }
+}
+impl Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Num => "Num",
+            Self::Add => "Add",
+            Self::Ord => "Ord",
+            Self::Bits => "Bits",
+            Self::Seq => "Seq",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Type {
+    pub fn from_expr(ty: &TypeExpr) -> Self {
+        match ty {
+            TypeExpr::Native { name, .. } => match name.as_str() {
+                "int" => Self::Int,
+                "float" => Self::Float,
+                "char" => Self::Char,
+                "str" => Self::Str,
+                "bool" => Self::Bool,
+                _ => Self::Named(name.clone(), vec![]),
+            },
+            TypeExpr::Named { name, args, .. } => {
+                Self::Named(name.clone(), args.iter().map(Self::from_expr).collect())
+            }
+            TypeExpr::Param { name, .. } => Self::Param(name.clone()),
+            TypeExpr::Array { element, .. } => Self::Array(Box::new(Self::from_expr(element))),
+            TypeExpr::Generator { element, .. } => {
+                Self::Generator(Box::new(Self::from_expr(element)))
+            }
+        }
+    }
+
+    /// `self` with each type parameter named in `args` replaced by its
+    /// argument.
+    pub fn substitute(&self, args: &HashMap<String, Type>) -> Type {
+        match self {
+            Self::Param(name) => args.get(name).cloned().unwrap_or_else(|| self.clone()),
+            Self::Named(name, params) => Self::Named(
+                name.clone(),
+                params.iter().map(|param| param.substitute(args)).collect(),
+            ),
+            Self::Array(element) => Self::Array(Box::new(element.substitute(args))),
+            Self::Generator(element) => Self::Generator(Box::new(element.substitute(args))),
+            Self::Function { params, ret } => Self::Function {
+                params: params.iter().map(|param| param.substitute(args)).collect(),
+                ret: Box::new(ret.substitute(args)),
+            },
+            ty => ty.clone(),
+        }
+    }
+
+    /// The names of the type parameters `self` mentions.
+    pub fn params(&self) -> Vec<String> {
+        match self {
+            Self::Param(name) => vec![name.clone()],
+            Self::Named(_, args) => args.iter().flat_map(Self::params).collect(),
+            Self::Array(element) | Self::Generator(element) => element.params(),
+            Self::Function { params, ret } => params
+                .iter()
+                .chain([&**ret])
+                .flat_map(Self::params)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Whether checks against this type always pass.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown | Self::Var(_))
+    }
+
+    pub fn is_var(&self) -> bool {
+        matches!(self, Self::Var(_))
+    }
+
+    /// The element type of a sequence: strings hold `char`s, ranges `int`s.
+    /// `None` for types that cannot be iterated.
+    pub fn element(&self) -> Option<Type> {
+        match self {
+            Self::Str => Some(Self::Char),
+            Self::Array(element) | Self::Generator(element) => Some(*element.clone()),
+            Self::Unknown | Self::Var(_) => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of this type passes an annotation of type `expected`,
+    /// as checked by the interpreter.
+    pub fn conforms(&self, expected: &Type) -> bool {
+        match (self, expected) {
+            (actual, expected) if actual.is_unknown() || expected.is_unknown() => true,
+            (Self::Never, _) => true,
+            (Self::Array(actual), Self::Array(expected)) => actual.conforms(expected),
+            (Self::Named(actual, args), Self::Named(expected, params)) => {
+                actual == expected
+                    && args.len() == params.len()
+                    && args
+                        .iter()
+                        .zip(params)
+                        .all(|(arg, param)| arg.conforms(param))
+            }
+            // Which clause a function runs depends on the values it is given.
+            (Self::Callable(_), Self::Function { .. })
+            | (Self::Function { .. }, Self::Callable(_)) => true,
+            (
+                Self::Function { params, ret },
+                Self::Function {
+                    params: expected,
+                    ret: expected_ret,
+                },
+            ) => {
+                params.len() == expected.len()
+                    && expected
+                        .iter()
+                        .zip(params)
+                        .all(|(expected, param)| expected.conforms(param))
+                    && ret.conforms(expected_ret)
+            }
+            // Anything that can be iterated can be consumed lazily.
+            (actual, Self::Generator(expected)) => actual
+                .element()
+                .is_some_and(|element| element.conforms(expected)),
+            (actual, expected) => actual == expected,
+        }
+    }
+
+    /// The common type of several values, `Unknown` if they differ. Values
+    /// that are never produced have no say.
+    pub fn join<I: IntoIterator<Item = Type>>(types: I) -> Type {
+        let mut types = types.into_iter().peekable();
+        let Some(first) = types.peek().cloned() else {
+            return Self::Unit;
+        };
+        let mut types = types.filter(|ty| *ty != Self::Never);
+        let Some(first) = types.next() else {
+            return first;
+        };
+        match types.all(|ty| ty == first) {
+            true => first,
+            false => Self::Unknown,
+        }
+    }
+}
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Never => write!(f, "!"),
+            Self::Int => write!(f, "int"),
+            Self::Float => write!(f, "float"),
+            Self::Char => write!(f, "char"),
+            Self::Str => write!(f, "str"),
+            Self::Bool => write!(f, "bool"),
+            Self::Named(name, args) if !args.is_empty() => {
+                write!(f, "{}<{}>", name, args.iter().join(", "))
+            }
+            Self::Named(name, _) | Self::Param(name) => write!(f, "{}", name),
+            Self::Array(element) => write!(f, "{}[]", element),
+            Self::Generator(element) => write!(f, "{}*", element),
+            Self::Callable(_) => write!(f, "fn"),
+            Self::Function { params, ret } => write!(f, "fn({}) {}", params.iter().join(", "), ret),
+            Self::Var(_) | Self::Unknown => write!(f, "_"),
+        }
+    }
+}